@@ -3,7 +3,10 @@ locals {
     "Accounts",
     "Block Rewards",
     "Blocks",
+    "Events",
     "Instructions",
+    "Native Transfers",
+    "Stake Actions",
     "Token Transfers",
     "Tokens",
     "Transactions"
@@ -9,4 +9,7 @@ while true; do
     gcloud storage mv output/tokens/*.jsonl gs://solana_tokens/
     gcloud storage mv output/token_transfers/*.jsonl gs://solana_token_transfers/
     gcloud storage mv output/accounts/*.jsonl gs://solana_accounts/
+    gcloud storage mv output/events/*.jsonl gs://solana_events/
+    gcloud storage mv output/native_transfers/*.jsonl gs://solana_native_transfers/
+    gcloud storage mv output/stake_actions/*.jsonl gs://solana_stake_actions/
 done
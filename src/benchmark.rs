@@ -15,16 +15,11 @@ use blockchain_etl_indexer::source::config::RequestConfig;
 ///
 /// NOTE: for a closer estimate, we could measure the size of every block in the period. But for now, this is probably close enough.
 #[allow(non_snake_case)]
-pub async fn get_blockchain_throughput(
-    request_builder: reqwest::RequestBuilder,
-    time_in_minutes: u32,
-) -> u64 {
+pub async fn get_blockchain_throughput(request_config: RequestConfig, time_in_minutes: u32) -> u64 {
     // our measurement periods are in minutes, but currently std::time::Duration doesn't have a `from_minutes` function.
     let time_in_seconds: u64 = (time_in_minutes as u64) * 60;
     let period = time::Duration::from_secs(time_in_seconds);
 
-    let request_config = RequestConfig::ReqBldr(request_builder);
-
     let block_height_start =
         blockchain_generic::call_getBlockHeight(request_config.try_clone().unwrap(), None).await;
     thread::sleep(period);
@@ -42,7 +37,7 @@ pub async fn get_blockchain_throughput(
                     .await;
 
             if let Some(_parsed_block) = block_in_period.result {
-                parsed_block = Some(_parsed_block);
+                parsed_block = Some(_parsed_block.block);
                 break;
             }
         }
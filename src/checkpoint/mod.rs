@@ -0,0 +1,304 @@
+//! A crash-safe record of the indexing status of each slot, used to resume a terminated run
+//! exactly where it left off.
+//!
+//! The store is an append-only log with one `<slot> <status>` line per status change.  When
+//! the store is opened, the log is replayed (the last status of a slot wins) and compacted into
+//! one `<first slot>-<last slot> <status>` line per run of consecutive slots sharing a status.
+//! A line torn by a crash is ignored.
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use log::{info, warn};
+
+use crate::config::Config;
+
+pub mod tests;
+
+/// The default location of the checkpoint log.
+const DEFAULT_PATH: &str = "./checkpoints.log";
+
+/// The indexing status of a slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlotStatus {
+    /// the slot was sent to the workers
+    Queued,
+    /// the slot's block was retrieved
+    Fetched,
+    /// the slot's records were sent to the stream queue
+    Published,
+    /// the slot has no block
+    Skipped,
+    /// the slot could not be indexed
+    Failed,
+}
+
+impl SlotStatus {
+    /// Whether nothing remains to be done for the slot.
+    pub fn is_done(self) -> bool {
+        matches!(self, SlotStatus::Published | SlotStatus::Skipped)
+    }
+}
+
+impl fmt::Display for SlotStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            SlotStatus::Queued => "queued",
+            SlotStatus::Fetched => "fetched",
+            SlotStatus::Published => "published",
+            SlotStatus::Skipped => "skipped",
+            SlotStatus::Failed => "failed",
+        };
+        f.write_str(status)
+    }
+}
+
+impl FromStr for SlotStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(SlotStatus::Queued),
+            "fetched" => Ok(SlotStatus::Fetched),
+            "published" => Ok(SlotStatus::Published),
+            "skipped" => Ok(SlotStatus::Skipped),
+            "failed" => Ok(SlotStatus::Failed),
+            _ => Err(format!("unknown slot status {:?}", s)),
+        }
+    }
+}
+
+/// The statuses of the slots, stored as runs of consecutive slots sharing a status so that long
+/// indexed ranges take little memory.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SlotRuns {
+    /// the first slot of each run, mapped to the last slot of the run and their status
+    runs: BTreeMap<u64, (u64, SlotStatus)>,
+}
+
+impl SlotRuns {
+    /// Returns the status of the slot, if it was ever recorded.
+    pub fn get(&self, slot: u64) -> Option<SlotStatus> {
+        self.runs
+            .range(..=slot)
+            .next_back()
+            .filter(|(_, (last, _))| *last >= slot)
+            .map(|(_, (_, status))| *status)
+    }
+
+    /// Sets the status of the slot.
+    pub fn set(&mut self, slot: u64, status: SlotStatus) {
+        self.set_range(slot, slot, status);
+    }
+
+    /// Sets the status of the slots from first to last (inclusive), splitting and merging runs
+    /// as needed.
+    pub fn set_range(&mut self, first: u64, last: u64, status: SlotStatus) {
+        // the runs overlapping the range are cut down to the parts outside of it
+        let overlapping: Vec<(u64, u64, SlotStatus)> = self
+            .runs
+            .range(..=last)
+            .rev()
+            .take_while(|(_, (run_last, _))| *run_last >= first)
+            .map(|(run_first, (run_last, run_status))| (*run_first, *run_last, *run_status))
+            .collect();
+        for (run_first, run_last, run_status) in overlapping {
+            self.runs.remove(&run_first);
+            if run_first < first {
+                self.runs.insert(run_first, (first - 1, run_status));
+            }
+            if run_last > last {
+                self.runs.insert(last + 1, (run_last, run_status));
+            }
+        }
+
+        // then the range is merged with its neighbours if they share its status
+        let (mut first, mut last) = (first, last);
+        if let Some((&previous_first, &(previous_last, previous_status))) =
+            self.runs.range(..first).next_back()
+        {
+            if previous_last + 1 == first && previous_status == status {
+                self.runs.remove(&previous_first);
+                first = previous_first;
+            }
+        }
+        if let Some(next) = last.checked_add(1) {
+            if let Some(&(next_last, next_status)) = self.runs.get(&next) {
+                if next_status == status {
+                    self.runs.remove(&next);
+                    last = next_last;
+                }
+            }
+        }
+        self.runs.insert(first, (last, status));
+    }
+
+    /// Iterates over the runs as `(first slot, last slot, status)`, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (u64, u64, SlotStatus)> + '_ {
+        self.runs
+            .iter()
+            .map(|(first, (last, status))| (*first, *last, *status))
+    }
+}
+
+/// parses a `<slot> <status>` or `<first slot>-<last slot> <status>` line of the log.
+fn parse_line(line: &str) -> Option<(u64, u64, SlotStatus)> {
+    let (slots, status) = line.split_once(' ')?;
+    let status = status.parse().ok()?;
+    let (first, last) = match slots.split_once('-') {
+        Some((first, last)) => (first.parse().ok()?, last.parse().ok()?),
+        None => {
+            let slot = slots.parse().ok()?;
+            (slot, slot)
+        }
+    };
+    (first <= last).then_some((first, last, status))
+}
+
+struct CheckpointLog {
+    file: File,
+    slots: SlotRuns,
+}
+
+/// A durable, append-only log of the status of each slot, shared by all of the workers.
+pub struct CheckpointStore {
+    path: PathBuf,
+    inner: Mutex<CheckpointLog>,
+}
+
+impl CheckpointStore {
+    /// Opens the checkpoint log at the path, creating it if it doesn't exist, and compacts it.
+    pub fn open(path: impl AsRef<Path>) -> CheckpointStore {
+        let path = path.as_ref().to_path_buf();
+        let mut slots = SlotRuns::default();
+        if let Ok(file) = File::open(&path) {
+            for line in BufReader::new(file).lines() {
+                let line = line.expect("checkpoint log is readable");
+                match parse_line(&line) {
+                    Some((first, last, status)) => slots.set_range(first, last, status),
+                    None => warn!("Ignoring the invalid checkpoint log line {:?}", line),
+                }
+            }
+        }
+
+        // rewrite the compacted log next to the old one, then swap it in
+        let compacted_path = path.with_extension("compacted");
+        {
+            let mut writer = BufWriter::new(
+                File::create(&compacted_path).expect("checkpoint log directory is writable"),
+            );
+            for (first, last, status) in slots.iter() {
+                if first == last {
+                    writeln!(writer, "{} {}", first, status)
+                } else {
+                    writeln!(writer, "{}-{} {}", first, last, status)
+                }
+                .expect("checkpoint log is writable");
+            }
+            let file = writer.into_inner().expect("checkpoint log is writable");
+            file.sync_all().expect("checkpoint log is writable");
+        }
+        fs::rename(&compacted_path, &path).expect("checkpoint log is writable");
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("checkpoint log is writable");
+        info!("Opened the checkpoint log at {:?}", path);
+
+        CheckpointStore {
+            path,
+            inner: Mutex::new(CheckpointLog { file, slots }),
+        }
+    }
+
+    /// Opens the checkpoint log at the `CHECKPOINT_PATH` setting.
+    pub fn from_config(config: &Config) -> CheckpointStore {
+        // env:checkpoint_path = Where the status of each indexed slot is recorded
+        let path = config.checkpoint_path.as_deref().unwrap_or(DEFAULT_PATH);
+        CheckpointStore::open(path)
+    }
+
+    /// The path of the checkpoint log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Records the status of the slot.  A slot's final status (published, skipped or failed) is
+    /// flushed to disk before returning, so it survives a crash.
+    pub fn record(&self, slot: u64, status: SlotStatus) {
+        let mut inner = self.inner.lock().unwrap();
+        // a single write per line, so a crash can only tear the last line
+        inner
+            .file
+            .write_all(format!("{} {}\n", slot, status).as_bytes())
+            .expect("checkpoint log is writable");
+        if status.is_done() || status == SlotStatus::Failed {
+            inner.file.sync_data().expect("checkpoint log is writable");
+        }
+        inner.slots.set(slot, status);
+    }
+
+    /// Flushes every recorded status to disk, e.g. before shutting down.
+    pub fn flush(&self) {
+        self.inner
+            .lock()
+            .unwrap()
+            .file
+            .sync_all()
+            .expect("checkpoint log is writable");
+    }
+
+    /// Returns the last recorded status of the slot.
+    pub fn status(&self, slot: u64) -> Option<SlotStatus> {
+        self.inner.lock().unwrap().slots.get(slot)
+    }
+
+    /// Whether the slot was already published or skipped.
+    pub fn is_done(&self, slot: u64) -> bool {
+        self.status(slot).is_some_and(SlotStatus::is_done)
+    }
+
+    /// Whether every one of the slots was published, skipped or failed, i.e. none of them is
+    /// still in flight or left to index.
+    pub fn all_settled<I>(&self, slots: I) -> bool
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let inner = self.inner.lock().unwrap();
+        slots.into_iter().all(|slot| {
+            inner
+                .slots
+                .get(slot)
+                .is_some_and(|status| status.is_done() || status == SlotStatus::Failed)
+        })
+    }
+
+    /// Returns the produced slots (slots with a block) that weren't published, in order.  A
+    /// produced slot recorded as skipped is a gap too, e.g. when the node hadn't caught up yet.
+    pub fn gaps<I>(&self, produced_slots: I) -> Vec<u64>
+    where
+        I: IntoIterator<Item = u64>,
+    {
+        let inner = self.inner.lock().unwrap();
+        produced_slots
+            .into_iter()
+            .filter(|slot| inner.slots.get(*slot) != Some(SlotStatus::Published))
+            .collect()
+    }
+
+    /// Filters out the slots that are already done, so that a terminated run resumes exactly
+    /// where it left off, whatever order the slots were indexed in.
+    pub fn remaining<I>(self: Arc<Self>, slots: I) -> impl Iterator<Item = u64>
+    where
+        I: Iterator<Item = u64>,
+    {
+        slots.filter(move |slot| !self.is_done(*slot))
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::Arc};
+
+    use crate::checkpoint::{CheckpointStore, SlotRuns, SlotStatus};
+
+    #[test]
+    fn test_slot_runs_merge_and_split() {
+        let mut runs = SlotRuns::default();
+        runs.set(10, SlotStatus::Published);
+        runs.set(12, SlotStatus::Published);
+        runs.set(11, SlotStatus::Skipped);
+        runs.set(11, SlotStatus::Published);
+
+        assert_eq!(
+            runs.iter().collect::<Vec<_>>(),
+            vec![(10, 12, SlotStatus::Published)]
+        );
+
+        runs.set(11, SlotStatus::Failed);
+        assert_eq!(
+            runs.iter().collect::<Vec<_>>(),
+            vec![
+                (10, 10, SlotStatus::Published),
+                (11, 11, SlotStatus::Failed),
+                (12, 12, SlotStatus::Published)
+            ]
+        );
+        assert_eq!(runs.get(9), None);
+        assert_eq!(runs.get(11), Some(SlotStatus::Failed));
+    }
+
+    #[test]
+    fn test_slot_runs_set_range_over_runs() {
+        let mut runs = SlotRuns::default();
+        runs.set_range(0, 9, SlotStatus::Published);
+        runs.set_range(20, 29, SlotStatus::Skipped);
+        runs.set_range(5, 24, SlotStatus::Queued);
+
+        assert_eq!(
+            runs.iter().collect::<Vec<_>>(),
+            vec![
+                (0, 4, SlotStatus::Published),
+                (5, 24, SlotStatus::Queued),
+                (25, 29, SlotStatus::Skipped)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_store_resumes_exactly() {
+        let path = std::env::temp_dir().join("test_checkpoint_store_resumes_exactly.log");
+        let _ = fs::remove_file(&path);
+
+        {
+            let checkpoints = CheckpointStore::open(&path);
+            for slot in 100..110 {
+                checkpoints.record(slot, SlotStatus::Queued);
+            }
+            // the workers finish the slots out of order, and one is still in flight
+            for slot in [101, 100, 103, 104, 106, 105, 108, 109] {
+                checkpoints.record(slot, SlotStatus::Fetched);
+                checkpoints.record(slot, SlotStatus::Published);
+            }
+            checkpoints.record(102, SlotStatus::Skipped);
+            checkpoints.record(107, SlotStatus::Fetched);
+        }
+
+        let checkpoints = Arc::new(CheckpointStore::open(&path));
+        assert_eq!(checkpoints.status(102), Some(SlotStatus::Skipped));
+        assert_eq!(checkpoints.status(107), Some(SlotStatus::Fetched));
+        assert_eq!(
+            checkpoints.clone().remaining(95..112).collect::<Vec<_>>(),
+            vec![95, 96, 97, 98, 99, 107, 110, 111]
+        );
+        assert_eq!(
+            checkpoints.remaining((95..112).rev()).collect::<Vec<_>>(),
+            vec![111, 110, 107, 99, 98, 97, 96, 95]
+        );
+
+        // the log was compacted into runs
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "100-101 published\n102 skipped\n103-106 published\n107 fetched\n108-109 published\n"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_store_gaps() {
+        let path = std::env::temp_dir().join("test_checkpoint_store_gaps.log");
+        let _ = fs::remove_file(&path);
+
+        let checkpoints = CheckpointStore::open(&path);
+        checkpoints.record(10, SlotStatus::Published);
+        checkpoints.record(11, SlotStatus::Skipped);
+        checkpoints.record(12, SlotStatus::Failed);
+        checkpoints.record(13, SlotStatus::Published);
+        checkpoints.record(14, SlotStatus::Fetched);
+
+        // slot 11 produced a block although the node returned none when it was indexed
+        assert_eq!(
+            checkpoints.gaps([10, 11, 12, 13, 14, 16]),
+            vec![11, 12, 14, 16]
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_store_ignores_torn_lines() {
+        let path = std::env::temp_dir().join("test_checkpoint_store_ignores_torn_lines.log");
+        fs::write(&path, "5 published\n6 publ").unwrap();
+
+        let checkpoints = CheckpointStore::open(&path);
+        checkpoints.record(7, SlotStatus::Published);
+
+        assert!(checkpoints.is_done(5));
+        assert!(!checkpoints.is_done(6));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "5 published\n7 published\n"
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,389 @@
+//! The typed configuration of the indexer, validated up front.
+//!
+//! The settings are read from a TOML file (by default `./config.toml`, when it exists), whose
+//! keys are the lowercase names of the environment variables.  The environment, including a
+//! `.env` file if there is one, overrides the file.  Once validated, the config is passed to the
+//! constructor of each module, which takes its own settings from it.
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub mod tests;
+
+/// The default location of the config file, read when it exists.
+const DEFAULT_PATH: &str = "./config.toml";
+/// What the secrets are replaced with when the config is shown.
+const REDACTED: &str = "<redacted>";
+
+/// An error reading or validating the configuration.
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("can't read the config file {path:?}: {reason}")]
+    File { path: PathBuf, reason: String },
+    #[error("the configuration is invalid:\n{}", list_problems(.0))]
+    Invalid(Vec<String>),
+}
+
+/// formats the problems of an invalid configuration, one per line.
+fn list_problems(problems: &[String]) -> String {
+    problems
+        .iter()
+        .map(|problem| format!("  - {}", problem))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Declares the settings of the config with the environment variable overriding each of them.
+/// The key of a setting in the config file is the lowercase name of its environment variable.
+macro_rules! settings {
+    ($($(#[doc = $doc:expr])* $field:ident: $type:ty = $var:literal,)*) => {
+        /// The settings of the indexer.  A setting is `None` when neither the config file nor the
+        /// environment set it, in which case its module falls back on its default.
+        #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        pub struct Config {
+            $(
+                $(#[doc = $doc])*
+                #[serde(default, skip_serializing_if = "Option::is_none")]
+                pub $field: Option<$type>,
+            )*
+        }
+
+        impl Config {
+            /// The environment variables of the settings.
+            pub const VARIABLES: &'static [&'static str] = &[$($var),*];
+
+            /// Overrides the settings with the variables found by `lookup`, returning the
+            /// variables that can't be parsed.
+            pub fn override_with<F>(&mut self, lookup: F) -> Vec<String>
+            where
+                F: Fn(&str) -> Option<String>,
+            {
+                let mut problems = Vec::new();
+                $(
+                    if let Some(value) = lookup($var) {
+                        match value.parse::<$type>() {
+                            Ok(parsed) => self.$field = Some(parsed),
+                            Err(e) => problems.push(format!("{} {:?} is invalid: {}", $var, value, e)),
+                        }
+                    }
+                )*
+                problems
+            }
+        }
+    };
+}
+
+settings! {
+    /// the address of the RPC node
+    endpoint: String = "ENDPOINT",
+    /// the address of the RPC node to switch to when the endpoint keeps failing
+    fallback_endpoint: String = "FALLBACK_ENDPOINT",
+    /// the number of attempts at a request before switching to the fallback endpoint
+    rpc_fallback_threshold: u32 = "RPC_FALLBACK_THRESHOLD",
+    /// the API key sent with each request to the RPC node
+    gcp_api_key: String = "GCP_API_KEY",
+    num_extractor_threads: usize = "NUM_EXTRACTOR_THREADS",
+    enable_metrics: bool = "ENABLE_METRICS",
+    metrics_address: String = "METRICS_ADDRESS",
+    metrics_port: u16 = "METRICS_PORT",
+    accounts_min_context_slot: bool = "ACCOUNTS_MIN_CONTEXT_SLOT",
+    accounts_from_create_instruction: bool = "ACCOUNTS_FROM_CREATE_INSTRUCTION",
+    /// either `created` or `writable`
+    account_discovery: String = "ACCOUNT_DISCOVERY",
+    checkpoint_path: String = "CHECKPOINT_PATH",
+    dead_letter_path: String = "DEAD_LETTER_PATH",
+    lease_dir: String = "LEASE_DIR",
+    lease_owner: String = "LEASE_OWNER",
+    lease_chunk_size: u64 = "LEASE_CHUNK_SIZE",
+    lease_ttl: u64 = "LEASE_TTL",
+    ordered_publishing: bool = "ORDERED_PUBLISHING",
+    ordered_publishing_capacity: usize = "ORDERED_PUBLISHING_CAPACITY",
+    ordered_publishing_timeout: u64 = "ORDERED_PUBLISHING_TIMEOUT",
+    /// either `skip` or `abort`
+    ordered_publishing_on_timeout: String = "ORDERED_PUBLISHING_ON_TIMEOUT",
+    transform_workers: usize = "TRANSFORM_WORKERS",
+    publish_workers: usize = "PUBLISH_WORKERS",
+    max_in_flight_bytes: usize = "MAX_IN_FLIGHT_BYTES",
+    shutdown_timeout: u64 = "SHUTDOWN_TIMEOUT",
+    token_cache_capacity: usize = "TOKEN_CACHE_CAPACITY",
+    token_cache_ttl: u64 = "TOKEN_CACHE_TTL",
+    token_cache_path: String = "TOKEN_CACHE_PATH",
+    anchor_idl_dir: String = "ANCHOR_IDL_DIR",
+    rabbitmq_address: String = "RABBITMQ_ADDRESS",
+    rabbitmq_port: u16 = "RABBITMQ_PORT",
+    rabbitmq_user: String = "RABBITMQ_USER",
+    rabbitmq_password: String = "RABBITMQ_PASSWORD",
+    gcp_credentials_json_path: String = "GCP_CREDENTIALS_JSON_PATH",
+    bigtable_cred: String = "BIGTABLE_CRED",
+    queue_name: String = "QUEUE_NAME",
+    queue_name_blocks: String = "QUEUE_NAME_BLOCKS",
+    queue_name_block_rewards: String = "QUEUE_NAME_BLOCK_REWARDS",
+    queue_name_transactions: String = "QUEUE_NAME_TRANSACTIONS",
+    queue_name_instructions: String = "QUEUE_NAME_INSTRUCTIONS",
+    queue_name_token_transfers: String = "QUEUE_NAME_TOKEN_TRANSFERS",
+    queue_name_tokens: String = "QUEUE_NAME_TOKENS",
+    queue_name_accounts: String = "QUEUE_NAME_ACCOUNTS",
+    queue_name_events: String = "QUEUE_NAME_EVENTS",
+    queue_name_native_transfers: String = "QUEUE_NAME_NATIVE_TRANSFERS",
+    queue_name_stake_actions: String = "QUEUE_NAME_STAKE_ACTIONS",
+}
+
+/// keeps the scheme, host and port of the url, and replaces the rest of it, if any, since RPC
+/// providers take the API key in the user info, the path or the query string.
+fn redact_endpoint(endpoint: &str) -> String {
+    let url = match reqwest::Url::parse(endpoint) {
+        Ok(url) => url,
+        Err(_) => return REDACTED.to_string(),
+    };
+    let mut address = format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default());
+    if let Some(port) = url.port() {
+        address.push_str(&format!(":{}", port));
+    }
+    let has_secrets = !url.username().is_empty()
+        || url.password().is_some()
+        || url.path() != "/"
+        || url.query().is_some()
+        || url.fragment().is_some();
+    if has_secrets {
+        format!("{}/{}", address, REDACTED)
+    } else {
+        endpoint.to_string()
+    }
+}
+
+/// reports a required setting that isn't set.  The reason, if any, starts with a space.
+fn require<T>(problems: &mut Vec<String>, value: &Option<T>, var: &str, reason: &str) {
+    if value.is_none() {
+        problems.push(format!(
+            "{} (`{}` in the config file) is required{}",
+            var,
+            var.to_lowercase(),
+            reason
+        ));
+    }
+}
+
+/// reports a setting that isn't one of the allowed values.
+fn require_one_of(problems: &mut Vec<String>, value: &Option<String>, var: &str, allowed: &[&str]) {
+    if let Some(value) = value {
+        if !allowed.contains(&value.as_str()) {
+            problems.push(format!(
+                "{} should be one of {:?}, not {:?}",
+                var, allowed, value
+            ));
+        }
+    }
+}
+
+/// reports a setting that is zero.
+fn require_positive<T: Default + PartialEq>(
+    problems: &mut Vec<String>,
+    value: &Option<T>,
+    var: &str,
+) {
+    if value.as_ref().is_some_and(|value| *value == T::default()) {
+        problems.push(format!("{} should be positive", var));
+    }
+}
+
+impl Config {
+    /// Parses the settings of a TOML config file.
+    pub fn from_toml(contents: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Reads the config file at the path.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let file_error = |reason: String| ConfigError::File {
+            path: path.to_path_buf(),
+            reason,
+        };
+        let contents = fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        Config::from_toml(&contents).map_err(|e| file_error(e.to_string()))
+    }
+
+    /// Loads the config file at the path, or at the `CONFIG_PATH` environment variable, or at
+    /// `./config.toml` if it exists, then overrides it with the environment.  A `.env` file is
+    /// loaded into the environment first, if there is one.
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        // a missing .env is fine, the settings can come from the config file or the environment
+        let _ = dotenvy::dotenv();
+
+        // env:config_path = The path of the TOML config file
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(|| dotenvy::var("CONFIG_PATH").ok().map(PathBuf::from));
+        let mut config = match path {
+            Some(path) => Config::from_file(path)?,
+            None if Path::new(DEFAULT_PATH).exists() => Config::from_file(DEFAULT_PATH)?,
+            None => Config::default(),
+        };
+
+        let problems = config.override_with(|var| dotenvy::var(var).ok());
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid(problems));
+        }
+        Ok(config)
+    }
+
+    /// Returns every problem with the settings, e.g. a required setting that isn't set.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        require(&mut problems, &self.endpoint, "ENDPOINT", "");
+        require(
+            &mut problems,
+            &self.num_extractor_threads,
+            "NUM_EXTRACTOR_THREADS",
+            "",
+        );
+        require(&mut problems, &self.enable_metrics, "ENABLE_METRICS", "");
+        if self.enable_metrics == Some(true) {
+            let reason = " when ENABLE_METRICS is true";
+            require(
+                &mut problems,
+                &self.metrics_address,
+                "METRICS_ADDRESS",
+                reason,
+            );
+            require(&mut problems, &self.metrics_port, "METRICS_PORT", reason);
+        }
+
+        #[cfg(any(feature = "RABBITMQ_STREAM", feature = "RABBITMQ_CLASSIC"))]
+        {
+            let reason = " to publish to RabbitMQ";
+            require(
+                &mut problems,
+                &self.rabbitmq_address,
+                "RABBITMQ_ADDRESS",
+                reason,
+            );
+            require(&mut problems, &self.rabbitmq_port, "RABBITMQ_PORT", reason);
+        }
+        #[cfg(feature = "RABBITMQ_CLASSIC")]
+        {
+            let reason = " to publish to RabbitMQ";
+            require(&mut problems, &self.rabbitmq_user, "RABBITMQ_USER", reason);
+            require(
+                &mut problems,
+                &self.rabbitmq_password,
+                "RABBITMQ_PASSWORD",
+                reason,
+            );
+        }
+        #[cfg(feature = "GOOGLE_PUBSUB")]
+        require(
+            &mut problems,
+            &self.gcp_credentials_json_path,
+            "GCP_CREDENTIALS_JSON_PATH",
+            " to publish to Google Pub/Sub",
+        );
+        #[cfg(feature = "SOLANA_BIGTABLE")]
+        require(
+            &mut problems,
+            &self.bigtable_cred,
+            "BIGTABLE_CRED",
+            " to read blocks from BigTable",
+        );
+        #[cfg(feature = "SINGLE_PUBLISHER")]
+        require(
+            &mut problems,
+            &self.queue_name,
+            "QUEUE_NAME",
+            " with a single publisher",
+        );
+        #[cfg(feature = "SEPARATE_PUBLISHERS")]
+        for (queue_name, var) in [
+            (&self.queue_name_blocks, "QUEUE_NAME_BLOCKS"),
+            (&self.queue_name_block_rewards, "QUEUE_NAME_BLOCK_REWARDS"),
+            (&self.queue_name_transactions, "QUEUE_NAME_TRANSACTIONS"),
+            (&self.queue_name_instructions, "QUEUE_NAME_INSTRUCTIONS"),
+            (
+                &self.queue_name_token_transfers,
+                "QUEUE_NAME_TOKEN_TRANSFERS",
+            ),
+            (&self.queue_name_tokens, "QUEUE_NAME_TOKENS"),
+            (&self.queue_name_accounts, "QUEUE_NAME_ACCOUNTS"),
+            (&self.queue_name_events, "QUEUE_NAME_EVENTS"),
+            (
+                &self.queue_name_native_transfers,
+                "QUEUE_NAME_NATIVE_TRANSFERS",
+            ),
+            (&self.queue_name_stake_actions, "QUEUE_NAME_STAKE_ACTIONS"),
+        ] {
+            require(&mut problems, queue_name, var, " with separate publishers");
+        }
+
+        require_one_of(
+            &mut problems,
+            &self.account_discovery,
+            "ACCOUNT_DISCOVERY",
+            &["created", "writable"],
+        );
+        require_one_of(
+            &mut problems,
+            &self.ordered_publishing_on_timeout,
+            "ORDERED_PUBLISHING_ON_TIMEOUT",
+            &["skip", "abort"],
+        );
+        require_positive(
+            &mut problems,
+            &self.num_extractor_threads,
+            "NUM_EXTRACTOR_THREADS",
+        );
+        require_positive(&mut problems, &self.transform_workers, "TRANSFORM_WORKERS");
+        require_positive(&mut problems, &self.publish_workers, "PUBLISH_WORKERS");
+        require_positive(
+            &mut problems,
+            &self.ordered_publishing_capacity,
+            "ORDERED_PUBLISHING_CAPACITY",
+        );
+        require_positive(
+            &mut problems,
+            &self.ordered_publishing_timeout,
+            "ORDERED_PUBLISHING_TIMEOUT",
+        );
+        require_positive(
+            &mut problems,
+            &self.max_in_flight_bytes,
+            "MAX_IN_FLIGHT_BYTES",
+        );
+        require_positive(&mut problems, &self.lease_chunk_size, "LEASE_CHUNK_SIZE");
+        require_positive(&mut problems, &self.lease_ttl, "LEASE_TTL");
+        problems
+    }
+
+    /// Fails with every problem with the settings.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Returns the settings with the passwords and keys replaced, so that they can be shown.  Only
+    /// the scheme, host and port of an endpoint are kept, since RPC providers take the API key in
+    /// the rest of it.
+    pub fn redacted(&self) -> Config {
+        let redact = |secret: &Option<String>| secret.as_ref().map(|_| REDACTED.to_string());
+        Config {
+            endpoint: self.endpoint.as_deref().map(redact_endpoint),
+            fallback_endpoint: self.fallback_endpoint.as_deref().map(redact_endpoint),
+            rabbitmq_password: redact(&self.rabbitmq_password),
+            gcp_api_key: redact(&self.gcp_api_key),
+            ..self.clone()
+        }
+    }
+
+    /// Formats the settings as a TOML config file.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("config is serializable")
+    }
+}
@@ -0,0 +1,182 @@
+//! A sink for the slots that fail to be indexed, so that one bad slot doesn't stop the run.
+//!
+//! Each failure is appended to a JSON lines file as a dead letter, with the slot, the stage that
+//! failed, the error, and the stage's input when it is more than the slot itself (i.e. the
+//! fetched block of a slot that failed to be converted or transformed).  The `retry-dead-letters`
+//! subcommand takes the dead letters out of the file, replays the fetched blocks, and fetches the
+//! other slots again.
+use std::{
+    any::Any,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+pub mod tests;
+
+/// The default location of the dead-letter file.
+const DEFAULT_PATH: &str = "./dead_letters.jsonl";
+
+/// The stage of the pipeline a slot failed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FailedStage {
+    /// requesting the block and its accounts
+    Fetch,
+    /// converting the fetched block to protobuf
+    Convert,
+    /// transforming the block into the records of each table
+    Transform,
+}
+
+/// A slot that failed to be indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub slot: u64,
+    pub stage: FailedStage,
+    pub error: String,
+    /// the input of the stage that failed (base64 encoded), e.g. the fetched block for the
+    /// convert and transform stages.  `None` when the slot is the only input.
+    pub input: Option<String>,
+    /// when the slot failed, as a unix timestamp
+    pub failed_at: i64,
+}
+
+impl DeadLetter {
+    pub fn new(slot: u64, stage: FailedStage, error: String, input: Option<&[u8]>) -> DeadLetter {
+        DeadLetter {
+            slot,
+            stage,
+            error,
+            input: input.map(|input| BASE64.encode(input)),
+            failed_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Decodes the input of the stage that failed.
+    pub fn decoded_input(&self) -> Option<Vec<u8>> {
+        self.input
+            .as_ref()
+            .map(|input| BASE64.decode(input).expect("dead letter input is base64"))
+    }
+}
+
+/// Returns the message of a caught panic.
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Returns the path of the dead-letter file from the `DEAD_LETTER_PATH` setting.
+pub fn path_from_config(config: &Config) -> PathBuf {
+    // env:dead_letter_path = Where the slots that fail to be indexed are recorded
+    PathBuf::from(config.dead_letter_path.as_deref().unwrap_or(DEFAULT_PATH))
+}
+
+/// An append-only file of dead letters, shared by all of the workers.
+pub struct DeadLetterSink {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl DeadLetterSink {
+    /// Opens the dead-letter file at the path, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> DeadLetterSink {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .expect("dead-letter file is writable");
+        DeadLetterSink {
+            path,
+            file: Mutex::new(file),
+        }
+    }
+
+    /// Opens the dead-letter file at the `DEAD_LETTER_PATH` setting.
+    pub fn from_config(config: &Config) -> DeadLetterSink {
+        DeadLetterSink::open(path_from_config(config))
+    }
+
+    /// The path of the dead-letter file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends the dead letter to the file, and flushes it to disk.
+    pub fn record(&self, letter: &DeadLetter) {
+        error!(
+            "slot {} failed in the {:?} stage, recorded in {:?}: {}",
+            letter.slot, letter.stage, self.path, letter.error
+        );
+        let mut line = serde_json::to_string(letter).expect("dead letter is serializable");
+        line.push('\n');
+        let mut file = self.file.lock().unwrap();
+        // a single write per line, so a crash can only tear the last line
+        file.write_all(line.as_bytes())
+            .expect("dead-letter file is writable");
+        file.sync_data().expect("dead-letter file is writable");
+    }
+
+    /// Takes the dead letters out of the file at the path, appending them to the file of the
+    /// letters retried before (with a `.retried` extension) so that the slots failing again are
+    /// recorded in a new file.
+    pub fn take(path: impl AsRef<Path>) -> Vec<DeadLetter> {
+        let path = path.as_ref();
+        let mut contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                info!("No dead letters at {:?}", path);
+                return Vec::new();
+            }
+        };
+        let letters: Vec<DeadLetter> = contents
+            .lines()
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(letter) => Some(letter),
+                Err(e) => {
+                    warn!("Ignoring the invalid dead letter {:?}: {}", line, e);
+                    None
+                }
+            })
+            .collect();
+
+        // a line torn by a crash is ended, so that it doesn't run into the next letters
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            contents.push('\n');
+        }
+        let retried_path = path.with_extension("retried");
+        let mut retried = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&retried_path)
+            .expect("retried dead-letter file is writable");
+        retried
+            .write_all(contents.as_bytes())
+            .expect("retried dead-letter file is writable");
+        retried
+            .sync_data()
+            .expect("retried dead-letter file is writable");
+        fs::remove_file(path).expect("dead-letter file is writable");
+        info!(
+            "Took {} dead letters from {:?}, moved to {:?}",
+            letters.len(),
+            path,
+            retried_path
+        );
+        letters
+    }
+}
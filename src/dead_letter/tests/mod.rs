@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::dead_letter::{panic_message, DeadLetter, DeadLetterSink, FailedStage};
+
+    #[test]
+    fn test_dead_letters_are_taken_for_retry() {
+        let path = std::env::temp_dir().join("test_dead_letters_are_taken_for_retry.jsonl");
+        let retried_path = path.with_extension("retried");
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&retried_path);
+
+        let fetch_letter = DeadLetter::new(
+            10,
+            FailedStage::Fetch,
+            "unexpected RPC error".to_string(),
+            None,
+        );
+        let transform_letter = DeadLetter::new(
+            11,
+            FailedStage::Transform,
+            "Unexpected token data shape".to_string(),
+            Some(&[1, 2, 3]),
+        );
+        {
+            let sink = DeadLetterSink::open(&path);
+            sink.record(&fetch_letter);
+            sink.record(&transform_letter);
+        }
+        // a line torn by a crash is ignored
+        let mut contents = fs::read_to_string(&path).unwrap();
+        contents.push_str("{\"slot\":12,\"sta");
+        fs::write(&path, contents).unwrap();
+
+        let letters = DeadLetterSink::take(&path);
+        assert_eq!(letters, vec![fetch_letter.clone(), transform_letter]);
+        assert_eq!(letters[1].decoded_input(), Some(vec![1, 2, 3]));
+        // the file is moved aside, so that the retried slots failing again start a new one
+        assert!(!path.exists());
+        assert!(DeadLetterSink::take(&path).is_empty());
+
+        // the letters of a later retry are appended to the ones retried before
+        DeadLetterSink::open(&path).record(&fetch_letter);
+        assert_eq!(DeadLetterSink::take(&path), vec![fetch_letter.clone()]);
+        let retried = fs::read_to_string(&retried_path).unwrap();
+        // the two letters and the torn line of the first retry, then the letter of the second
+        assert_eq!(retried.lines().count(), 4);
+        assert_eq!(
+            retried.lines().last().unwrap(),
+            serde_json::to_string(&fetch_letter).unwrap()
+        );
+
+        fs::remove_file(retried_path).unwrap();
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let panic = std::panic::catch_unwind(|| panic!("Unexpected token data shape")).unwrap_err();
+        assert_eq!(panic_message(&*panic), "Unexpected token data shape");
+
+        let slot = 5;
+        let panic = std::panic::catch_unwind(|| panic!("unknown reward type at slot {}", slot))
+            .unwrap_err();
+        assert_eq!(panic_message(&*panic), "unknown reward type at slot 5");
+    }
+}
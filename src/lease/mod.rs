@@ -0,0 +1,471 @@
+//! Slot-range leases, so that several instances can index a range together without splitting it
+//! by hand.
+//!
+//! The range is split into fixed-size chunks.  An instance leases a chunk from a store shared by
+//! the whole fleet, renews the lease while it indexes the chunk, and marks the chunk complete
+//! once every slot of it is settled, so each chunk is indexed by a single instance.  A lease that
+//! isn't renewed before it expires, e.g. because its instance died, is taken over by another
+//! instance, which indexes the chunk again.  An instance that loses its lease stops indexing the
+//! chunk.
+//!
+//! The store only records which chunks are complete.  The progress within a chunk is kept in the
+//! checkpoint log of the instance indexing it, so a chunk that is taken over is indexed again from
+//! its start, and the gaps of a range are only found in the checkpoint log of each instance.
+//! Recording the settled slots in the store would let another instance resume the chunk instead.
+//!
+//! The store is behind the [`LeaseStore`] trait.  [`DirLeaseStore`] keeps the leases as files in
+//! a directory, which works for the instances of a single machine and for instances sharing a
+//! network file system (e.g. an NFS mount).
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+
+use crate::{config::Config, shutdown::Shutdown};
+
+pub mod tests;
+
+/// The default location of the lease directory.
+const DEFAULT_DIR: &str = "./leases";
+/// The default number of slots in a chunk.
+const DEFAULT_CHUNK_SIZE: u64 = 10_000;
+/// The default number of seconds a lease lasts without being renewed.
+const DEFAULT_TTL_SECS: u64 = 300;
+
+/// A chunk of consecutive slots, from `start` (inclusive) to `end` (exclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Chunk {
+    /// The slots of the chunk.
+    pub fn slots(&self) -> Range<u64> {
+        self.start..self.end
+    }
+}
+
+impl fmt::Display for Chunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// The state of a chunk after trying to lease it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaseState {
+    /// the chunk is leased to this instance
+    Acquired,
+    /// the chunk is leased to another instance, and the lease hasn't expired
+    HeldByOther,
+    /// the chunk was already indexed
+    Completed,
+}
+
+/// A store of chunk leases, shared by every instance indexing the range.  The store may be
+/// remote, so every method fails with the I/O error of reaching it.
+pub trait LeaseStore: Send + Sync {
+    /// Leases the chunk to the owner for `ttl`, unless another owner holds an unexpired lease on
+    /// it or it is complete.  The owner's own lease is renewed.
+    fn try_acquire(&self, chunk: Chunk, owner: &str, ttl: Duration) -> io::Result<LeaseState>;
+
+    /// Extends the owner's lease on the chunk by `ttl`.  Returns false if the owner lost the
+    /// lease to another owner.
+    fn renew(&self, chunk: Chunk, owner: &str, ttl: Duration) -> io::Result<bool>;
+
+    /// Marks the chunk leased to the owner as complete.  Returns false if the owner lost the
+    /// lease to another owner.
+    fn complete(&self, chunk: Chunk, owner: &str) -> io::Result<bool>;
+}
+
+/// A lease store in a directory, with one file per lease.
+///
+/// The lease on a chunk is a `<chunk>.<generation>.lease` file holding its owner and expiry, and
+/// the latest generation is the current lease.  Creating a lease file only succeeds if it
+/// doesn't exist yet (by hard-linking a temporary file to it), so when several instances take
+/// over an expired lease at once, a single one creates the next generation.  A complete chunk
+/// has a `<chunk>.done` file.
+pub struct DirLeaseStore {
+    dir: PathBuf,
+}
+
+/// A lease read from a lease file.
+struct LeaseFile {
+    generation: u64,
+    owner: String,
+    /// when the lease expires, in milliseconds since the unix epoch
+    expires_at: i64,
+}
+
+impl DirLeaseStore {
+    /// Opens the lease directory at the path, creating it if it doesn't exist.
+    pub fn open(dir: impl AsRef<Path>) -> DirLeaseStore {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).expect("lease directory is writable");
+        DirLeaseStore { dir }
+    }
+
+    /// The path of the lease directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn lease_path(&self, chunk: Chunk, generation: u64) -> PathBuf {
+        self.dir.join(format!("{}.{}.lease", chunk, generation))
+    }
+
+    fn done_path(&self, chunk: Chunk) -> PathBuf {
+        self.dir.join(format!("{}.done", chunk))
+    }
+
+    /// Returns the current lease on the chunk, i.e. its latest generation.
+    fn current(&self, chunk: Chunk) -> io::Result<Option<LeaseFile>> {
+        let mut current = None;
+        for generation in 0.. {
+            let path = self.lease_path(chunk, generation);
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => break,
+                Err(e) => return Err(e),
+            };
+            // the contents are written before the file is linked in, so they are always whole
+            let (owner, expires_at) = contents
+                .trim_end()
+                .rsplit_once(' ')
+                .and_then(|(owner, expires_at)| Some((owner, expires_at.parse().ok()?)))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{:?} doesn't hold an owner and expiry", path),
+                    )
+                })?;
+            current = Some(LeaseFile {
+                generation,
+                owner: owner.to_string(),
+                expires_at,
+            });
+        }
+        Ok(current)
+    }
+
+    /// Writes the file at the path in one step, through a temporary file.  When `exclusive`,
+    /// fails with `AlreadyExists` if the file exists, otherwise the file is replaced.
+    fn write_file(
+        &self,
+        path: &Path,
+        contents: &str,
+        owner: &str,
+        exclusive: bool,
+    ) -> io::Result<()> {
+        let temporary_path = path.with_extension(format!("{}.tmp", sanitize(owner)));
+        {
+            let mut file = File::create(&temporary_path)?;
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()?;
+        }
+        let written = if exclusive {
+            fs::hard_link(&temporary_path, path)
+        } else {
+            fs::rename(&temporary_path, path)
+        };
+        let _ = fs::remove_file(&temporary_path);
+        written
+    }
+
+    /// Creates the given generation of the lease on the chunk for the owner.  Returns false if
+    /// another instance created it first.
+    fn create_lease(
+        &self,
+        chunk: Chunk,
+        generation: u64,
+        owner: &str,
+        ttl: Duration,
+    ) -> io::Result<bool> {
+        let contents = format!("{} {}\n", owner, expiry(ttl));
+        match self.write_file(&self.lease_path(chunk, generation), &contents, owner, true) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl LeaseStore for DirLeaseStore {
+    fn try_acquire(&self, chunk: Chunk, owner: &str, ttl: Duration) -> io::Result<LeaseState> {
+        if self.done_path(chunk).try_exists()? {
+            return Ok(LeaseState::Completed);
+        }
+        let generation = match self.current(chunk)? {
+            None => 0,
+            Some(lease) if lease.owner == owner => {
+                return match self.renew(chunk, owner, ttl)? {
+                    true => Ok(LeaseState::Acquired),
+                    false => Ok(LeaseState::HeldByOther),
+                };
+            }
+            Some(lease) if lease.expires_at > now() => return Ok(LeaseState::HeldByOther),
+            Some(lease) => {
+                warn!(
+                    "The lease of {} on chunk {} expired, taking it over",
+                    lease.owner, chunk
+                );
+                lease.generation + 1
+            }
+        };
+        if !self.create_lease(chunk, generation, owner, ttl)? {
+            return Ok(LeaseState::HeldByOther);
+        }
+        // the chunk may have been completed, and its lease files removed, since it was checked
+        if self.done_path(chunk).try_exists()? {
+            let _ = fs::remove_file(self.lease_path(chunk, generation));
+            return Ok(LeaseState::Completed);
+        }
+        Ok(LeaseState::Acquired)
+    }
+
+    fn renew(&self, chunk: Chunk, owner: &str, ttl: Duration) -> io::Result<bool> {
+        let generation = match self.current(chunk)? {
+            Some(lease) if lease.owner == owner => lease.generation,
+            _ => return Ok(false),
+        };
+        let contents = format!("{} {}\n", owner, expiry(ttl));
+        self.write_file(&self.lease_path(chunk, generation), &contents, owner, false)?;
+        // another instance may have taken the lease over while it was being renewed
+        Ok(self
+            .current(chunk)?
+            .is_some_and(|lease| lease.generation == generation))
+    }
+
+    fn complete(&self, chunk: Chunk, owner: &str) -> io::Result<bool> {
+        let generation = match self.current(chunk)? {
+            Some(lease) if lease.owner == owner => lease.generation,
+            _ => return Ok(false),
+        };
+        self.write_file(
+            &self.done_path(chunk),
+            &format!("{}\n", owner),
+            owner,
+            false,
+        )?;
+        for generation in 0..=generation {
+            let _ = fs::remove_file(self.lease_path(chunk, generation));
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the current time, in milliseconds since the unix epoch.
+fn now() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Returns when a lease taken now for `ttl` expires, in milliseconds since the unix epoch.
+fn expiry(ttl: Duration) -> i64 {
+    now() + ttl.as_millis() as i64
+}
+
+/// Keeps the characters of the owner that are safe in a file name.
+fn sanitize(owner: &str) -> String {
+    owner
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The next chunk for an instance to index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NextChunk {
+    /// the chunk is leased to this instance
+    Leased(Chunk),
+    /// every chunk left is leased to other instances, whose leases may yet expire
+    Wait,
+    /// every chunk of the range is complete
+    Done,
+}
+
+/// Leases the chunks of a range for this instance.
+#[derive(Clone)]
+pub struct LeaseCoordinator {
+    store: Arc<dyn LeaseStore>,
+    /// identifies this instance in the leases, so it must be unique across the fleet
+    owner: String,
+    chunk_size: u64,
+    ttl: Duration,
+}
+
+impl LeaseCoordinator {
+    pub fn new(
+        store: Arc<dyn LeaseStore>,
+        owner: String,
+        chunk_size: u64,
+        ttl: Duration,
+    ) -> LeaseCoordinator {
+        assert!(chunk_size > 0, "LEASE_CHUNK_SIZE should be positive");
+        LeaseCoordinator {
+            store,
+            owner,
+            chunk_size,
+            ttl,
+        }
+    }
+
+    /// Creates a coordinator with the `LEASE_DIR`, `LEASE_OWNER`, `LEASE_CHUNK_SIZE` and
+    /// `LEASE_TTL` settings.
+    pub fn from_config(config: &Config) -> LeaseCoordinator {
+        // env:lease_dir = The directory of the slot-range leases, shared by the instances
+        let dir = config.lease_dir.as_deref().unwrap_or(DEFAULT_DIR);
+        // env:lease_owner = The name of this instance in the leases, unique across the instances
+        let owner = config.lease_owner.clone().unwrap_or_else(default_owner);
+        // env:lease_chunk_size = The number of slots leased at once
+        let chunk_size = config.lease_chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        // env:lease_ttl = The number of seconds a lease lasts without being renewed
+        let ttl_secs = config.lease_ttl.unwrap_or(DEFAULT_TTL_SECS);
+        let store = DirLeaseStore::open(dir);
+        info!(
+            "Leasing chunks of {} slots from {:?} as {}",
+            chunk_size, dir, owner
+        );
+        LeaseCoordinator::new(
+            Arc::new(store),
+            owner,
+            chunk_size,
+            Duration::from_secs(ttl_secs),
+        )
+    }
+
+    /// The name of this instance in the leases.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// How long to wait before looking for a chunk again, when every chunk left is leased to
+    /// other instances.
+    pub fn poll_interval(&self) -> Duration {
+        self.ttl / 4
+    }
+
+    /// Splits the range into chunks.  The chunks are aligned on the start of the range, so every
+    /// instance given the same range and chunk size agrees on them.
+    pub fn chunks(&self, range: Range<u64>) -> impl Iterator<Item = Chunk> {
+        let chunk_size = self.chunk_size;
+        (range.start..range.end)
+            .step_by(chunk_size as usize)
+            .map(move |start| Chunk {
+                start,
+                end: start.saturating_add(chunk_size).min(range.end),
+            })
+    }
+
+    /// Leases the first chunk of the range that is neither complete nor leased to another
+    /// instance.  A chunk whose lease can't be reached in the store is left for later.
+    pub fn next_chunk(&self, range: Range<u64>) -> NextChunk {
+        let mut next = NextChunk::Done;
+        for chunk in self.chunks(range) {
+            match self.store.try_acquire(chunk, &self.owner, self.ttl) {
+                Ok(LeaseState::Acquired) => {
+                    info!("Leased chunk {}", chunk);
+                    return NextChunk::Leased(chunk);
+                }
+                Ok(LeaseState::HeldByOther) => next = NextChunk::Wait,
+                Ok(LeaseState::Completed) => {}
+                Err(e) => {
+                    warn!("Could not lease chunk {}, trying again later: {}", chunk, e);
+                    next = NextChunk::Wait;
+                }
+            }
+        }
+        next
+    }
+
+    /// Renews the lease on the chunk in the background until the returned task is aborted.  A
+    /// renewal that fails to reach the store is retried on the next tick, until the lease expires.
+    /// Once the lease is lost, `chunk_shutdown` is requested, so the chunk stops being indexed by
+    /// this instance while another one takes it over.
+    pub fn keep_alive(
+        &self,
+        chunk: Chunk,
+        chunk_shutdown: Shutdown,
+    ) -> tokio::task::JoinHandle<()> {
+        let coordinator = self.clone();
+        tokio::task::spawn(async move {
+            let mut renewed_at = Instant::now();
+            loop {
+                tokio::time::sleep(coordinator.ttl / 3).await;
+                let store = coordinator.store.clone();
+                let (owner, ttl) = (coordinator.owner.clone(), coordinator.ttl);
+                let renewal = tokio::task::spawn_blocking(move || store.renew(chunk, &owner, ttl))
+                    .await
+                    .expect("lease renewal doesn't panic");
+                match renewal {
+                    Ok(true) => renewed_at = Instant::now(),
+                    Ok(false) => {
+                        error!(
+                            "Lost the lease on chunk {}, another instance is indexing it again",
+                            chunk
+                        );
+                        chunk_shutdown.request();
+                        return;
+                    }
+                    Err(e) if renewed_at.elapsed() < coordinator.ttl => {
+                        warn!(
+                            "Could not renew the lease on chunk {}, retrying: {}",
+                            chunk, e
+                        );
+                    }
+                    Err(e) => {
+                        error!(
+                            "Could not renew the lease on chunk {} before it expired, another instance may index it again: {}",
+                            chunk, e
+                        );
+                        chunk_shutdown.request();
+                        return;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Marks the chunk as complete.  Returns false if the lease on it was lost, or the store
+    /// couldn't be reached.
+    pub fn complete(&self, chunk: Chunk) -> bool {
+        match self.store.complete(chunk, &self.owner) {
+            Ok(true) => {
+                info!("Completed chunk {}", chunk);
+                true
+            }
+            Ok(false) => {
+                warn!("Lost the lease on chunk {}, not marking it complete", chunk);
+                false
+            }
+            Err(e) => {
+                error!("Could not mark chunk {} complete: {}", chunk, e);
+                false
+            }
+        }
+    }
+}
+
+/// The default name of this instance, when `LEASE_OWNER` isn't set: its host name, as read from
+/// `/etc/hostname`, and process id.
+fn default_owner() -> String {
+    let host = fs::read_to_string("/etc/hostname")
+        .ok()
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| "indexer".to_string());
+    format!("{}-{}", host, std::process::id())
+}
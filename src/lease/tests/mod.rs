@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs, io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use crate::{
+        lease::{Chunk, DirLeaseStore, LeaseCoordinator, LeaseState, LeaseStore, NextChunk},
+        shutdown::Shutdown,
+    };
+
+    fn coordinator(store: &Arc<DirLeaseStore>, owner: &str) -> LeaseCoordinator {
+        LeaseCoordinator::new(
+            store.clone(),
+            owner.to_string(),
+            10,
+            Duration::from_secs(60),
+        )
+    }
+
+    #[test]
+    fn test_range_is_split_into_aligned_chunks() {
+        let dir = std::env::temp_dir().join("test_range_is_split_into_aligned_chunks");
+        let store = Arc::new(DirLeaseStore::open(&dir));
+        let chunks: Vec<Chunk> = coordinator(&store, "a").chunks(5..28).collect();
+        assert_eq!(
+            chunks,
+            vec![
+                Chunk { start: 5, end: 15 },
+                Chunk { start: 15, end: 25 },
+                Chunk { start: 25, end: 28 },
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_instances_cover_the_range_once() {
+        let dir = std::env::temp_dir().join("test_instances_cover_the_range_once");
+        let _ = fs::remove_dir_all(&dir);
+        let store = Arc::new(DirLeaseStore::open(&dir));
+        let (a, b) = (coordinator(&store, "a"), coordinator(&store, "b"));
+
+        let first = Chunk { start: 0, end: 10 };
+        let second = Chunk { start: 10, end: 20 };
+        assert_eq!(a.next_chunk(0..20), NextChunk::Leased(first));
+        assert_eq!(b.next_chunk(0..20), NextChunk::Leased(second));
+        // every chunk left is leased to another instance
+        assert_eq!(a.next_chunk(10..20), NextChunk::Wait);
+
+        assert!(a.complete(first));
+        assert!(
+            !a.complete(second),
+            "only the lease holder completes a chunk"
+        );
+        assert!(b.complete(second));
+        assert_eq!(a.next_chunk(0..20), NextChunk::Done);
+        assert_eq!(b.next_chunk(0..20), NextChunk::Done);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expired_lease_is_taken_over() {
+        let dir = std::env::temp_dir().join("test_expired_lease_is_taken_over");
+        let _ = fs::remove_dir_all(&dir);
+        let store = DirLeaseStore::open(&dir);
+        let chunk = Chunk { start: 0, end: 10 };
+
+        assert_eq!(
+            store.try_acquire(chunk, "dead", Duration::ZERO).unwrap(),
+            LeaseState::Acquired
+        );
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(
+            store
+                .try_acquire(chunk, "alive", Duration::from_secs(60))
+                .unwrap(),
+            LeaseState::Acquired
+        );
+        assert_eq!(
+            store
+                .try_acquire(chunk, "dead", Duration::from_secs(60))
+                .unwrap(),
+            LeaseState::HeldByOther
+        );
+        // the instance that lost the lease finds out when it renews or completes it
+        assert!(!store.renew(chunk, "dead", Duration::from_secs(60)).unwrap());
+        assert!(!store.complete(chunk, "dead").unwrap());
+        assert!(store
+            .renew(chunk, "alive", Duration::from_secs(60))
+            .unwrap());
+        assert!(store.complete(chunk, "alive").unwrap());
+        assert_eq!(
+            store
+                .try_acquire(chunk, "dead", Duration::from_secs(60))
+                .unwrap(),
+            LeaseState::Completed
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// a store whose first `failures` renewals can't reach it.
+    struct UnreachableStore {
+        failures: usize,
+        renewals: AtomicUsize,
+    }
+
+    impl LeaseStore for UnreachableStore {
+        fn try_acquire(&self, _: Chunk, _: &str, _: Duration) -> io::Result<LeaseState> {
+            Ok(LeaseState::Acquired)
+        }
+
+        fn renew(&self, _: Chunk, _: &str, _: Duration) -> io::Result<bool> {
+            if self.renewals.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "store unreachable"));
+            }
+            Ok(true)
+        }
+
+        fn complete(&self, _: Chunk, _: &str) -> io::Result<bool> {
+            Ok(true)
+        }
+    }
+
+    fn keep_alive(
+        store: &Arc<UnreachableStore>,
+        chunk_shutdown: &Shutdown,
+    ) -> tokio::task::JoinHandle<()> {
+        LeaseCoordinator::new(
+            store.clone(),
+            String::from("a"),
+            10,
+            Duration::from_millis(300),
+        )
+        .keep_alive(Chunk { start: 0, end: 10 }, chunk_shutdown.clone())
+    }
+
+    #[tokio::test]
+    async fn test_failed_renewal_is_retried_before_the_lease_expires() {
+        let store = Arc::new(UnreachableStore {
+            failures: 1,
+            renewals: AtomicUsize::new(0),
+        });
+        let chunk_shutdown = Shutdown::new(Duration::from_secs(30));
+        let renewal = keep_alive(&store, &chunk_shutdown);
+        tokio::time::sleep(Duration::from_millis(1_000)).await;
+        assert!(!renewal.is_finished());
+        assert!(!chunk_shutdown.is_requested());
+        assert!(store.renewals.load(Ordering::SeqCst) > 2);
+        renewal.abort();
+    }
+
+    #[tokio::test]
+    async fn test_renewal_gives_up_once_the_lease_expires() {
+        let store = Arc::new(UnreachableStore {
+            failures: usize::MAX,
+            renewals: AtomicUsize::new(0),
+        });
+        let chunk_shutdown = Shutdown::new(Duration::from_secs(30));
+        let renewal = keep_alive(&store, &chunk_shutdown);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!renewal.is_finished(), "the lease hasn't expired yet");
+        assert!(!chunk_shutdown.is_requested());
+        tokio::time::timeout(Duration::from_secs(5), renewal)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(store.renewals.load(Ordering::SeqCst) >= 3);
+        assert!(
+            chunk_shutdown.is_requested(),
+            "the chunk stops being indexed once its lease is lost"
+        );
+    }
+}
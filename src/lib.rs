@@ -1,9 +1,14 @@
 #![doc = include_str!("README.md")]
 
+pub mod checkpoint;
+pub mod config;
 mod constants;
+pub mod dead_letter;
+pub mod lease;
 pub mod metrics;
 pub mod output;
 mod request;
+pub mod shutdown;
 pub mod source;
 
 #[cfg(feature = "SOLANA")]
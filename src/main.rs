@@ -5,18 +5,27 @@ use actix_web_prom::PrometheusMetricsBuilder;
 use clap::{Args, Parser, Subcommand};
 use log::info;
 use std::error::Error;
-use std::fs::{create_dir, read_dir, File};
+use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 mod benchmark;
 pub mod constants;
 mod request;
+use blockchain_etl_indexer::checkpoint::CheckpointStore;
+use blockchain_etl_indexer::config::Config;
+use blockchain_etl_indexer::dead_letter::{self, DeadLetterSink};
+use blockchain_etl_indexer::lease::{LeaseCoordinator, NextChunk};
 use blockchain_etl_indexer::metrics::Metrics;
+use blockchain_etl_indexer::shutdown::Shutdown;
+use blockchain_etl_indexer::source::config::{RequestConfig, RpcFallback};
 
 // Get the config associated with the chosen blockchain.  We should import the config as
 // `blockchain_config` so we can use the blockchain configuration generically.
 #[cfg(feature = "SOLANA")]
+use blockchain_etl_indexer::solana_config::instruction_decoders::InstructionDecoderRegistry;
+#[cfg(feature = "SOLANA")]
 use blockchain_etl_indexer::solana_config::lib as blockchain_config;
 
 #[cfg(feature = "SOLANA_BIGTABLE")]
@@ -38,6 +47,9 @@ static GLOBAL: Jemalloc = Jemalloc;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// The TOML config file, defaults to `CONFIG_PATH` or, if it exists, `./config.toml`
+    #[clap(long, global = true)]
+    config: Option<PathBuf>,
 }
 
 #[derive(Subcommand)]
@@ -48,6 +60,26 @@ enum Commands {
     IndexRange(IndexRangeArgs),
     /// Extract blocks from a list
     IndexList(IndexListArgs),
+    /// Extract the blocks of a range together with other instances, leasing chunks of it from
+    /// the shared lease directory
+    IndexRangeLeased(IndexRangeLeasedArgs),
+    /// Write the slots of a range that produced a block but weren't published to a list
+    FindGaps(FindGapsArgs),
+    /// Extract the blocks of a range that weren't published
+    BackfillGaps(BackfillGapsArgs),
+    /// Extract the blocks of the slots that failed, recorded in the dead-letter file
+    RetryDeadLetters(RetryDeadLettersArgs),
+    /// Inspect the configuration, from the config file and the environment
+    #[command(subcommand)]
+    Config(ConfigCommands),
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective configuration as a config file, with the secrets redacted
+    Show,
+    /// Check the configuration, printing every problem with it
+    Validate,
 }
 
 /// Arguments relating the the indexing of the crypto currency, particularly output,
@@ -79,6 +111,49 @@ struct IndexListArgs {
     list: String,
 }
 
+/// Arguments relating to indexing a range together with other instances, each indexing the
+/// chunks of the range it leases.
+#[derive(Args)]
+struct IndexRangeLeasedArgs {
+    /// OutputType is the object expected to be used to send the data extracted by the Indexer.
+    out: OutputType,
+    /// The slot to begin indexing from
+    start: u64,
+    /// The slot to stop indexing at
+    end: u64,
+}
+
+/// Arguments relating to finding the gaps of an indexed range, i.e. the slots that produced a
+/// block but weren't published.
+#[derive(Args)]
+struct FindGapsArgs {
+    /// The slot to begin checking from
+    start: u64,
+    /// The slot to stop checking at
+    end: u64,
+    /// The path to write the list of missing blocks to, in the format read by `index-list`.
+    list: String,
+}
+
+/// Arguments relating to indexing the gaps of an indexed range, i.e. the slots that produced a
+/// block but weren't published.
+#[derive(Args)]
+struct BackfillGapsArgs {
+    /// OutputType is the object expected to be used to send the data extracted by the Indexer.
+    out: OutputType,
+    /// The slot to begin backfilling from
+    start: u64,
+    /// The slot to stop backfilling at
+    end: u64,
+}
+
+/// Arguments relating to indexing the slots that failed in a previous run.
+#[derive(Args)]
+struct RetryDeadLettersArgs {
+    /// OutputType is the object expected to be used to send the data extracted by the Indexer.
+    out: OutputType,
+}
+
 /// The possible output types for the extracted data
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
 enum OutputType {
@@ -140,64 +215,30 @@ fn read_block_list_csv(file_path: &Path) -> Box<dyn Iterator<Item = u64>> {
     Box::new(values_iter)
 }
 
-/// Opens the directory of indexed block numbers and determine where to pick up from.
-fn pick_up_from_previous_range(
-    start: u64,
-    end: Option<u64>,
-    is_reverse: bool,
-) -> (u64, Option<u64>) {
-    let mut range_start = start;
-    let mut range_end = end;
-    // check for the index logs to pick up from a terminated run.
-    //      - create the directory if it doesn't exist.
-    let indexed_blocks_dir = Path::new("./indexed_blocks/");
-    if indexed_blocks_dir.exists() {
-        let indexed_blocks_files = read_dir(indexed_blocks_dir).unwrap();
-        // the order that the files are iterated through is platform-dependent,
-        // so we treat it as an unsorted list.
-        for file in indexed_blocks_files {
-            let path = file.unwrap().path();
-            let filename = path.file_name().unwrap().to_str().unwrap();
-
-            if is_reverse {
-                let previous_start: u64 = filename.parse().expect("file name is a u64");
-                match range_end {
-                    Some(e) => {
-                        if previous_start <= e {
-                            range_end = Some(previous_start - 1);
-                        }
-                    }
-                    None => {
-                        if previous_start <= range_start {
-                            range_start = previous_start - 1;
-                        }
-                    }
-                }
-            } else {
-                let previous_end: u64 = filename.parse().expect("file name is a u64");
-                if previous_end >= range_start {
-                    range_start = previous_end + 1;
-                    info!("setting range_start to {range_start}");
-                }
-
-                // ensure that we don't continue indexing beyond the range end, if passed in.
-                if let Some(e) = end {
-                    if previous_end >= e {
-                        panic!("This range has already been indexed. Stopping...");
-                    }
-                }
-            }
-        }
-    } else {
-        create_dir(indexed_blocks_dir).expect("filesystem is writable");
+/// Writes a list of u64 values as a CSV, one value per line and without a header.
+fn write_block_list_csv(file_path: &Path, values: &[u64]) {
+    let mut wtr = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_path(file_path)
+        .expect("file path is writable");
+    for value in values {
+        wtr.write_record([value.to_string()])
+            .expect("file is writable");
     }
+    wtr.flush().expect("file is writable");
+}
 
-    (range_start, range_end)
+/// Sets up the shutdown of the indexing on SIGINT (ctrl-c) or SIGTERM.
+fn listen_for_shutdown(config: &Config) -> Shutdown {
+    let shutdown = Shutdown::from_config(config);
+    info!("Press 'CRTL-C' to terminate...");
+    shutdown.listen_for_signals();
+    shutdown
 }
 
 /// Main function for the ETL-Core code.  Performs the following startup-tasks:
 /// - Setup the logging system
-/// - Loads in the .env
+/// - Loads and validates the configuration (config file, .env and environment)
 /// - Set up the RequestBuilder (reuse the same client)
 /// - Setup the Prometheus metrics system
 /// - Setup the stream connection (whether it is pubsub, rabbitmq, etc)
@@ -208,39 +249,64 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Set up the logger
     env_logger::init();
 
-    // Loads the .env file, raises an error otherwise
-    dotenvy::dotenv().expect(".env file is required");
+    let cli = Cli::parse();
+
+    // Loads the config file and the .env file, both optional, under the environment
+    let config = Config::load(cli.config.as_deref()).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2)
+    });
+    match cli.command {
+        Commands::Config(ConfigCommands::Show) => {
+            print!("{}", config.redacted().to_toml());
+            if let Err(e) = config.validate() {
+                eprintln!("{}", e);
+            }
+            return Ok(());
+        }
+        Commands::Config(ConfigCommands::Validate) => {
+            return match config.validate() {
+                Ok(()) => {
+                    println!("The configuration is valid");
+                    Ok(())
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1)
+                }
+            };
+        }
+        _ => {}
+    }
+    config.validate().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2)
+    });
 
     // Set up the RequestBuilder to be used in the ETL-Core code.
     // NOTE: the reqwest docs suggest reusing a single client, rather than using multiple
     // the endpoint and request headers will be the same for every request, so
     // we will clone this request builder, rather than constructing a new one every time.
-    let request_builder = {
-        let endpoint = dotenvy::var("ENDPOINT")
-            .expect("ENDPOINT should exist in .env file")
-            .parse::<String>()
-            .unwrap();
+    let request_config = {
+        let endpoint = config.endpoint.clone().expect("ENDPOINT is validated");
         let connection_timeout = std::time::Duration::from_secs(constants::CONNECTION_TIMEOUT);
         let client_builder = reqwest::Client::builder().connect_timeout(connection_timeout);
 
         let client = client_builder.build().unwrap();
-        let headers = request::get_headers();
-        client.post(endpoint).headers(headers)
+        let headers = request::get_headers(config.gcp_api_key.as_deref());
+        RequestConfig::ReqBldr(
+            client.post(endpoint).headers(headers),
+            RpcFallback::from_config(&config),
+        )
     };
 
-    let cli = Cli::parse();
-
     // env:num_extractor_threads = The number of threads to use for extracting data.
-    let num_extractor_threads = dotenvy::var("NUM_EXTRACTOR_THREADS")
-        .expect("NUM_EXTRACTOR_THREADS should exist in .env file")
-        .parse::<usize>()
-        .unwrap();
+    let num_extractor_threads = config
+        .num_extractor_threads
+        .expect("NUM_EXTRACTOR_THREADS is validated");
 
     // env:enable_metrics = Whether we should be tracking metrics (prometheus connection)
-    let enable_metrics = dotenvy::var("ENABLE_METRICS")
-        .expect("ENABLE_METRICS should exist in .env file")
-        .parse::<bool>()
-        .unwrap();
+    let enable_metrics = config.enable_metrics.expect("ENABLE_METRICS is validated");
 
     // metrics setup
     // - Reads in the metrics address and port from the .env file
@@ -248,15 +314,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let (metrics, srv_handle) = if enable_metrics {
         let (metrics_address, metrics_port) = {
             // env:metrics_address = Address for connecting to the Prometheus server
-            let metrics_address = dotenvy::var("METRICS_ADDRESS")
-                .expect("METRICS_ADDRESS should exist in .env file")
-                .parse::<String>()
-                .unwrap();
+            let metrics_address = config
+                .metrics_address
+                .clone()
+                .expect("METRICS_ADDRESS is validated");
             // env:metrics_port = Port for connecting to the Prometheus server
-            let metrics_port = dotenvy::var("METRICS_PORT")
-                .expect("METRICS_PORT should exist in .env file")
-                .parse::<u16>()
-                .unwrap();
+            let metrics_port = config.metrics_port.expect("METRICS_PORT is validated");
             (metrics_address, metrics_port)
         };
 
@@ -281,6 +344,24 @@ async fn main() -> Result<(), Box<dyn Error>> {
             .registry
             .register(Box::new(failed_request_count.clone()))
             .unwrap();
+        let token_cache_hit_count = prometheus::IntCounter::new(
+            "token_cache_hit_count",
+            "Total number of token metadata lookups served from the token cache",
+        )
+        .unwrap();
+        let token_cache_miss_count = prometheus::IntCounter::new(
+            "token_cache_miss_count",
+            "Total number of token metadata lookups requested from the RPC node",
+        )
+        .unwrap();
+        prometheus
+            .registry
+            .register(Box::new(token_cache_hit_count.clone()))
+            .unwrap();
+        prometheus
+            .registry
+            .register(Box::new(token_cache_miss_count.clone()))
+            .unwrap();
 
         let srv = HttpServer::new(move || App::new().wrap(prometheus.clone()).service(index))
             .bind((metrics_address, metrics_port))?
@@ -293,6 +374,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         let metrics = Metrics {
             request_count,
             failed_request_count,
+            token_cache_hit_count,
+            token_cache_miss_count,
         };
         (Some(metrics), Some(srv_handle))
     } else {
@@ -302,53 +385,62 @@ async fn main() -> Result<(), Box<dyn Error>> {
     match cli.command {
         Commands::Benchmark { time } => {
             info!("Benchmarking blockchain network for {} minutes...", time);
-            let throughput = benchmark::get_blockchain_throughput(request_builder, 1).await;
+            let throughput = benchmark::get_blockchain_throughput(request_config, 1).await;
             info!("Throughput in bytes per second: {}", throughput);
             return Ok(());
         }
+        Commands::Config(_) => unreachable!("the config subcommands return early"),
         Commands::IndexRange(args) => {
             if args.start == 0 && args.end.is_none() && args.reverse {
                 panic!("FATAL: cannot index backwards from genesis");
             }
-            let (start, opt_end) = pick_up_from_previous_range(args.start, args.end, args.reverse);
 
             #[allow(clippy::collapsible_else_if)]
             let indexing_range: Box<dyn Iterator<Item = u64>> = if args.reverse {
-                if let Some(end) = opt_end {
-                    Box::new((start..end).rev())
+                if let Some(end) = args.end {
+                    Box::new((args.start..end).rev())
                 } else {
-                    Box::new((0..start).rev())
+                    Box::new((0..args.start).rev())
                 }
             } else {
-                if let Some(end) = opt_end {
-                    Box::new(start..end)
+                if let Some(end) = args.end {
+                    Box::new(args.start..end)
                 } else {
-                    Box::new(start..)
+                    Box::new(args.start..)
                 }
             };
+            // pick up from a terminated run by skipping the slots that were already indexed
+            let checkpoints = Arc::new(CheckpointStore::from_config(&config));
+            let indexing_range = checkpoints.clone().remaining(indexing_range);
 
             match args.out {
                 OutputType::Stream => {
                     let publisher =
-                        blockchain_etl_indexer::output::publish::StreamPublisher::new().await;
+                        blockchain_etl_indexer::output::publish::StreamPublisher::new(&config)
+                            .await;
 
                     let cur_publisher = publisher.clone();
                     #[cfg(not(feature = "SOLANA_BIGTABLE"))]
                     let bigtable = None;
                     #[cfg(feature = "SOLANA_BIGTABLE")]
                     let bigtable = {
-                        let _bigtable = bigtable::connect_to_bigtable().await;
+                        let _bigtable = bigtable::connect_to_bigtable(&config).await;
                         match _bigtable {
                             Ok(bt) => Some(bt),
                             Err(e) => panic!("Failed to connect to bigtable {:?}", e),
                         }
                     };
+                    let shutdown = listen_for_shutdown(&config);
                     blockchain_config::extract(
+                        &config,
                         indexing_range,
-                        request_builder,
+                        request_config,
                         bigtable,
                         num_extractor_threads,
                         cur_publisher,
+                        InstructionDecoderRegistry::from_config(&config),
+                        checkpoints,
+                        shutdown,
                         metrics,
                     )
                     .await
@@ -361,6 +453,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Commands::IndexList(args) => {
             let list_arg = args.list;
             let list_path = Path::new(&list_arg);
+            let checkpoints = Arc::new(CheckpointStore::from_config(&config));
             let indexing_list = if list_path.exists() {
                 // read the slots in from the csv file at the path
                 read_block_list_csv(list_path)
@@ -374,25 +467,213 @@ async fn main() -> Result<(), Box<dyn Error>> {
             match args.out {
                 OutputType::Stream => {
                     let publisher =
-                        blockchain_etl_indexer::output::publish::StreamPublisher::new().await;
+                        blockchain_etl_indexer::output::publish::StreamPublisher::new(&config)
+                            .await;
                     let cur_publisher = publisher.clone();
                     #[cfg(not(feature = "SOLANA_BIGTABLE"))]
                     let bigtable = None;
                     #[cfg(feature = "SOLANA_BIGTABLE")]
                     let bigtable = {
-                        let _bigtable = bigtable::connect_to_bigtable().await;
+                        let _bigtable = bigtable::connect_to_bigtable(&config).await;
                         match _bigtable {
                             Ok(bt) => Some(bt),
                             Err(e) => panic!("Failed to connect to bigtable {:?}", e),
                         }
                     };
 
+                    let shutdown = listen_for_shutdown(&config);
                     blockchain_config::extract(
+                        &config,
                         indexing_list,
-                        request_builder,
+                        request_config,
+                        bigtable,
+                        num_extractor_threads,
+                        cur_publisher,
+                        InstructionDecoderRegistry::from_config(&config),
+                        checkpoints,
+                        shutdown,
+                        metrics,
+                    )
+                    .await
+                    .unwrap();
+                    publisher.disconnect().await;
+                }
+            }
+        }
+        Commands::IndexRangeLeased(args) => {
+            let coordinator = LeaseCoordinator::from_config(&config);
+            let checkpoints = Arc::new(CheckpointStore::from_config(&config));
+
+            match args.out {
+                OutputType::Stream => {
+                    let publisher =
+                        blockchain_etl_indexer::output::publish::StreamPublisher::new(&config)
+                            .await;
+                    #[cfg(not(feature = "SOLANA_BIGTABLE"))]
+                    let bigtable = None;
+                    #[cfg(feature = "SOLANA_BIGTABLE")]
+                    let bigtable = {
+                        let _bigtable = bigtable::connect_to_bigtable(&config).await;
+                        match _bigtable {
+                            Ok(bt) => Some(bt),
+                            Err(e) => panic!("Failed to connect to bigtable {:?}", e),
+                        }
+                    };
+
+                    let shutdown = listen_for_shutdown(&config);
+                    while !shutdown.is_requested() {
+                        let chunk = match coordinator.next_chunk(args.start..args.end) {
+                            NextChunk::Leased(chunk) => chunk,
+                            NextChunk::Wait => {
+                                // the leases of the other instances expire if they die
+                                tokio::select! {
+                                    _ = tokio::time::sleep(coordinator.poll_interval()) => {}
+                                    _ = shutdown.requested() => {}
+                                }
+                                continue;
+                            }
+                            NextChunk::Done => {
+                                info!("Every chunk of the range is complete");
+                                break;
+                            }
+                        };
+
+                        // stops indexing the chunk if its lease is lost, as well as on shutdown
+                        let chunk_shutdown = shutdown.child();
+                        let renewal = coordinator.keep_alive(chunk, chunk_shutdown.clone());
+                        blockchain_config::extract(
+                            &config,
+                            checkpoints.clone().remaining(chunk.slots()),
+                            request_config.try_clone().unwrap(),
+                            bigtable.clone(),
+                            num_extractor_threads,
+                            publisher.clone(),
+                            InstructionDecoderRegistry::from_config(&config),
+                            checkpoints.clone(),
+                            chunk_shutdown.clone(),
+                            metrics.clone(),
+                        )
+                        .await
+                        .unwrap();
+                        renewal.abort();
+
+                        // a chunk left unfinished by a shutdown is leased again once it expires.
+                        // The settled slots are those of this instance's checkpoint log only, so
+                        // an instance that takes a chunk over indexes all of it again.
+                        if !chunk_shutdown.is_requested() && checkpoints.all_settled(chunk.slots())
+                        {
+                            coordinator.complete(chunk);
+                        }
+                    }
+                    publisher.disconnect().await;
+                }
+            }
+        }
+        Commands::FindGaps(args) => {
+            let checkpoints = CheckpointStore::from_config(&config);
+            let gaps = blockchain_config::find_gaps(
+                request_config,
+                &checkpoints,
+                args.start,
+                args.end,
+                metrics,
+            )
+            .await;
+            write_block_list_csv(Path::new(&args.list), &gaps);
+            info!("Wrote {} missing blocks to {}", gaps.len(), args.list);
+        }
+        Commands::BackfillGaps(args) => {
+            let checkpoints = Arc::new(CheckpointStore::from_config(&config));
+            let gaps = blockchain_config::find_gaps(
+                request_config.try_clone().unwrap(),
+                &checkpoints,
+                args.start,
+                args.end,
+                metrics.clone(),
+            )
+            .await;
+            info!("Backfilling {} missing blocks", gaps.len());
+
+            match args.out {
+                OutputType::Stream => {
+                    let publisher =
+                        blockchain_etl_indexer::output::publish::StreamPublisher::new(&config)
+                            .await;
+                    let cur_publisher = publisher.clone();
+                    #[cfg(not(feature = "SOLANA_BIGTABLE"))]
+                    let bigtable = None;
+                    #[cfg(feature = "SOLANA_BIGTABLE")]
+                    let bigtable = {
+                        let _bigtable = bigtable::connect_to_bigtable(&config).await;
+                        match _bigtable {
+                            Ok(bt) => Some(bt),
+                            Err(e) => panic!("Failed to connect to bigtable {:?}", e),
+                        }
+                    };
+
+                    let shutdown = listen_for_shutdown(&config);
+                    blockchain_config::extract(
+                        &config,
+                        gaps.into_iter(),
+                        request_config,
+                        bigtable,
+                        num_extractor_threads,
+                        cur_publisher,
+                        InstructionDecoderRegistry::from_config(&config),
+                        checkpoints,
+                        shutdown,
+                        metrics,
+                    )
+                    .await
+                    .unwrap();
+                    publisher.disconnect().await;
+                }
+            }
+        }
+        Commands::RetryDeadLetters(args) => {
+            let checkpoints = Arc::new(CheckpointStore::from_config(&config));
+            // the slots failing again are recorded in a new dead-letter file
+            let letters = DeadLetterSink::take(dead_letter::path_from_config(&config));
+
+            match args.out {
+                OutputType::Stream => {
+                    let publisher =
+                        blockchain_etl_indexer::output::publish::StreamPublisher::new(&config)
+                            .await;
+                    let cur_publisher = publisher.clone();
+                    #[cfg(not(feature = "SOLANA_BIGTABLE"))]
+                    let bigtable = None;
+                    #[cfg(feature = "SOLANA_BIGTABLE")]
+                    let bigtable = {
+                        let _bigtable = bigtable::connect_to_bigtable(&config).await;
+                        match _bigtable {
+                            Ok(bt) => Some(bt),
+                            Err(e) => panic!("Failed to connect to bigtable {:?}", e),
+                        }
+                    };
+
+                    // the blocks that failed to be transformed are replayed as they were fetched
+                    let slots = blockchain_config::replay_dead_letters(
+                        &config,
+                        letters,
+                        cur_publisher.clone(),
+                        InstructionDecoderRegistry::from_config(&config),
+                        checkpoints.clone(),
+                    )
+                    .await;
+                    info!("Retrying {} failed slots", slots.len());
+
+                    let shutdown = listen_for_shutdown(&config);
+                    blockchain_config::extract(
+                        &config,
+                        slots.into_iter(),
+                        request_config,
                         bigtable,
                         num_extractor_threads,
                         cur_publisher,
+                        InstructionDecoderRegistry::from_config(&config),
+                        checkpoints,
+                        shutdown,
                         metrics,
                     )
                     .await
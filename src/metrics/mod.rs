@@ -7,4 +7,8 @@ pub struct Metrics {
     pub request_count: IntCounter,
     // Total number of failed requests.
     pub failed_request_count: IntCounter,
+    // Total number of token metadata lookups served from the token cache.
+    pub token_cache_hit_count: IntCounter,
+    // Total number of token metadata lookups that had to be requested from the RPC node.
+    pub token_cache_miss_count: IntCounter,
 }
@@ -15,29 +15,23 @@ use google_cloud_pubsub::{
 };
 
 use super::publish::{StreamPublisherConnection, StreamPublisherConnectionClient};
+use crate::config::Config;
 
-/// Establishes the connection to the Google Cloud Pub/Sub extracting the credentials
-/// and information from the .env file.  This function creates the connection for
-/// using a single publisher.
-/// Must have the `GCP_CREDENTIAL_JSON_PATH` filepath pointing to the credentials json file,
-/// and have `GOOGLE_PUBSUB_TOPIC` string saved in the .env file.
-pub async fn connect(queue_name: &str) -> StreamPublisherConnection {
+/// Establishes the connection to the named Google Cloud Pub/Sub topic.
+/// The config must have the `GCP_CREDENTIALS_JSON_PATH` filepath pointing to the credentials
+/// json file.
+pub async fn connect(config: &Config, topic_name: &str) -> StreamPublisherConnection {
     let gcp_credentials_file = {
-        let gcp_credentials_json_path = dotenvy::var("GCP_CREDENTIALS_JSON_PATH")
-            .expect("GCP_CREDENTIALS_JSON_PATH should exist in .env file")
-            .parse::<String>()
-            .unwrap();
+        let gcp_credentials_json_path = config
+            .gcp_credentials_json_path
+            .clone()
+            .expect("GCP_CREDENTIALS_JSON_PATH is validated");
 
         CredentialsFile::new_from_file(gcp_credentials_json_path)
             .await
             .expect("GCP credentials file exists")
     };
 
-    let topic_name = dotenvy::var(queue_name)
-        .unwrap_or_else(|_| panic!("{} should exist in .env file", queue_name))
-        .parse::<String>()
-        .unwrap();
-
     // Attempt to open the credentials file to create the configuration
     let gcp_config = ClientConfig::default()
         .with_credentials(gcp_credentials_file)
@@ -48,25 +42,19 @@ pub async fn connect(queue_name: &str) -> StreamPublisherConnection {
     let gcp_client = Client::new(gcp_config).await.unwrap();
 
     // Use the client to connect to the specific topic.
-    connect_to_topic(gcp_client.clone(), &topic_name).await
+    connect_to_topic(gcp_client.clone(), topic_name).await
 }
 
 /// Establishes a connection to the Google Cloud Pub/Sub Topic.  Assumes that the
 /// pubsub topic has already been created in Google Cloud Platform (GCP), and panics
 /// if the topic does not exist.
-/// Should provide the GCP Client and the topic_name, where `topic_name` **is the name
-/// of a property in the .env file**.  Not the actual topic name itself.
+/// Should provide the GCP Client and the topic_name.
 async fn connect_to_topic(
     gcp_client: google_cloud_pubsub::client::Client,
     topic_name: &str,
 ) -> StreamPublisherConnection {
-    let google_pubsub_topic = dotenvy::var(topic_name)
-        .expect("GOOGLE_PUBSUB_TOPIC should exist in .env file")
-        .parse::<String>()
-        .unwrap();
-
     // NOTE: assumes that this pubsub topic has already been created in GCP.
-    let topic = gcp_client.topic(&google_pubsub_topic);
+    let topic = gcp_client.topic(topic_name);
 
     if !topic.exists(None).await.unwrap() {
         panic!("Topic {} doesn't exist! Terminating...", topic_name);
@@ -5,42 +5,35 @@
 //! confused with RabbitMQ Stream)
 
 use super::publish::{StreamPublisherConnection, StreamPublisherConnectionClient};
+use crate::config::Config;
 
 //use amqprs::channel::Channel;
 use log::info;
 
-/// Connects to the RabbitMQ Classic queue system.
-/// Expects the following parameters to be stored in the .env file:
+/// Connects to the RabbitMQ Classic queue system, to publish to the named queue.
+/// Expects the following settings in the config:
 /// - `RABBITMQ_ADDRESS`
 /// - `RABBITMQ_PORT`
 /// - `RABBITMQ_USER`
 /// - `RABBITMQ_PASSWORD`
-pub async fn connect(queue_name: &str) -> StreamPublisherConnection {
-    // Extract necessary information from the .env from the queue
-    let address = dotenvy::var("RABBITMQ_ADDRESS")
-        .expect("RABBITMQ_ADDRESS should exist in .env file")
-        .parse::<String>()
-        .unwrap();
-    let port = dotenvy::var("RABBITMQ_PORT")
-        .expect("RABBITMQ_PORT should exist in .env file")
-        .parse::<u16>()
-        .unwrap();
-    let user = dotenvy::var("RABBITMQ_USER")
-        .expect("RABBITMQ_USER should exist in .env file")
-        .parse::<String>()
-        .unwrap();
-    let password = dotenvy::var("RABBITMQ_PASSWORD")
-        .expect("RABBITMQ_PASSWORD should exist in .env file")
-        .parse::<String>()
-        .unwrap();
-    let rabbitmq_queue_name = dotenvy::var(queue_name)
-        .unwrap_or_else(|_| panic!("{} should exist in .env file", queue_name))
-        .parse::<String>()
-        .unwrap();
+pub async fn connect(config: &Config, queue_name: &str) -> StreamPublisherConnection {
+    let address = config
+        .rabbitmq_address
+        .as_deref()
+        .expect("RABBITMQ_ADDRESS is validated");
+    let port = config.rabbitmq_port.expect("RABBITMQ_PORT is validated");
+    let user = config
+        .rabbitmq_user
+        .as_deref()
+        .expect("RABBITMQ_USER is validated");
+    let password = config
+        .rabbitmq_password
+        .as_deref()
+        .expect("RABBITMQ_PASSWORD is validated");
 
     info!("Creating rabbitmq environment...");
     let connection = amqprs::connection::Connection::open(
-        &amqprs::connection::OpenConnectionArguments::new(&address, port, &user, &password),
+        &amqprs::connection::OpenConnectionArguments::new(address, port, user, password),
     )
     .await
     .expect("rabbitmq server has been setup");
@@ -52,7 +45,7 @@ pub async fn connect(queue_name: &str) -> StreamPublisherConnection {
 
     StreamPublisherConnection {
         client: StreamPublisherConnectionClient::RabbitMQClassic(connection),
-        queue_name: rabbitmq_queue_name,
+        queue_name: queue_name.to_string(),
         channel: None,
     }
 }
@@ -101,6 +94,14 @@ impl StreamPublisherConnection {
         }
     }
 
+    /// Closes the channel created by `with_channel`, once every message published on it was
+    /// handed to the server.
+    pub async fn close_channel(self) {
+        if let Some(channel) = self.channel {
+            let _ = channel.close().await;
+        }
+    }
+
     /// Sends the message to the RabbitMQ classic queue.
     ///
     /// NOTE: Will panic if channel is not yet created.  The `RABBITMQ_CLASSIC` feature
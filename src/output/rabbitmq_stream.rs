@@ -12,6 +12,7 @@ use rabbitmq_stream_client::types::Message;
 
 // local imports
 use super::publish::{StreamPublisherConnection, StreamPublisherConnectionClient};
+use crate::config::Config;
 
 impl StreamPublisherConnectionClient {
     /// Sends a message to the RabbitMQ Stream server.
@@ -34,38 +35,22 @@ impl StreamPublisherConnectionClient {
     }
 }
 
-/// Connects to the RabbitMQ Classic queue system.
-/// Expects the following parameters to be stored in the .env file:
+/// Connects to the RabbitMQ Stream server, to publish to the named stream.
+/// Expects the following settings in the config:
 /// - `RABBITMQ_ADDRESS`
 /// - `RABBITMQ_PORT`
-/// - `environment`
-/// - `RABBITMQ_PASSWORD`
-///
-/// NOTE: We also expect whatever string is passed for `queue_name` to
-/// also appear in the .env file with the name that is going to be used.
-/// This means you do not pass the queue name for `queue_name`, rather
-/// the name of the parameter in the .env file that reflects the name
-/// for the queue.
-pub async fn connect(queue_name: &str) -> StreamPublisherConnection {
-    // Extract values from the .env
-    let rabbitmq_address = dotenvy::var("RABBITMQ_ADDRESS")
-        .expect("RABBITMQ_ADDRESS should exist in .env file")
-        .parse::<String>()
-        .unwrap();
-    let rabbitmq_port = dotenvy::var("RABBITMQ_PORT")
-        .expect("RABBITMQ_PORT should exist in .env file")
-        .parse::<u16>()
-        .unwrap();
+pub async fn connect(config: &Config, queue_name: &str) -> StreamPublisherConnection {
+    let rabbitmq_address = config
+        .rabbitmq_address
+        .as_deref()
+        .expect("RABBITMQ_ADDRESS is validated");
+    let rabbitmq_port = config.rabbitmq_port.expect("RABBITMQ_PORT is validated");
     let rabbitmq_environment = rabbitmq_stream_client::Environment::builder()
-        .host(&rabbitmq_address)
+        .host(rabbitmq_address)
         .port(rabbitmq_port)
         .build()
         .await
         .expect("FATAL: could not create rabbitmq environment");
-    let rabbitmq_queue_name = dotenvy::var(queue_name)
-        .unwrap_or_else(|_| panic!("{} should exist in .env file", queue_name))
-        .parse::<String>()
-        .unwrap();
     info!("Successfully created the rabbitmq environment");
 
     // this will cause a panic if the stream HAS been created:
@@ -74,13 +59,13 @@ pub async fn connect(queue_name: &str) -> StreamPublisherConnection {
     // this will cause a panic if the stream has NOT been created:
     let producer = rabbitmq_environment
         .producer()
-        .build(&rabbitmq_queue_name)
+        .build(queue_name)
         .await
         .expect("FATAL: stream has not yet been created");
 
     StreamPublisherConnection {
         client: StreamPublisherConnectionClient::RabbitMQStream(producer),
-        queue_name: rabbitmq_queue_name,
+        queue_name: queue_name.to_string(),
     }
 }
 
@@ -2,6 +2,7 @@
 //! it is not blockchain specific.  Note, StreamPublishers supporting seperate publishers should be implemented within
 //! the blockchain.
 use super::publish::StreamPublisherConnection;
+use crate::config::Config;
 use log::info;
 
 // Get the appropriate connect
@@ -22,13 +23,32 @@ pub struct StreamPublisher {
 
 #[cfg(feature = "SINGLE_PUBLISHER")]
 impl StreamPublisher {
-    pub async fn new() -> StreamPublisher {
+    /// Connects to the queue named by `QUEUE_NAME`.
+    pub async fn new(config: &Config) -> StreamPublisher {
         info!("Connecting to the publisher...");
+        let queue_name = config
+            .queue_name
+            .as_deref()
+            .expect("QUEUE_NAME is validated");
         StreamPublisher {
-            all: connect("QUEUE_NAME").await,
+            all: connect(config, queue_name).await,
         }
     }
 
+    /// Creates the channel of the publisher in the current thread (RabbitMQ-Classic specific).
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    pub async fn with_channels(self) -> StreamPublisher {
+        StreamPublisher {
+            all: self.all.with_channel().await,
+        }
+    }
+
+    /// Closes the channel of the publisher created by `with_channels` (RabbitMQ-Classic specific).
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    pub async fn close_channels(self) {
+        self.all.close_channel().await;
+    }
+
     pub async fn disconnect(self) {
         info!("Disconnecting from publisher...");
         self.all.disconnect().await;
@@ -2,7 +2,8 @@
 mod tests {
     #[tokio::test]
     async fn test_publisher_connection() {
-        let publisher = crate::output::publish::StreamPublisher::new().await;
+        let config = crate::config::Config::load(None).unwrap();
+        let publisher = crate::output::publish::StreamPublisher::new(&config).await;
         publisher.disconnect().await;
     }
 }
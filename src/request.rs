@@ -1,15 +1,15 @@
 use log::info;
 use reqwest::header::HeaderMap;
 
-/// Returns reqwest headermap. If the GCP_API_KEY environment variable is present, then it is added to the headers.
-pub fn get_headers() -> HeaderMap {
+/// Returns reqwest headermap. If there is an API key (the GCP_API_KEY setting), then it is added to the headers.
+pub fn get_headers(api_key: Option<&str>) -> HeaderMap {
     let mut headers = reqwest::header::HeaderMap::new();
     headers.insert(
         reqwest::header::CONTENT_TYPE,
         "application/json".parse().unwrap(),
     );
 
-    if let Ok(key) = dotenvy::var("GCP_API_KEY") {
+    if let Some(key) = api_key {
         info!("Using the GCP API key...");
         headers.insert("X-goog-api-key", key.parse().unwrap());
     }
@@ -0,0 +1,123 @@
+//! Graceful shutdown of the indexer on SIGINT (ctrl-c) or SIGTERM.
+//!
+//! Once a shutdown is requested, no more slots are queued and the slots already in flight have
+//! until the deadline to be published.  The slots left unfinished keep their checkpoint status,
+//! so the next run picks them up.  A second signal terminates the indexer right away.
+use std::{sync::Arc, time::Duration};
+
+use log::{error, warn};
+use tokio::sync::watch;
+
+use crate::config::Config;
+
+pub mod tests;
+
+/// The default number of seconds the slots in flight have to finish once a shutdown is requested.
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// A handle on the shutdown of the indexer, shared by everything that should stop with it.
+#[derive(Clone)]
+pub struct Shutdown {
+    requester: Arc<watch::Sender<bool>>,
+    requested: watch::Receiver<bool>,
+    /// how long the slots in flight have to finish once a shutdown is requested
+    timeout: Duration,
+}
+
+impl Shutdown {
+    /// Creates a shutdown handle, with the given time for the slots in flight to finish.
+    pub fn new(timeout: Duration) -> Shutdown {
+        let (requester, requested) = watch::channel(false);
+        Shutdown {
+            requester: Arc::new(requester),
+            requested,
+            timeout,
+        }
+    }
+
+    /// Creates a shutdown handle with the `SHUTDOWN_TIMEOUT` setting.
+    pub fn from_config(config: &Config) -> Shutdown {
+        // env:shutdown_timeout = The number of seconds the slots in flight have to finish on shutdown
+        let timeout_secs = config.shutdown_timeout.unwrap_or(DEFAULT_TIMEOUT_SECS);
+        Shutdown::new(Duration::from_secs(timeout_secs))
+    }
+
+    /// Creates a handle that is requested along with this one, but can also be requested on its
+    /// own, e.g. to stop indexing a single chunk without stopping the indexer.
+    pub fn child(&self) -> Shutdown {
+        let child = Shutdown::new(self.timeout);
+        let (parent, requester) = (self.clone(), child.requester.clone());
+        tokio::task::spawn(async move {
+            tokio::select! {
+                _ = parent.requested() => {
+                    requester.send_replace(true);
+                }
+                // every handle on the child is dropped
+                _ = requester.closed() => {}
+            }
+        });
+        child
+    }
+
+    /// How long the slots in flight have to finish once a shutdown is requested.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Requests a shutdown.
+    pub fn request(&self) {
+        self.requester.send_replace(true);
+    }
+
+    /// Whether a shutdown was requested.
+    pub fn is_requested(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    /// Waits until a shutdown is requested.
+    pub async fn requested(&self) {
+        let mut requested = self.requested.clone();
+        // the sender lives as long as this handle, so waiting can't fail
+        let _ = requested.wait_for(|requested| *requested).await;
+    }
+
+    /// Waits until the deadline of a requested shutdown has passed.
+    pub async fn timed_out(&self) {
+        self.requested().await;
+        tokio::time::sleep(self.timeout).await;
+    }
+
+    /// Requests a shutdown on the first SIGINT or SIGTERM, and exits on the second one.
+    pub fn listen_for_signals(&self) {
+        let shutdown = self.clone();
+        tokio::task::spawn(async move {
+            wait_for_signal().await;
+            warn!(
+                "Shutting down: finishing the slots in flight within {:?}. Signal again to terminate now...",
+                shutdown.timeout
+            );
+            shutdown.request();
+
+            wait_for_signal().await;
+            error!("Terminating without finishing the slots in flight");
+            std::process::exit(130);
+        });
+    }
+}
+
+/// Waits for a SIGINT or, on unix, a SIGTERM (which container orchestrators send to stop a pod).
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Error setting the SIGTERM handler");
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.expect("Error setting the Ctrl-C handler"),
+            _ = sigterm.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Error setting the Ctrl-C handler");
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::shutdown::Shutdown;
+
+    #[tokio::test]
+    async fn test_shutdown_wakes_every_handle() {
+        let shutdown = Shutdown::new(Duration::from_secs(30));
+        let waiter = {
+            let shutdown = shutdown.clone();
+            tokio::task::spawn(async move { shutdown.requested().await })
+        };
+        assert!(!shutdown.is_requested());
+
+        shutdown.request();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("the waiting handle is woken up")
+            .unwrap();
+        assert!(shutdown.clone().is_requested());
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_deadline_starts_when_requested() {
+        let shutdown = Shutdown::new(Duration::from_millis(100));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), shutdown.timed_out())
+                .await
+                .is_err(),
+            "the deadline doesn't run until a shutdown is requested"
+        );
+
+        shutdown.request();
+        let requested_at = std::time::Instant::now();
+        shutdown.timed_out().await;
+        assert!(requested_at.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_child_is_requested_with_its_parent_only() {
+        let parent = Shutdown::new(Duration::from_secs(30));
+        let child = parent.child();
+        child.request();
+        assert!(!parent.is_requested(), "a child doesn't stop its parent");
+
+        let child = parent.child();
+        parent.request();
+        tokio::time::timeout(Duration::from_secs(1), child.requested())
+            .await
+            .expect("the child is requested along with its parent");
+    }
+}
@@ -12,30 +12,37 @@
 //!     KeyedAccountValueResponse {
 //!         KeyedAccountInfoResponse {
 //!             account_data,
+//!             context_slot,
 //!             pubkey
 //!         }
 //!     }
 //! }
 //! ```
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 #[cfg(debug_assertions)]
 use {std::time, tokio::time::sleep};
 
+use futures::stream::{self, StreamExt};
 use log::error;
 
 use super::{
+    native_accounts,
     proto_conversions::account::PackagedAccount,
+    stake, token_extensions,
     types::account_response_types::{
         AccountDataEnumResponse, AccountInfoInfoEnumResponse, AccountInfoResponse,
-        AccountValueResponse, ContextInfoResponse,
+        AccountValueResponse,
     },
 };
 use crate::{
     self as blockchain_generic,
     solana_config::{
-        data_sources::json_rpc::get_multiple_accounts_post_body,
+        constants, data_sources::json_rpc::get_multiple_accounts_post_body,
         types::account_response_types::AccountResponse,
     },
 };
@@ -44,14 +51,38 @@ use blockchain_generic::{metrics::Metrics, source::config::RequestConfig};
 
 use log::{info, warn};
 
+/// The data of an [account](https://docs.solana.com/terminology#account) along with the slot
+/// at which the RPC node evaluated the request that returned it.
+#[derive(Debug)]
+pub struct ContextualAccountInfoResponse {
+    /// The slot of the context of the response the account was part of.
+    pub context_slot: u64,
+    /// The [account](https://docs.solana.com/terminology#account) data responded.
+    pub accounts_data: AccountInfoResponse,
+}
+
 /// Account Value Response with the timestamp of when the data was retrieved.
 #[derive(Debug)]
 pub struct TimestampedAccounts {
     /// A timestamp of when the [account](https://docs.solana.com/terminology#account) data was
     /// retrieved from the RPC node.
     pub timestamp: u64,
-    /// The accounts' data retrieved
-    pub accounts: AccountValueResponse,
+    /// The accounts' data retrieved, in the order of the requested pubkeys. `None` for accounts
+    /// that don't exist, or whose request had no result.
+    pub accounts: Vec<Option<ContextualAccountInfoResponse>>,
+}
+
+/// stamps each account of a getMultipleAccounts response with the slot of the response's context.
+pub fn contextual_accounts(
+    response: AccountValueResponse,
+) -> impl Iterator<Item = Option<ContextualAccountInfoResponse>> {
+    let context_slot = response.context.slot;
+    response.value.into_iter().map(move |account| {
+        account.map(|accounts_data| ContextualAccountInfoResponse {
+            context_slot,
+            accounts_data,
+        })
+    })
 }
 
 /// KeyedAccount is the [account](https://docs.solana.com/terminology#account) data of a singular
@@ -63,18 +94,17 @@ pub struct KeyedAccountInfoResponse {
     /// The [public key](https://docs.solana.com/terminology#public-key-pubkey) associated with the
     /// [account](https://docs.solana.com/terminology#account).
     pub pubkey: String,
+    /// The slot at which the RPC node evaluated the request that returned this account. Accounts
+    /// requested in different chunks may have been evaluated at different slots.
+    pub context_slot: u64,
     /// The [account](https://docs.solana.com/terminology#account) data responded.
     pub accounts_data: AccountInfoResponse,
 }
 
-/// KeyedAccountValueResponse contain the context in which the call for the account(s)
-/// was made, with the KeyedAccountInfoResponse containing both the timestamps when
-/// the RPC node responded with the data and the public key.
+/// KeyedAccountValueResponse contains the KeyedAccountInfoResponses, each carrying both the
+/// context slot at which the RPC node evaluated it and the public key.
 #[derive(Clone)]
 pub struct KeyedAccountValueResponse {
-    /// Context relating to the [account](https://docs.solana.com/terminology#account) value
-    /// response. (slot data & api version)
-    pub context: ContextInfoResponse,
     /// A vector of the [account](https://docs.solana.com/terminology#account) data responded
     /// with the [public keys](https://docs.solana.com/terminology#public-key-pubkey)
     pub value: Vec<KeyedAccountInfoResponse>,
@@ -100,22 +130,61 @@ impl KeyedTimestampedAccounts {
         KeyedTimestampedAccounts {
             timestamp: timestamped_accounts.timestamp,
             accounts: KeyedAccountValueResponse {
-                context: timestamped_accounts.accounts.context,
                 value: timestamped_accounts
                     .accounts
-                    .value
                     .into_iter()
                     .zip(pubkeys)
                     .filter_map(|(account, pubkey)| {
-                        account.map(|accounts_data| KeyedAccountInfoResponse {
+                        account.map(|account| KeyedAccountInfoResponse {
                             pubkey,
-                            accounts_data,
+                            context_slot: account.context_slot,
+                            accounts_data: account.accounts_data,
                         })
                     })
                     .collect(),
             },
         }
     }
+
+    /// returns the subset of these accounts that match the given pubkeys, in the order of the pubkeys.
+    /// pubkeys that were not returned by the RPC node are skipped.
+    /// useful for fanning the accounts of an entire block back out to its transactions.
+    pub fn select_pubkeys(
+        &self,
+        accounts_by_pubkey: &HashMap<&str, &KeyedAccountInfoResponse>,
+        pubkeys: &[String],
+    ) -> KeyedTimestampedAccounts {
+        KeyedTimestampedAccounts {
+            timestamp: self.timestamp,
+            accounts: KeyedAccountValueResponse {
+                value: pubkeys
+                    .iter()
+                    .filter_map(|pubkey| accounts_by_pubkey.get(pubkey.as_str()))
+                    .map(|account| (*account).clone())
+                    .collect(),
+            },
+        }
+    }
+
+    /// indexes the accounts by their pubkey, so that they can be looked up with `select_pubkeys()`.
+    pub fn by_pubkey(&self) -> HashMap<&str, &KeyedAccountInfoResponse> {
+        self.accounts
+            .value
+            .iter()
+            .map(|account| (account.pubkey.as_str(), account))
+            .collect()
+    }
+}
+
+/// parses a u64 that the jsonParsed encoding returns as a string, logging the values that aren't numbers.
+pub fn parse_amount(field: &str, amount: &str) -> Option<u64> {
+    match amount.parse() {
+        Ok(amount) => Some(amount),
+        Err(e) => {
+            warn!("Failed to parse the {} {:?}: {:?}", field, amount, e);
+            None
+        }
+    }
 }
 
 /// helper function for conversion from the raw data received from the RPC node to the protocol buffers specification.
@@ -126,6 +195,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
 
     for KeyedAccountInfoResponse {
         pubkey,
+        context_slot,
         accounts_data,
     } in keyed_accounts.accounts.value.into_iter()
     {
@@ -147,12 +217,17 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                             let owner = s.owner;
                             let mint = s.mint;
                             let state = s.state;
+                            let extensions = s
+                                .extensions
+                                .as_deref()
+                                .map(token_extensions::decode_extensions);
                             let (token_amount, token_amount_decimals) = match s.tokenAmount {
                                 Some(inner) => (Some(inner.amount), Some(inner.decimals)),
                                 None => (None, None),
                             };
                             let a = PackagedAccount::new_account_type(
                                 timestamp,
+                                context_slot,
                                 pubkey,
                                 executable,
                                 lamports,
@@ -165,14 +240,20 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                                 token_amount_decimals,
                                 is_native,
                                 state,
+                                extensions,
                             );
                             accounts_packaged.push(a);
                         } else if account_type == "mint" {
                             let token_amount_decimals = s.decimals;
                             let mint_authority = s.mintAuthority;
                             let supply = s.supply;
+                            let extensions = s
+                                .extensions
+                                .as_deref()
+                                .map(token_extensions::decode_extensions);
                             let a = PackagedAccount::new_mint_type(
                                 timestamp,
+                                context_slot,
                                 pubkey,
                                 executable,
                                 lamports,
@@ -183,12 +264,14 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                                 token_amount_decimals,
                                 mint_authority,
                                 supply,
+                                extensions,
                             );
                             accounts_packaged.push(a);
                         } else if account_type == "program" {
                             let program_data = s.programData;
                             let a = PackagedAccount::new_program_type(
                                 timestamp,
+                                context_slot,
                                 pubkey,
                                 executable,
                                 lamports,
@@ -212,6 +295,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
 
                             let a = PackagedAccount::new_vote_type(
                                 timestamp,
+                                context_slot,
                                 pubkey,
                                 executable,
                                 lamports,
@@ -230,9 +314,31 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                                 last_timestamp,
                             );
                             accounts_packaged.push(a);
-                        } else if account_type == "delegated" {
-                            let a = PackagedAccount::new_unused_type(
+                        } else if let (true, Some(meta)) =
+                            (program == stake::STAKE_PROGRAM, &s.meta)
+                        {
+                            // "initialized" or "delegated" stake accounts
+                            let stake = stake::decode_stake_account(meta, s.stake.as_ref());
+                            let a = PackagedAccount::new_stake_type(
+                                timestamp,
+                                context_slot,
+                                pubkey,
+                                executable,
+                                lamports,
+                                owner,
+                                rent_epoch,
+                                space,
+                                program,
+                                account_type,
+                                stake,
+                            );
+                            accounts_packaged.push(a);
+                        } else if let Some(state) =
+                            native_accounts::decode_native_account(&program, &account_type, &s)
+                        {
+                            let a = PackagedAccount::new_native_type(
                                 timestamp,
+                                context_slot,
                                 pubkey,
                                 executable,
                                 lamports,
@@ -241,6 +347,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                                 space,
                                 program,
                                 account_type,
+                                state,
                             );
                             accounts_packaged.push(a);
                         } else {
@@ -250,6 +357,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
 
                             let a = PackagedAccount::new_other_type(
                                 timestamp,
+                                context_slot,
                                 pubkey,
                                 executable,
                                 lamports,
@@ -272,6 +380,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                         .collect();*/
                         let a = PackagedAccount::new_unused_type(
                             timestamp,
+                            context_slot,
                             pubkey,
                             executable,
                             lamports,
@@ -287,6 +396,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                         warn!("Encountered a feeCalculator list. Unused for now...");
                         let a = PackagedAccount::new_unused_type(
                             timestamp,
+                            context_slot,
                             pubkey,
                             executable,
                             lamports,
@@ -302,6 +412,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                         warn!("Encountered a stakeHistory list. Unused for now...");
                         let a = PackagedAccount::new_unused_type(
                             timestamp,
+                            context_slot,
                             pubkey,
                             executable,
                             lamports,
@@ -316,6 +427,7 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                     None => {
                         let a = PackagedAccount::new_unused_type(
                             timestamp,
+                            context_slot,
                             pubkey,
                             executable,
                             lamports,
@@ -335,7 +447,15 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
                 let [data, encoding] = string_slice;
 
                 let a = PackagedAccount::new_encoded_type(
-                    timestamp, pubkey, executable, lamports, owner, rent_epoch, data, encoding,
+                    timestamp,
+                    context_slot,
+                    pubkey,
+                    executable,
+                    lamports,
+                    owner,
+                    rent_epoch,
+                    data,
+                    encoding,
                 );
                 accounts_packaged.push(a);
 
@@ -354,111 +474,166 @@ pub fn package_accounts(keyed_accounts: KeyedTimestampedAccounts) -> Vec<Package
 }
 
 /// makes a request for the account data associated with each of the pubkeys.
+/// the pubkeys are split into chunks of up to 100 (the maximum allowed by getMultipleAccounts),
+/// and the chunks are requested concurrently. the returned accounts are in the same order as the pubkeys.
+/// if `min_context_slot` is set, the RPC node will not evaluate the request before it has reached that slot.
 #[allow(non_snake_case)]
 pub async fn call_getMultipleAccounts(
     request_config: RequestConfig,
     account_keys: Vec<String>,
+    min_context_slot: Option<u64>,
     metrics: Option<Metrics>,
 ) -> Option<TimestampedAccounts> {
-    // can only call getMultipleAccounts with up to 100 account pubkeys at once.
-    // so we break up the vector by chunks of 100, and join each of the responses.
-    let mut context: Option<ContextInfoResponse> = None;
-    let mut AccountInfoResponses: Vec<Option<AccountInfoResponse>> = Vec::new();
-
-    for chunk in account_keys.chunks(100) {
-        info!("Making a call to getMultipleAccounts...");
-        let deserialized_accounts = loop {
-            let cur_builder = request_config.try_clone().unwrap();
-            let response: reqwest::Response = blockchain_generic::call_rpc_method(
-                cur_builder,
-                get_multiple_accounts_post_body(chunk.to_vec()),
+    let chunk_requests = account_keys
+        .chunks(constants::MAX_ACCOUNTS_PER_REQUEST)
+        .map(|chunk| {
+            call_getMultipleAccounts_chunk(
+                request_config.try_clone().unwrap(),
+                chunk.to_vec(),
+                min_context_slot,
                 metrics.clone(),
             )
-            .await;
-            #[cfg(debug_assertions)]
-            {
-                let r = response
-                    .text()
-                    .await
-                    .expect("FATAL: could not parse the response as a string");
-
-                if r.contains("Too many requests for a specific RPC call, contact your app developer or support@rpcpool.com.") {
+        })
+        .collect::<Vec<_>>();
+
+    // `buffered` preserves the order of the chunks, so the responses line up with `account_keys`.
+    let chunk_responses: Vec<(usize, Option<AccountValueResponse>)> = stream::iter(chunk_requests)
+        .buffered(constants::MAX_CONCURRENT_ACCOUNT_REQUESTS)
+        .collect()
+        .await;
+
+    let mut any_response = false;
+    let mut accounts: Vec<Option<ContextualAccountInfoResponse>> =
+        Vec::with_capacity(account_keys.len());
+    for (chunk_len, chunk_response) in chunk_responses {
+        match chunk_response {
+            // each chunk may have been evaluated at a different slot, so every account keeps the
+            // context slot of its own chunk
+            Some(res) => {
+                any_response = true;
+                accounts.extend(contextual_accounts(res));
+            }
+            // keep the accounts aligned with their pubkeys if a chunk had no result
+            None => accounts.extend(std::iter::repeat_with(|| None).take(chunk_len)),
+        }
+    }
+
+    any_response.then(|| {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time only moves forward")
+            .as_secs();
+
+        TimestampedAccounts {
+            timestamp,
+            accounts,
+        }
+    })
+}
+
+/// makes a single getMultipleAccounts request for up to 100 pubkeys, retrying until the response can be deserialized.
+/// returns the number of pubkeys requested along with the result.
+#[allow(non_snake_case)]
+async fn call_getMultipleAccounts_chunk(
+    request_config: RequestConfig,
+    chunk: Vec<String>,
+    min_context_slot: Option<u64>,
+    metrics: Option<Metrics>,
+) -> (usize, Option<AccountValueResponse>) {
+    info!("Making a call to getMultipleAccounts...");
+    let deserialized_accounts = loop {
+        let cur_builder = request_config.try_clone().unwrap();
+        let response: reqwest::Response = blockchain_generic::call_rpc_method(
+            cur_builder,
+            get_multiple_accounts_post_body(chunk.clone(), min_context_slot),
+            metrics.clone(),
+        )
+        .await;
+        #[cfg(debug_assertions)]
+        {
+            let r = response
+                .text()
+                .await
+                .expect("FATAL: could not parse the response as a string");
+
+            if r.contains("Too many requests for a specific RPC call, contact your app developer or support@rpcpool.com.") {
                 error!("throttled by the public node.");
                 let seconds = time::Duration::from_secs(5);
                 sleep(seconds).await;
                 continue;
-             }
+            }
 
-                let deserialized_response: AccountResponse = match serde_json::from_str(&r) {
-                    Ok(deserialized_response) => deserialized_response,
-                    Err(e) => {
-                        dbg!(r.clone());
-                        warn!("Failed to parse the getMultipleAccounts() response for pubkeys {:?}: {:?}", chunk, e);
-                        continue;
-                    }
-                };
-                break deserialized_response;
+            let deserialized_response: AccountResponse = match serde_json::from_str(&r) {
+                Ok(deserialized_response) => deserialized_response,
+                Err(e) => {
+                    warn!(
+                        "Failed to parse the getMultipleAccounts() response for pubkeys {:?}: {:?}, body: {}",
+                        chunk,
+                        e,
+                        r.chars().take(constants::MAX_LOGGED_BODY_CHARS).collect::<String>()
+                    );
+                    continue;
+                }
+            };
+            if min_context_slot_not_reached(&deserialized_response) {
+                wait_for_min_context_slot(min_context_slot).await;
+                continue;
             }
+            break deserialized_response;
+        }
 
-            #[cfg(not(debug_assertions))]
-            {
-                // the `serde_json` deserializer produces more information for debugging (such as the column in the string, and the value)
-                //  but it is slower than `simd_json`.
-                // NOTE: if something is broken in the simd_json crate, then we will not notice it if we compile in debug mode.
-                let deserialized_response: Result<AccountResponse, simd_json::Error> = {
-                    let response_bytes = match response.bytes().await {
-                        Err(e) => {
-                            error!("could not read response: {:?}", e);
-                            continue;
-                        }
-                        Ok(val) => val,
-                    };
-                    let mut byte_vec = response_bytes.to_vec();
-                    let byte_slice = byte_vec.as_mut_slice();
-                    simd_json::from_slice(byte_slice)
-                };
-                match deserialized_response {
-                    Ok(deserialized) => break deserialized,
+        #[cfg(not(debug_assertions))]
+        {
+            // the `serde_json` deserializer produces more information for debugging (such as the column in the string, and the value)
+            //  but it is slower than `simd_json`.
+            // NOTE: if something is broken in the simd_json crate, then we will not notice it if we compile in debug mode.
+            let deserialized_response: Result<AccountResponse, simd_json::Error> = {
+                let response_bytes = match response.bytes().await {
                     Err(e) => {
-                        warn!("Failed to parse the getMultipleAccounts() response for pubkeys {:?}: {:?}", chunk, e);
+                        error!("could not read response: {:?}", e);
                         continue;
                     }
+                    Ok(val) => val,
+                };
+                let mut byte_vec = response_bytes.to_vec();
+                let byte_slice = byte_vec.as_mut_slice();
+                simd_json::from_slice(byte_slice)
+            };
+            match deserialized_response {
+                Ok(deserialized) if min_context_slot_not_reached(&deserialized) => {
+                    wait_for_min_context_slot(min_context_slot).await;
+                    continue;
                 }
-            }
-        };
-
-        info!("Successfully deserialized accounts data");
-
-        match context {
-            // if it's the first/only call to getMultipleAccounts, then create the result vector (AccountInfoResponses)
-            None => {
-                let res_opt = deserialized_accounts.result;
-                if let Some(res) = res_opt {
-                    context = Some(res.context);
-                    AccountInfoResponses = res.value.clone();
+                Ok(deserialized) => break deserialized,
+                Err(e) => {
+                    warn!(
+                        "Failed to parse the getMultipleAccounts() response for pubkeys {:?}: {:?}",
+                        chunk, e
+                    );
+                    continue;
                 }
             }
-            // if this is a subsequent call to getMultipleAccounts, append to the result vector (AccountInfoResponses)
-            Some(_) => {
-                AccountInfoResponses.append(&mut deserialized_accounts.result.unwrap().value)
-            }
         }
-    }
+    };
 
-    context.map(|c| {
-        let accounts = AccountValueResponse {
-            context: c,
-            value: AccountInfoResponses,
-        };
+    info!("Successfully deserialized accounts data");
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("time only moves forward")
-            .as_secs();
+    (chunk.len(), deserialized_accounts.result)
+}
 
-        TimestampedAccounts {
-            timestamp,
-            accounts,
-        }
-    })
+/// returns true if the RPC node rejected the request because it has not yet reached the `minContextSlot`.
+fn min_context_slot_not_reached(response: &AccountResponse) -> bool {
+    response
+        .error
+        .as_ref()
+        .is_some_and(|err| err.code == constants::MIN_CONTEXT_SLOT_NOT_REACHED_ERROR_CODE)
+}
+
+/// gives a lagging RPC node some time to catch up to the `minContextSlot` before retrying.
+async fn wait_for_min_context_slot(min_context_slot: Option<u64>) {
+    warn!(
+        "RPC node has not reached the minimum context slot {:?}, retrying...",
+        min_context_slot
+    );
+    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
 }
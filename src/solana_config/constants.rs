@@ -15,6 +15,35 @@ pub const METADATA_BYTES: &[u8] = b"metadata";
 /// used for retrieving token data, useful in [find_program_address](solana_sdk::pubkey::Pubkey::find_program_address)
 pub const METADATA_PROGRAM_ID_STR: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
 
+/// The id of the Compute Budget program, which sets the compute unit limit and price of a transaction.
+pub const COMPUTE_BUDGET_PROGRAM_ID_STR: &str = "ComputeBudget111111111111111111111111111111";
+
+/// The fee paid for each signature of a transaction, in lamports.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+/// The compute unit limit given to each instruction when the transaction doesn't set one.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// The maximum compute unit limit of a transaction.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// The compute unit price is set in micro-lamports.
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// The maximum number of pubkeys that can be passed to a single
+/// [getMultipleAccounts](https://docs.solana.com/api/http#getmultipleaccounts) call.
+pub const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// The maximum number of slots that can be passed to a single
+/// [getBlocks](https://docs.solana.com/api/http#getblocks) call.
+pub const MAX_GET_BLOCKS_RANGE: u64 = 500_000;
+
+/// The maximum number of getMultipleAccounts requests that are in flight at once for a single block.
+pub const MAX_CONCURRENT_ACCOUNT_REQUESTS: usize = 8;
+
+/// The maximum number of characters of an unparseable RPC response body that are logged.
+pub const MAX_LOGGED_BODY_CHARS: usize = 1_000;
+
 /// Error code returned when calling for a block given a skipped slot
 pub const SKIPPED_SLOT_ERROR_CODE: i32 = -32009;
 
@@ -32,3 +61,6 @@ pub const INTERNAL_ERROR_CODE: i32 = -32603;
 pub const NO_TX_HISTORY: i32 = -32011;
 
 pub const NO_STATUS: i32 = -32014;
+
+/// Error code returned when the node has not yet reached the `minContextSlot` of a request
+pub const MIN_CONTEXT_SLOT_NOT_REACHED_ERROR_CODE: i32 = -32016;
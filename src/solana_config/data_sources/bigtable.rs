@@ -1,5 +1,6 @@
 //! This module contains the functions required to communicate with the
 //! [Solana Foundation's BigTable instance](https://docs.solana.com/implemented-proposals/rpc-transaction-history#table-schema).  
+use crate::config::Config;
 use crate::solana_config::constants;
 use solana_transaction_status::{
     BlockEncodingOptions, ConfirmedBlock, TransactionDetails, UiConfirmedBlock,
@@ -15,12 +16,14 @@ pub type BigTableResult<T> = std::result::Result<T, solana_storage_bigtable::Err
 
 /// Connects to the BigTable.  
 ///
-/// Note: Must have BigTable credentials set up in the .env file, otherwise this will fail.
-pub async fn connect_to_bigtable() -> Result<LedgerStorage, solana_storage_bigtable::Error> {
-    let bigtable_cred = dotenvy::var("BIGTABLE_CRED")
-        .expect("BIGTABLE_CRED should exist in .env file")
-        .parse::<String>()
-        .unwrap();
+/// Note: Must have the BigTable credentials (`BIGTABLE_CRED`) in the config.
+pub async fn connect_to_bigtable(
+    config: &Config,
+) -> Result<LedgerStorage, solana_storage_bigtable::Error> {
+    let bigtable_cred = config
+        .bigtable_cred
+        .clone()
+        .expect("BIGTABLE_CRED is validated");
 
     let bt_config = solana_storage_bigtable::LedgerStorageConfig {
         credential_type: CredentialType::Filepath(Some(bigtable_cred)),
@@ -36,6 +39,7 @@ pub async fn connect_to_bigtable() -> Result<LedgerStorage, solana_storage_bigta
 #[inline]
 pub async fn call_get_confirmed_block(
     bigtable: &solana_storage_bigtable::LedgerStorage,
+    config: &Config,
     confirmed_slot: u64,
 ) -> BigTableResult<ConfirmedBlock> {
     info!(
@@ -75,7 +79,7 @@ pub async fn call_get_confirmed_block(
                             confirmed_slot, e_reattempted
                         );
                         // attempt to re-connect to the bigtable if there's a connection error
-                        let attempted_reconnection = connect_to_bigtable().await;
+                        let attempted_reconnection = connect_to_bigtable(config).await;
                         match attempted_reconnection {
                             Ok(connection) => bt = connection,
                             Err(e_connection) => {
@@ -10,7 +10,7 @@ use crate as blockchain_generic;
 use {
     blockchain_generic::{
         metrics::Metrics,
-        solana_config::{constants, data_sources::json_rpc},
+        solana_config::{constants, data_sources::json_rpc, types::block_response_types},
         source::config::RequestConfig,
     },
     log::warn,
@@ -44,8 +44,12 @@ pub fn get_slot_post_body() -> String {
 
 /// creates a post request body (as a `String`) to make an RPC call for getMultipleAccounts() for the given PubKeys.
 ///     account - A vector of up to 100 [pubkeys](https://docs.solana.com/terminology#public-key-pubkey)
-pub fn get_multiple_accounts_post_body(account: Vec<String>) -> String {
-    let post_body_struct = request_types::AccountsRequest::new(account);
+///     min_context_slot - The minimum [Slot](https://docs.solana.com/terminology#slot) that the request can be evaluated at, if any
+pub fn get_multiple_accounts_post_body(
+    account: Vec<String>,
+    min_context_slot: Option<u64>,
+) -> String {
+    let post_body_struct = request_types::AccountsRequest::new(account, min_context_slot);
     serde_json::to_string(&post_body_struct).unwrap()
 }
 
@@ -58,7 +62,7 @@ pub async fn get_recent_block(
     request_config: RequestConfig,
     slot: u64,
     metrics: Option<Metrics>,
-) -> Option<solana_transaction_status::UiConfirmedBlock> {
+) -> Option<block_response_types::BlockWithTransactionErrors> {
     let mut _request_config = request_config;
     let mut tried_fallback = false;
     loop {
@@ -80,13 +84,15 @@ pub async fn get_recent_block(
                         if tried_fallback {
                             return None;
                         }
-                        if let Ok(fb) = dotenvy::var("FALLBACK_ENDPOINT") {
+                        if let Some(fb) = _request_config.fallback().cloned() {
                             warn!("Trying again with the fallback endpoint...");
                             let request_body = json_rpc::get_block_post_body(slot);
-                            let cur_request_builder =
-                                blockchain_generic::use_fallback_endpoint(request_body.clone(), fb)
-                                    .await;
-                            _request_config = RequestConfig::ReqBldr(cur_request_builder);
+                            let cur_request_builder = blockchain_generic::use_fallback_endpoint(
+                                request_body.clone(),
+                                &fb,
+                            )
+                            .await;
+                            _request_config = RequestConfig::ReqBldr(cur_request_builder, Some(fb));
                             tried_fallback = true;
                         } else {
                             return None;
@@ -103,16 +109,17 @@ pub async fn get_recent_block(
                                 if tried_fallback {
                                     return None;
                                 }
-                                if let Ok(fb) = dotenvy::var("FALLBACK_ENDPOINT") {
+                                if let Some(fb) = _request_config.fallback().cloned() {
                                     warn!("Trying again with the fallback endpoint...");
                                     let request_body = json_rpc::get_block_post_body(slot);
                                     let cur_request_builder =
                                         blockchain_generic::use_fallback_endpoint(
                                             request_body.clone(),
-                                            fb,
+                                            &fb,
                                         )
                                         .await;
-                                    _request_config = RequestConfig::ReqBldr(cur_request_builder);
+                                    _request_config =
+                                        RequestConfig::ReqBldr(cur_request_builder, Some(fb));
                                     tried_fallback = true;
                                 } else {
                                     return None;
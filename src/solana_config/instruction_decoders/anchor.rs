@@ -0,0 +1,537 @@
+//! Decoder for [Anchor](https://www.anchor-lang.com/) programs, driven by the program's IDL.
+//! IDLs are loaded from local JSON files, so new programs can be decoded without a code change.
+//! Both the legacy IDL format (Anchor < 0.30) and the current one are supported.
+//!
+//! Anchor prefixes instruction, account and event data with an 8-byte discriminator, followed by
+//! the borsh-serialized arguments (or fields).  The discriminators are read from the IDL when
+//! present, and otherwise derived the same way Anchor does (the first 8 bytes of
+//! `sha256("global:<snake_case_instruction_name>")`, `sha256("account:<AccountName>")` and
+//! `sha256("event:<EventName>")`).
+use std::{collections::HashMap, fs, path::Path};
+
+use borsh::BorshDeserialize;
+use log::{debug, warn};
+use serde_json::{Map, Value};
+
+use super::{DecodedAccount, DecodedEvent, DecodedInstruction, InstructionDecoder};
+
+/// The length of the discriminator prefixed to Anchor instruction and account data.
+const DISCRIMINATOR_LEN: usize = 8;
+
+/// An Anchor IDL, keeping only the parts used for decoding.
+#[derive(serde::Deserialize, Debug)]
+pub struct Idl {
+    /// The program id (current format).
+    #[serde(default)]
+    address: Option<String>,
+    /// The program name (legacy format).
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    metadata: Option<IdlMetadata>,
+    #[serde(default)]
+    instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    accounts: Vec<IdlAccountDef>,
+    #[serde(default)]
+    events: Vec<IdlEvent>,
+    #[serde(default)]
+    types: Vec<IdlTypeDef>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlMetadata {
+    /// The program id (legacy format).
+    #[serde(default)]
+    address: Option<String>,
+    /// The program name (current format).
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlInstruction {
+    name: String,
+    #[serde(default)]
+    discriminator: Option<Vec<u8>>,
+    #[serde(default)]
+    accounts: Vec<IdlInstructionAccount>,
+    #[serde(default)]
+    args: Vec<IdlField>,
+}
+
+/// An account of an instruction, or a named group of accounts.
+#[derive(serde::Deserialize, Debug)]
+struct IdlInstructionAccount {
+    name: String,
+    #[serde(default)]
+    accounts: Vec<IdlInstructionAccount>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlAccountDef {
+    name: String,
+    #[serde(default)]
+    discriminator: Option<Vec<u8>>,
+    /// The layout of the account (legacy format), otherwise it is found in `types`.
+    #[serde(default, rename = "type")]
+    ty: Option<IdlTypeDefTy>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlEvent {
+    name: String,
+    #[serde(default)]
+    discriminator: Option<Vec<u8>>,
+    /// The fields of the event (legacy format), otherwise they are found in `types`.
+    #[serde(default)]
+    fields: Option<Vec<IdlField>>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlTypeDef {
+    name: String,
+    #[serde(rename = "type")]
+    ty: IdlTypeDefTy,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum IdlTypeDefTy {
+    Struct {
+        #[serde(default)]
+        fields: Option<IdlDefinedFields>,
+    },
+    Enum {
+        variants: Vec<IdlEnumVariant>,
+    },
+    Alias {
+        value: IdlType,
+    },
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlEnumVariant {
+    name: String,
+    #[serde(default)]
+    fields: Option<IdlDefinedFields>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum IdlDefinedFields {
+    Named(Vec<IdlField>),
+    Tuple(Vec<IdlType>),
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct IdlField {
+    name: String,
+    #[serde(rename = "type")]
+    ty: IdlType,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum IdlType {
+    Primitive(String),
+    Vec { vec: Box<IdlType> },
+    Option { option: Box<IdlType> },
+    COption { coption: Box<IdlType> },
+    Array { array: (Box<IdlType>, usize) },
+    Defined { defined: IdlDefined },
+}
+
+/// A reference to a type in `types`, either by name (legacy format) or as an object (current format).
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum IdlDefined {
+    Name(String),
+    Named { name: String },
+}
+
+impl IdlDefined {
+    fn name(&self) -> &str {
+        match self {
+            IdlDefined::Name(name) => name,
+            IdlDefined::Named { name } => name,
+        }
+    }
+}
+
+/// converts a camelCase instruction name to snake_case, the way Anchor does when deriving
+/// discriminators (e.g. `setAMMConfig` becomes `set_amm_config`).
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// the first 8 bytes of the sha256 hash of `<namespace>:<name>`.
+fn sighash(namespace: &str, name: &str) -> [u8; DISCRIMINATOR_LEN] {
+    let hash = solana_sdk::hash::hash(format!("{}:{}", namespace, name).as_bytes());
+    hash.to_bytes()[..DISCRIMINATOR_LEN].try_into().unwrap()
+}
+
+/// reads the discriminator from the IDL, if it is there and well-formed.
+fn idl_discriminator(discriminator: &Option<Vec<u8>>) -> Option<[u8; DISCRIMINATOR_LEN]> {
+    discriminator
+        .as_deref()
+        .and_then(|discriminator| discriminator.try_into().ok())
+}
+
+/// flattens the (possibly nested) accounts of an instruction into a list of names.
+fn flatten_account_names(accounts: &[IdlInstructionAccount], names: &mut Vec<String>) {
+    for account in accounts {
+        if account.accounts.is_empty() {
+            names.push(account.name.to_owned());
+        } else {
+            flatten_account_names(&account.accounts, names);
+        }
+    }
+}
+
+/// Decodes the instructions and accounts of an Anchor program from its IDL.
+pub struct AnchorDecoder {
+    program_id: String,
+    program_name: String,
+    instructions: HashMap<[u8; DISCRIMINATOR_LEN], IdlInstruction>,
+    accounts: HashMap<[u8; DISCRIMINATOR_LEN], IdlAccountDef>,
+    events: HashMap<[u8; DISCRIMINATOR_LEN], IdlEvent>,
+    types: HashMap<String, IdlTypeDefTy>,
+}
+
+impl AnchorDecoder {
+    /// Creates a decoder from the IDL.  Returns `None` if the IDL does not contain the program id.
+    pub fn new(idl: Idl) -> Option<AnchorDecoder> {
+        let program_id = idl
+            .address
+            .or_else(|| idl.metadata.as_ref().and_then(|m| m.address.to_owned()))?;
+        let program_name = idl
+            .name
+            .or_else(|| idl.metadata.and_then(|m| m.name))
+            .unwrap_or_else(|| program_id.to_owned());
+
+        let instructions = idl
+            .instructions
+            .into_iter()
+            .map(|instruction| {
+                let discriminator = idl_discriminator(&instruction.discriminator)
+                    .unwrap_or_else(|| sighash("global", &to_snake_case(&instruction.name)));
+                (discriminator, instruction)
+            })
+            .collect();
+        let accounts = idl
+            .accounts
+            .into_iter()
+            .map(|account| {
+                let discriminator = idl_discriminator(&account.discriminator)
+                    .unwrap_or_else(|| sighash("account", &account.name));
+                (discriminator, account)
+            })
+            .collect();
+        let events = idl
+            .events
+            .into_iter()
+            .map(|event| {
+                let discriminator = idl_discriminator(&event.discriminator)
+                    .unwrap_or_else(|| sighash("event", &event.name));
+                (discriminator, event)
+            })
+            .collect();
+        let types = idl
+            .types
+            .into_iter()
+            .map(|type_def| (type_def.name, type_def.ty))
+            .collect();
+
+        Some(AnchorDecoder {
+            program_id,
+            program_name,
+            instructions,
+            accounts,
+            events,
+            types,
+        })
+    }
+
+    /// Reads the IDL from a JSON file.
+    pub fn from_file(path: &Path) -> Option<AnchorDecoder> {
+        let idl = fs::read(path)
+            .map_err(|e| warn!("could not read the IDL at {:?}: {:?}", path, e))
+            .ok()?;
+        let idl: Idl = serde_json::from_slice(&idl)
+            .map_err(|e| warn!("could not parse the IDL at {:?}: {:?}", path, e))
+            .ok()?;
+        let decoder = AnchorDecoder::new(idl);
+        if decoder.is_none() {
+            warn!("the IDL at {:?} does not have a program address", path);
+        }
+        decoder
+    }
+
+    /// deserializes a value of the IDL type from the buffer, advancing it.
+    fn decode_type(&self, ty: &IdlType, buf: &mut &[u8]) -> Option<Value> {
+        match ty {
+            IdlType::Primitive(primitive) => decode_primitive(primitive, buf),
+            IdlType::Vec { vec } => {
+                // the length comes from the data being decoded, so it is only trusted as far as
+                // the bytes left can hold that many elements
+                let len = u32::deserialize(buf).ok()? as usize;
+                if len > buf.len() {
+                    return None;
+                }
+                let mut values = Vec::with_capacity(len);
+                for _ in 0..len {
+                    let remaining = buf.len();
+                    values.push(self.decode_type(vec, buf)?);
+                    // an element that takes no bytes would let a forged length loop for nothing
+                    if buf.len() == remaining {
+                        return None;
+                    }
+                }
+                Some(Value::Array(values))
+            }
+            IdlType::Option { option } => match u8::deserialize(buf).ok()? {
+                0 => Some(Value::Null),
+                _ => self.decode_type(option, buf),
+            },
+            IdlType::COption { coption } => match u32::deserialize(buf).ok()? {
+                0 => {
+                    // a COption always takes up the space of its value
+                    let size = self.type_size(coption)?;
+                    *buf = buf.get(size..)?;
+                    Some(Value::Null)
+                }
+                _ => self.decode_type(coption, buf),
+            },
+            IdlType::Array { array: (ty, len) } => (0..*len)
+                .map(|_| self.decode_type(ty, buf))
+                .collect::<Option<Vec<Value>>>()
+                .map(Value::Array),
+            IdlType::Defined { defined } => {
+                self.decode_type_def(self.types.get(defined.name())?, buf)
+            }
+        }
+    }
+
+    /// deserializes a value of the defined type from the buffer, advancing it.
+    fn decode_type_def(&self, ty: &IdlTypeDefTy, buf: &mut &[u8]) -> Option<Value> {
+        match ty {
+            IdlTypeDefTy::Struct { fields } => self.decode_fields(fields.as_ref(), buf),
+            IdlTypeDefTy::Enum { variants } => {
+                let variant = variants.get(u8::deserialize(buf).ok()? as usize)?;
+                match &variant.fields {
+                    None => Some(Value::from(variant.name.to_owned())),
+                    Some(fields) => {
+                        let mut object = Map::new();
+                        object.insert(
+                            variant.name.to_owned(),
+                            self.decode_fields(Some(fields), buf)?,
+                        );
+                        Some(Value::Object(object))
+                    }
+                }
+            }
+            IdlTypeDefTy::Alias { value } => self.decode_type(value, buf),
+        }
+    }
+
+    /// deserializes named fields into an object, and tuple fields into an array.
+    fn decode_fields(&self, fields: Option<&IdlDefinedFields>, buf: &mut &[u8]) -> Option<Value> {
+        match fields {
+            None => Some(Value::Object(Map::new())),
+            Some(IdlDefinedFields::Named(fields)) => fields
+                .iter()
+                .map(|field| Some((field.name.to_owned(), self.decode_type(&field.ty, buf)?)))
+                .collect::<Option<Map<String, Value>>>()
+                .map(Value::Object),
+            Some(IdlDefinedFields::Tuple(types)) => types
+                .iter()
+                .map(|ty| self.decode_type(ty, buf))
+                .collect::<Option<Vec<Value>>>()
+                .map(Value::Array),
+        }
+    }
+
+    /// the serialized size of a fixed-size type, used to skip over empty `COption`s.
+    fn type_size(&self, ty: &IdlType) -> Option<usize> {
+        match ty {
+            IdlType::Primitive(primitive) => primitive_size(primitive),
+            IdlType::Array { array: (ty, len) } => Some(self.type_size(ty)? * len),
+            IdlType::Defined { defined } => match self.types.get(defined.name())? {
+                IdlTypeDefTy::Struct {
+                    fields: Some(IdlDefinedFields::Named(fields)),
+                } => fields.iter().map(|field| self.type_size(&field.ty)).sum(),
+                IdlTypeDefTy::Struct {
+                    fields: Some(IdlDefinedFields::Tuple(types)),
+                } => types.iter().map(|ty| self.type_size(ty)).sum(),
+                IdlTypeDefTy::Struct { fields: None } => Some(0),
+                IdlTypeDefTy::Alias { value } => self.type_size(value),
+                IdlTypeDefTy::Enum { .. } => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// the serialized size of a fixed-size primitive.
+fn primitive_size(primitive: &str) -> Option<usize> {
+    match primitive {
+        "bool" | "u8" | "i8" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        "u128" | "i128" => Some(16),
+        "publicKey" | "pubkey" => Some(32),
+        _ => None,
+    }
+}
+
+/// deserializes a borsh primitive from the buffer, advancing it.  128-bit integers are kept as
+/// strings since JSON numbers can't represent them.
+fn decode_primitive(primitive: &str, buf: &mut &[u8]) -> Option<Value> {
+    let value = match primitive {
+        "bool" => Value::from(bool::deserialize(buf).ok()?),
+        "u8" => Value::from(u8::deserialize(buf).ok()?),
+        "i8" => Value::from(i8::deserialize(buf).ok()?),
+        "u16" => Value::from(u16::deserialize(buf).ok()?),
+        "i16" => Value::from(i16::deserialize(buf).ok()?),
+        "u32" => Value::from(u32::deserialize(buf).ok()?),
+        "i32" => Value::from(i32::deserialize(buf).ok()?),
+        "u64" => Value::from(u64::deserialize(buf).ok()?),
+        "i64" => Value::from(i64::deserialize(buf).ok()?),
+        "u128" => Value::from(u128::deserialize(buf).ok()?.to_string()),
+        "i128" => Value::from(i128::deserialize(buf).ok()?.to_string()),
+        "f32" => Value::from(f32::deserialize(buf).ok()?),
+        "f64" => Value::from(f64::deserialize(buf).ok()?),
+        "string" => Value::from(String::deserialize(buf).ok()?),
+        "bytes" => {
+            Value::from(solana_sdk::bs58::encode(Vec::<u8>::deserialize(buf).ok()?).into_string())
+        }
+        "publicKey" | "pubkey" => {
+            Value::from(solana_sdk::bs58::encode(<[u8; 32]>::deserialize(buf).ok()?).into_string())
+        }
+        _ => {
+            debug!("unsupported IDL type: {}", primitive);
+            return None;
+        }
+    };
+    Some(value)
+}
+
+impl InstructionDecoder for AnchorDecoder {
+    fn program_id(&self) -> &str {
+        &self.program_id
+    }
+
+    fn program_name(&self) -> &str {
+        &self.program_name
+    }
+
+    fn decode(&self, data: &[u8], accounts: &[String]) -> Option<DecodedInstruction> {
+        let discriminator: [u8; DISCRIMINATOR_LEN] =
+            data.get(..DISCRIMINATOR_LEN)?.try_into().ok()?;
+        let instruction = self.instructions.get(&discriminator)?;
+
+        // the accounts are named by their position in the instruction
+        let mut account_names = Vec::new();
+        flatten_account_names(&instruction.accounts, &mut account_names);
+        let mut params: Vec<(String, Value)> = account_names
+            .into_iter()
+            .zip(accounts)
+            .map(|(name, pubkey)| (name, Value::from(pubkey.to_owned())))
+            .collect();
+
+        let mut buf = &data[DISCRIMINATOR_LEN..];
+        for arg in &instruction.args {
+            match self.decode_type(&arg.ty, &mut buf) {
+                Some(value) => params.push((arg.name.to_owned(), value)),
+                None => {
+                    warn!(
+                        "could not decode the {} argument of {}.{}",
+                        arg.name, self.program_name, instruction.name
+                    );
+                    break;
+                }
+            }
+        }
+
+        Some(DecodedInstruction {
+            instruction_type: instruction.name.to_owned(),
+            params,
+        })
+    }
+
+    fn decode_account(&self, data: &[u8]) -> Option<DecodedAccount> {
+        let discriminator: [u8; DISCRIMINATOR_LEN] =
+            data.get(..DISCRIMINATOR_LEN)?.try_into().ok()?;
+        let account = self.accounts.get(&discriminator)?;
+        let layout = account
+            .ty
+            .as_ref()
+            .or_else(|| self.types.get(&account.name))?;
+
+        let mut buf = &data[DISCRIMINATOR_LEN..];
+        let fields = self.decode_type_def(layout, &mut buf);
+        if fields.is_none() {
+            warn!(
+                "could not decode the {} account of {}",
+                account.name, self.program_name
+            );
+        }
+
+        Some(DecodedAccount {
+            account_type: account.name.to_owned(),
+            fields,
+        })
+    }
+
+    fn decode_event(&self, data: &[u8]) -> Option<DecodedEvent> {
+        let discriminator: [u8; DISCRIMINATOR_LEN] =
+            data.get(..DISCRIMINATOR_LEN)?.try_into().ok()?;
+        let event = self.events.get(&discriminator)?;
+
+        let mut buf = &data[DISCRIMINATOR_LEN..];
+        let fields = match &event.fields {
+            Some(fields) => fields
+                .iter()
+                .map(|field| {
+                    Some((
+                        field.name.to_owned(),
+                        self.decode_type(&field.ty, &mut buf)?,
+                    ))
+                })
+                .collect::<Option<Map<String, Value>>>()
+                .map(Value::Object),
+            None => self
+                .types
+                .get(&event.name)
+                .and_then(|layout| self.decode_type_def(layout, &mut buf)),
+        };
+        if fields.is_none() {
+            warn!(
+                "could not decode the {} event of {}",
+                event.name, self.program_name
+            );
+        }
+
+        Some(DecodedEvent {
+            event_type: event.name.to_owned(),
+            fields,
+        })
+    }
+}
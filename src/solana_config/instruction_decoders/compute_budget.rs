@@ -0,0 +1,117 @@
+//! Decoder for the [Compute Budget program](https://docs.solana.com/developing/programming-model/runtime#compute-budget),
+//! which sets the compute unit limit and priority fee of a transaction.
+use serde_json::Value;
+
+use super::{DecodedInstruction, InstructionDecoder};
+use crate::solana_config::constants;
+
+/// Decodes the instructions of the Compute Budget program.
+pub struct ComputeBudgetDecoder;
+
+/// The instructions of the Compute Budget program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComputeBudgetInstruction {
+    /// Deprecated, replaced by [SetComputeUnitLimit](ComputeBudgetInstruction::SetComputeUnitLimit)
+    /// and [SetComputeUnitPrice](ComputeBudgetInstruction::SetComputeUnitPrice).
+    RequestUnits {
+        units: u32,
+        additional_fee: u32,
+    },
+    RequestHeapFrame {
+        bytes: u32,
+    },
+    SetComputeUnitLimit {
+        units: u32,
+    },
+    SetComputeUnitPrice {
+        micro_lamports: u64,
+    },
+    SetLoadedAccountsDataSizeLimit {
+        bytes: u32,
+    },
+}
+
+/// reads a little-endian u32 at the offset of the data.
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// reads a little-endian u64 at the offset of the data.
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+impl ComputeBudgetInstruction {
+    /// Parses the (borsh-serialized) instruction data.  Returns `None` if the data is not recognised.
+    pub fn parse(data: &[u8]) -> Option<ComputeBudgetInstruction> {
+        let instruction = match data.first()? {
+            0 => ComputeBudgetInstruction::RequestUnits {
+                units: read_u32(data, 1)?,
+                additional_fee: read_u32(data, 5)?,
+            },
+            1 => ComputeBudgetInstruction::RequestHeapFrame {
+                bytes: read_u32(data, 1)?,
+            },
+            2 => ComputeBudgetInstruction::SetComputeUnitLimit {
+                units: read_u32(data, 1)?,
+            },
+            3 => ComputeBudgetInstruction::SetComputeUnitPrice {
+                micro_lamports: read_u64(data, 1)?,
+            },
+            4 => ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit {
+                bytes: read_u32(data, 1)?,
+            },
+            _ => return None,
+        };
+        Some(instruction)
+    }
+}
+
+impl InstructionDecoder for ComputeBudgetDecoder {
+    fn program_id(&self) -> &str {
+        constants::COMPUTE_BUDGET_PROGRAM_ID_STR
+    }
+
+    fn program_name(&self) -> &str {
+        "compute-budget"
+    }
+
+    fn decode(&self, data: &[u8], _accounts: &[String]) -> Option<DecodedInstruction> {
+        let (instruction_type, params) = match ComputeBudgetInstruction::parse(data)? {
+            ComputeBudgetInstruction::RequestUnits {
+                units,
+                additional_fee,
+            } => (
+                "requestUnits",
+                vec![
+                    ("units", Value::from(units)),
+                    ("additionalFee", Value::from(additional_fee)),
+                ],
+            ),
+            ComputeBudgetInstruction::RequestHeapFrame { bytes } => {
+                ("requestHeapFrame", vec![("bytes", Value::from(bytes))])
+            }
+            ComputeBudgetInstruction::SetComputeUnitLimit { units } => {
+                ("setComputeUnitLimit", vec![("units", Value::from(units))])
+            }
+            ComputeBudgetInstruction::SetComputeUnitPrice { micro_lamports } => (
+                "setComputeUnitPrice",
+                vec![("microLamports", Value::from(micro_lamports))],
+            ),
+            ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit { bytes } => (
+                "setLoadedAccountsDataSizeLimit",
+                vec![("bytes", Value::from(bytes))],
+            ),
+        };
+
+        Some(DecodedInstruction {
+            instruction_type: instruction_type.to_string(),
+            params: params
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        })
+    }
+}
@@ -0,0 +1,192 @@
+//! # Instruction decoders
+//! The RPC node's `jsonParsed` encoding only covers a handful of programs, the instructions of
+//! every other program are left as opaque base58 `data`.  An [InstructionDecoder] decodes the raw
+//! data of a single program into the instruction type and its parameters, which are used to fill
+//! the `instruction_type` and `params` columns of the instruction records.
+//!
+//! Decoders are looked up by program id in an [InstructionDecoderRegistry].  The registry comes
+//! with decoders for some common programs (see [InstructionDecoderRegistry::with_builtin_decoders]),
+//! and more can be added with [InstructionDecoderRegistry::register] before indexing starts.
+//! Anchor programs can be decoded without a code change, by placing their IDLs in the directory
+//! set by `ANCHOR_IDL_DIR` (see [anchor]).
+//!
+//! A decoder can also decode the data of the accounts owned by its program, which the RPC node
+//! returns base64-encoded when it can't parse them, and the events its program logs with
+//! `Program data:`.
+use std::{collections::HashMap, fs, path::Path};
+
+use base64::Engine;
+use log::{debug, info, warn};
+use serde_json::Value;
+
+use super::proto_codegen::confirmed_block::InnerInstruction;
+use crate::config::Config;
+
+pub mod anchor;
+pub mod compute_budget;
+pub mod token_metadata;
+
+/// An instruction decoded by an [InstructionDecoder].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedInstruction {
+    /// The name of the instruction (e.g. `"setComputeUnitPrice"`)
+    pub instruction_type: String,
+    /// The parameters of the instruction, as key-value pairs.
+    pub params: Vec<(String, Value)>,
+}
+
+/// An account decoded by an [InstructionDecoder].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedAccount {
+    /// The type of the account (e.g. the name of the Anchor account struct)
+    pub account_type: String,
+    /// The decoded fields of the account, or `None` if only the type could be determined.
+    pub fields: Option<Value>,
+}
+
+/// An event decoded by an [InstructionDecoder].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedEvent {
+    /// The name of the event (e.g. the name of the Anchor event struct)
+    pub event_type: String,
+    /// The decoded fields of the event, or `None` if only the type could be determined.
+    pub fields: Option<Value>,
+}
+
+/// Decodes the raw instruction data of a single program.
+pub trait InstructionDecoder: Send + Sync {
+    /// The base58 id of the program whose instructions this decoder understands.
+    fn program_id(&self) -> &str;
+
+    /// The name of the program, used for the `program` column when the RPC node did not provide one.
+    fn program_name(&self) -> &str;
+
+    /// Decodes the instruction data (already decoded from base58) along with the instruction's
+    /// accounts.  Returns `None` if the data is not recognised.
+    fn decode(&self, data: &[u8], accounts: &[String]) -> Option<DecodedInstruction>;
+
+    /// Decodes the data of an account owned by the program.  Returns `None` if the data is not
+    /// recognised, which is the default for decoders that only understand instructions.
+    fn decode_account(&self, _data: &[u8]) -> Option<DecodedAccount> {
+        None
+    }
+
+    /// Decodes the data of an event logged by the program.  Returns `None` if the data is not
+    /// recognised, which is the default for decoders that only understand instructions.
+    fn decode_event(&self, _data: &[u8]) -> Option<DecodedEvent> {
+        None
+    }
+}
+
+/// A collection of [InstructionDecoder]s keyed by program id.
+#[derive(Default)]
+pub struct InstructionDecoderRegistry {
+    decoders: HashMap<String, Box<dyn InstructionDecoder>>,
+}
+
+impl InstructionDecoderRegistry {
+    /// Creates a registry without any decoders.
+    pub fn new() -> InstructionDecoderRegistry {
+        InstructionDecoderRegistry::default()
+    }
+
+    /// Creates a registry with the built-in decoders registered.
+    pub fn with_builtin_decoders() -> InstructionDecoderRegistry {
+        let mut registry = InstructionDecoderRegistry::new();
+        registry.register(Box::new(compute_budget::ComputeBudgetDecoder));
+        registry.register(Box::new(token_metadata::TokenMetadataDecoder));
+        registry
+    }
+
+    /// Creates a registry with the built-in decoders, along with a decoder for each of the Anchor
+    /// IDLs in the `ANCHOR_IDL_DIR` directory.
+    pub fn from_config(config: &Config) -> InstructionDecoderRegistry {
+        let mut registry = InstructionDecoderRegistry::with_builtin_decoders();
+        // env:anchor_idl_dir = A directory of Anchor IDL JSON files used to decode their programs
+        if let Some(dir) = &config.anchor_idl_dir {
+            registry.load_anchor_idls(Path::new(dir));
+        }
+        registry
+    }
+
+    /// Registers a decoder for each of the Anchor IDL (`.json`) files in the directory.
+    /// IDLs that can't be read are skipped, as is the whole directory if it can't be read.
+    pub fn load_anchor_idls(&mut self, dir: &Path) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("could not read the IDL directory {:?}: {:?}", dir, e);
+                return;
+            }
+        };
+        let mut loaded = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                if let Some(decoder) = anchor::AnchorDecoder::from_file(&path) {
+                    info!(
+                        "Loaded the IDL of {} ({})",
+                        decoder.program_name(),
+                        decoder.program_id()
+                    );
+                    self.register(Box::new(decoder));
+                    loaded += 1;
+                }
+            }
+        }
+        info!("Loaded {} Anchor IDLs from {:?}", loaded, dir);
+    }
+
+    /// Registers a decoder, replacing any decoder previously registered for the same program id.
+    pub fn register(&mut self, decoder: Box<dyn InstructionDecoder>) {
+        self.decoders
+            .insert(decoder.program_id().to_string(), decoder);
+    }
+
+    /// Returns the decoder registered for the program id, if any.
+    pub fn get(&self, program_id: &str) -> Option<&dyn InstructionDecoder> {
+        self.decoders
+            .get(program_id)
+            .map(|decoder| decoder.as_ref())
+    }
+
+    /// Decodes the instruction with the decoder registered for its program, returning the name of
+    /// the program along with the decoded instruction.  Returns `None` if there is no decoder for
+    /// the program or the instruction could not be decoded.
+    pub fn decode(&self, instruction: &InnerInstruction) -> Option<(&str, DecodedInstruction)> {
+        let decoder = self.get(instruction.program_id.as_deref()?)?;
+        let data = match solana_sdk::bs58::decode(instruction.data.as_deref()?).into_vec() {
+            Ok(data) => data,
+            Err(e) => {
+                debug!("instruction data is not valid base58: {:?}", e);
+                return None;
+            }
+        };
+        decoder
+            .decode(&data, &instruction.accounts)
+            .map(|decoded| (decoder.program_name(), decoded))
+    }
+
+    /// Decodes the base64 data of an account with the decoder registered for its owner.
+    /// Returns `None` if there is no decoder for the owner or the data could not be decoded.
+    pub fn decode_account(&self, owner: &str, data: &str) -> Option<DecodedAccount> {
+        let decoder = self.get(owner)?;
+        let data = match base64::engine::general_purpose::STANDARD.decode(data) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("account data is not valid base64: {:?}", e);
+                return None;
+            }
+        };
+        decoder.decode_account(&data)
+    }
+
+    /// Decodes the data of an event with the decoder registered for the program that logged it.
+    /// Returns `None` if there is no decoder for the program or the event could not be decoded.
+    pub fn decode_event(&self, program_id: &str, data: &[u8]) -> Option<DecodedEvent> {
+        self.get(program_id)?.decode_event(data)
+    }
+}
@@ -0,0 +1,149 @@
+//! Decoder for the [Metaplex Token Metadata program](https://developers.metaplex.com/token-metadata).
+//! Only the instruction type is decoded; the Borsh-encoded arguments are not.
+use super::{DecodedInstruction, InstructionDecoder};
+use crate::solana_config::constants;
+
+/// Decodes the instruction types of the Metaplex Token Metadata program.
+pub struct TokenMetadataDecoder;
+
+/// The names of the single-variant instructions, indexed by their discriminator.
+const INSTRUCTIONS: [&str; 41] = [
+    "createMetadataAccount",
+    "updateMetadataAccount",
+    "deprecatedCreateMasterEdition",
+    "deprecatedMintNewEditionFromMasterEditionViaPrintingToken",
+    "updatePrimarySaleHappenedViaToken",
+    "deprecatedSetReservationList",
+    "deprecatedCreateReservationList",
+    "signMetadata",
+    "deprecatedMintPrintingTokensViaToken",
+    "deprecatedMintPrintingTokens",
+    "createMasterEdition",
+    "mintNewEditionFromMasterEditionViaToken",
+    "convertMasterEditionV1ToV2",
+    "mintNewEditionFromMasterEditionViaVaultProxy",
+    "puffMetadata",
+    "updateMetadataAccountV2",
+    "createMetadataAccountV2",
+    "createMasterEditionV3",
+    "verifyCollection",
+    "utilize",
+    "approveUseAuthority",
+    "revokeUseAuthority",
+    "unverifyCollection",
+    "approveCollectionAuthority",
+    "revokeCollectionAuthority",
+    "setAndVerifyCollection",
+    "freezeDelegatedAccount",
+    "thawDelegatedAccount",
+    "removeCreatorVerification",
+    "burnNft",
+    "verifySizedCollectionItem",
+    "unverifySizedCollectionItem",
+    "setAndVerifySizedCollectionItem",
+    "createMetadataAccountV3",
+    "setCollectionSize",
+    "setTokenStandard",
+    "bubblegumSetCollectionSize",
+    "burnEditionNft",
+    "createEscrowAccount",
+    "closeEscrowAccount",
+    "transferOutOfEscrow",
+];
+
+/// The variants of the `delegate` instruction (discriminator 44).
+const DELEGATE_VARIANTS: [&str; 14] = [
+    "delegateCollectionV1",
+    "delegateSaleV1",
+    "delegateTransferV1",
+    "delegateDataV1",
+    "delegateUtilityV1",
+    "delegateStakingV1",
+    "delegateStandardV1",
+    "delegateLockedTransferV1",
+    "delegateProgrammableConfigV1",
+    "delegateAuthorityItemV1",
+    "delegateDataItemV1",
+    "delegateCollectionItemV1",
+    "delegateProgrammableConfigItemV1",
+    "delegatePrintDelegateV1",
+];
+
+/// The variants of the `revoke` instruction (discriminator 45).
+const REVOKE_VARIANTS: [&str; 15] = [
+    "revokeCollectionV1",
+    "revokeSaleV1",
+    "revokeTransferV1",
+    "revokeDataV1",
+    "revokeUtilityV1",
+    "revokeStakingV1",
+    "revokeStandardV1",
+    "revokeLockedTransferV1",
+    "revokeProgrammableConfigV1",
+    "revokeMigrationV1",
+    "revokeAuthorityItemV1",
+    "revokeDataItemV1",
+    "revokeCollectionItemV1",
+    "revokeProgrammableConfigItemV1",
+    "revokePrintDelegateV1",
+];
+
+/// The variants of the `update` instruction (discriminator 50).
+const UPDATE_VARIANTS: [&str; 9] = [
+    "updateV1",
+    "updateAsUpdateAuthorityV2",
+    "updateAsAuthorityItemDelegateV2",
+    "updateAsCollectionDelegateV2",
+    "updateAsDataDelegateV2",
+    "updateAsProgrammableConfigDelegateV2",
+    "updateAsDataItemDelegateV2",
+    "updateAsCollectionItemDelegateV2",
+    "updateAsProgrammableConfigItemDelegateV2",
+];
+
+/// returns the name of the instruction with the discriminator and (for the versioned
+/// instructions) the variant that follows it.
+fn instruction_name(discriminator: u8, variant: Option<u8>) -> Option<&'static str> {
+    let variant = variant.map(usize::from);
+    match discriminator {
+        0..=40 => INSTRUCTIONS.get(discriminator as usize).copied(),
+        41 => Some("burnV1"),
+        42 => Some("createV1"),
+        43 => Some("mintV1"),
+        44 => DELEGATE_VARIANTS.get(variant?).copied(),
+        45 => REVOKE_VARIANTS.get(variant?).copied(),
+        46 => Some("lockV1"),
+        47 => Some("unlockV1"),
+        48 => Some("migrate"),
+        49 => Some("transferV1"),
+        50 => UPDATE_VARIANTS.get(variant?).copied(),
+        51 => Some("useV1"),
+        52 => ["verifyCreatorV1", "verifyCollectionV1"]
+            .get(variant?)
+            .copied(),
+        53 => ["unverifyCreatorV1", "unverifyCollectionV1"]
+            .get(variant?)
+            .copied(),
+        54 => Some("collect"),
+        55 => ["printV1", "printV2"].get(variant?).copied(),
+        _ => None,
+    }
+}
+
+impl InstructionDecoder for TokenMetadataDecoder {
+    fn program_id(&self) -> &str {
+        constants::METADATA_PROGRAM_ID_STR
+    }
+
+    fn program_name(&self) -> &str {
+        "mpl-token-metadata"
+    }
+
+    fn decode(&self, data: &[u8], _accounts: &[String]) -> Option<DecodedInstruction> {
+        let name = instruction_name(*data.first()?, data.get(1).copied())?;
+        Some(DecodedInstruction {
+            instruction_type: name.to_string(),
+            params: Vec::new(),
+        })
+    }
+}
@@ -2,33 +2,50 @@
 //! the Solana network.
 use prost::Message as prost_message;
 use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     error::Error,
-    fs::{remove_file, File},
-    path::Path,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::Arc,
     time,
 };
 
-use futures::future::join_all;
-use log::{debug, error, info};
-use tokio::time::sleep;
+use futures::{future::join_all, FutureExt};
+use log::{debug, error, info, warn};
+use tokio::{
+    sync::{oneshot, Semaphore},
+    time::sleep,
+};
 
-use super::accounts::{self, call_getMultipleAccounts, KeyedTimestampedAccounts};
+use super::accounts::{
+    self, call_getMultipleAccounts, ContextualAccountInfoResponse, KeyedTimestampedAccounts,
+};
+use super::instruction_decoders::InstructionDecoderRegistry;
+use super::pipeline::{
+    send_within_budget, FetchedBlock, InFlightBlock, PipelineConfig, TransformedBlock,
+};
 use super::proto_codegen::{
-    account_info as solana_account_protobuf, account_info::Token, confirmed_block::UnixTimestamp,
-    etl_block,
+    account_info as solana_account_protobuf, account_info::Token, etl_block,
 };
-use super::proto_conversions::{account::PackagedAccount, block::parsed_block_to_proto};
+use super::proto_conversions::account::PackagedAccount;
+use super::reorder::{ReorderBuffer, ReorderConfig, StuckSlotError, StuckSlotPolicy};
+use super::token_cache::TokenCache;
 use super::tokens;
-use super::transactions::{self, TransactionAccounts};
+use super::transactions::{self, AccountDiscovery, TransactionAccounts};
 use super::types::account_response_types::AccountDataEnumResponse;
 
+use super::constants::MAX_GET_BLOCKS_RANGE;
 use crate as blockchain_generic;
 use blockchain_generic::{
-    constants::RANGE_SIZE, metrics::Metrics, request, source::config::RequestConfig,
+    checkpoint::{CheckpointStore, SlotStatus},
+    config::Config,
+    constants::RANGE_SIZE,
+    dead_letter::{panic_message, DeadLetter, DeadLetterSink, FailedStage},
+    metrics::Metrics,
+    output::publish::StreamPublisher,
+    request,
+    shutdown::Shutdown,
+    source::config::{RequestConfig, RpcFallback},
 };
 
 #[cfg(feature = "SOLANA_BIGTABLE")]
@@ -37,27 +54,42 @@ use super::data_sources::bigtable;
 #[cfg(not(feature = "SOLANA_BIGTABLE"))]
 use super::data_sources::json_rpc::get_recent_block;
 
-#[cfg(feature = "SEPARATE_PUBLISHERS")]
-use blockchain_generic::solana_config::transformation;
-
-/// Given KeyedTimestampedAccounts and a request builder, ,
-async fn get_accounts_and_tokens_from_pubkeys(
+/// Given the KeyedTimestampedAccounts of an entire block and a request builder, returns the
+/// token metadata of every mint account.  Tokens found in the token cache are reused, and the
+/// rest are requested at once and added to the cache.  The tokens are returned keyed by their
+/// mint, so that they can be fanned back out to each transaction.
+async fn get_tokens_by_mint(
     keyed_accounts: KeyedTimestampedAccounts,
+    min_context_slot: Option<u64>,
+    token_cache: &TokenCache,
     request_config: RequestConfig,
     metrics: Option<Metrics>,
-) -> (Vec<PackagedAccount>, Vec<Token>) {
-    // each value is a (pubkey, is_nft)
-    let token_accounts = tokens::get_tokens_from_mint_accounts(keyed_accounts.clone());
-    let accounts_packaged = accounts::package_accounts(keyed_accounts);
+) -> HashMap<String, Token> {
+    // the same mint may be created or touched by multiple transactions.
+    let mut token_accounts = tokens::get_tokens_from_mint_accounts(keyed_accounts);
+    let mut seen_mints = HashSet::new();
+    token_accounts.retain(|token| seen_mints.insert(token.mint.clone()));
+
+    let mut tokens_packaged = HashMap::new();
+    // only the mints that are not already cached need to be requested
+    token_accounts.retain(|token| match token_cache.get(&token.mint) {
+        Some(cached) => {
+            tokens_packaged.insert(token.mint.clone(), cached);
+            false
+        }
+        None => true,
+    });
+    if let Some(m) = &metrics {
+        m.token_cache_hit_count.inc_by(tokens_packaged.len() as u64);
+        m.token_cache_miss_count.inc_by(token_accounts.len() as u64);
+    }
 
-    let mut tokens_packaged = Vec::new();
     if !token_accounts.is_empty() {
         info!("Requesting token data");
-        // Extract pubkeys from token account vector
+        // Extract the metadata account pubkeys from token account vector
         let token_pubkeys: Vec<String> = token_accounts
-            .clone()
-            .into_iter()
-            .map(|token| token.mint)
+            .iter()
+            .map(|token| token.metadata_account.clone())
             .collect();
 
         // Extract the request builder
@@ -65,34 +97,45 @@ async fn get_accounts_and_tokens_from_pubkeys(
 
         // Get account data from pubkeys
         let token_accounts_opt =
-            call_getMultipleAccounts(request_builder, token_pubkeys, metrics).await;
+            call_getMultipleAccounts(request_builder, token_pubkeys, min_context_slot, metrics)
+                .await;
 
-        // If there are tokens, start extracting the values and placing them in the tokens_packaged vector
+        // If there are tokens, start extracting the values and placing them in the tokens_packaged map
         if let Some(token_accounts_response) = token_accounts_opt {
-            // Extract data and add it to the token_data vector
+            // Extract data and add it to the token_data map
             let token_retrieval_timestamp = token_accounts_response.timestamp;
-            for (i, account_info) in token_accounts_response
-                .accounts
-                .value
-                .into_iter()
-                .enumerate()
-            {
-                // Place the token data into the tokens_packaged vector
-                if let Some(a) = account_info {
-                    match &a.data {
-                        AccountDataEnumResponse::Array(strings) => {
-                            if strings[0] == "AA==" {
-                                info!("Empty token data. Skipping...");
-                                continue;
-                            }
+            for (i, account_info) in token_accounts_response.accounts.into_iter().enumerate() {
+                // Place the token data into the tokens_packaged map
+                if let Some(ContextualAccountInfoResponse {
+                    context_slot: token_context_slot,
+                    accounts_data: a,
+                }) = account_info
+                {
+                    if let AccountDataEnumResponse::Array(strings) = &a.data {
+                        if strings.first().is_some_and(|data| data == "AA==") {
+                            info!("Empty token data. Skipping...");
+                            continue;
                         }
-                        _ => panic!("Unexpected token data shape"),
                     }
                     let is_nft = token_accounts[i].is_nft;
 
-                    let token_data =
-                        tokens::unpack_token_account(a.data, is_nft, token_retrieval_timestamp);
-                    tokens_packaged.push(token_data);
+                    // a metadata account that can't be decoded only leaves its token out
+                    match tokens::unpack_token_account(
+                        a.data,
+                        is_nft,
+                        token_retrieval_timestamp,
+                        token_context_slot,
+                    ) {
+                        Ok(token_data) => {
+                            token_cache.insert(token_accounts[i].mint.clone(), token_data.clone());
+                            tokens_packaged.insert(token_accounts[i].mint.clone(), token_data);
+                        }
+                        Err(error) => warn!(
+                            "Skipping the token of mint {}: {}",
+                            token_accounts[i].mint,
+                            error.for_account(&token_accounts[i].metadata_account)
+                        ),
+                    }
                 }
             }
         };
@@ -100,86 +143,163 @@ async fn get_accounts_and_tokens_from_pubkeys(
         info!("No tokens to request.");
     }
 
-    (accounts_packaged, tokens_packaged)
+    tokens_packaged
+}
+
+/// Given the created accounts of each transaction in a block and a request builder, requests the
+/// account data and token data for the whole block at once.  The pubkeys are deduplicated across
+/// transactions and requested concurrently, then the results are fanned back out to each transaction.
+/// If `min_context_slot` is set, the RPC node will not return account state older than that slot.
+async fn get_accounts_and_tokens_for_block(
+    all_account_pubkeys: Vec<TransactionAccounts>,
+    min_context_slot: Option<u64>,
+    token_cache: &TokenCache,
+    request_config: RequestConfig,
+    metrics: Option<Metrics>,
+) -> Vec<(String, Vec<PackagedAccount>, Vec<Token>)> {
+    let unique_pubkeys: Vec<String> = {
+        let mut seen_pubkeys = HashSet::new();
+        all_account_pubkeys
+            .iter()
+            .flat_map(|transaction_accounts| transaction_accounts.accounts.iter())
+            .filter(|pubkey| seen_pubkeys.insert(pubkey.as_str()))
+            .cloned()
+            .collect()
+    };
+    if unique_pubkeys.is_empty() {
+        return Vec::new();
+    }
+
+    let timestamped_accounts_data = call_getMultipleAccounts(
+        request_config.try_clone().unwrap(),
+        unique_pubkeys.clone(),
+        min_context_slot,
+        metrics.clone(),
+    )
+    .await;
+
+    let block_accounts = match timestamped_accounts_data {
+        Some(accounts_inner) => {
+            KeyedTimestampedAccounts::from_keys_and_accounts(unique_pubkeys, accounts_inner)
+        }
+        None => {
+            info!("No accounts found");
+            return Vec::new();
+        }
+    };
+
+    let tokens_by_mint = get_tokens_by_mint(
+        block_accounts.clone(),
+        min_context_slot,
+        token_cache,
+        request_config,
+        metrics,
+    )
+    .await;
+
+    let accounts_by_pubkey = block_accounts.by_pubkey();
+    all_account_pubkeys
+        .into_iter()
+        .map(
+            |TransactionAccounts {
+                 tx_signature,
+                 accounts,
+                 ..
+             }| {
+                let keyed_accounts = block_accounts.select_pubkeys(&accounts_by_pubkey, &accounts);
+
+                // this is the full account data and full token data associated with the current transaction
+                let mut tokens: Vec<Token> =
+                    tokens::get_tokens_from_mint_accounts(keyed_accounts.clone())
+                        .into_iter()
+                        .filter_map(|token| tokens_by_mint.get(&token.mint).cloned())
+                        .collect();
+                // Token-2022 mints may also carry their metadata on-chain
+                tokens.extend(tokens::get_tokens_from_metadata_extensions(&keyed_accounts));
+                let accounts = accounts::package_accounts(keyed_accounts);
+
+                (tx_signature, accounts, tokens) // the timestamps are stored within the accounts
+            },
+        )
+        .collect()
 }
 
-/// this function is expected to be run by multiple threads.
-/// each instance pulls a slot from the concurrent queue, makes the block request, processes the response, and sends it to the pub/sub middleware for transformation and insertion.
+/// Given the created accounts of each transaction in a block, packages each account from the
+/// `createAccount` instruction that created it, without making any RPC calls.  The accounts
+/// describe their initial state at the block's slot rather than their current state, and no
+/// token data is retrieved.  Accounts that weren't created by the system program, e.g. those
+/// only initialized by the token program, are left out since their initial state is unknown.
+fn get_accounts_from_create_instructions(
+    all_account_pubkeys: Vec<TransactionAccounts>,
+    slot: u64,
+) -> Vec<(String, Vec<PackagedAccount>, Vec<Token>)> {
+    let timestamp = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .expect("time only moves forward")
+        .as_secs();
+
+    all_account_pubkeys
+        .into_iter()
+        .map(|transaction_accounts| {
+            let accounts = transaction_accounts
+                .created_accounts
+                .into_iter()
+                .map(|created_account| {
+                    PackagedAccount::new_created_type(
+                        timestamp,
+                        slot,
+                        created_account.pubkey,
+                        created_account.lamports,
+                        created_account.owner,
+                        created_account.space as i64,
+                    )
+                })
+                .collect();
+            (transaction_accounts.tx_signature, accounts, Vec::new())
+        })
+        .collect()
+}
+
+/// this function is expected to be run by multiple threads, and makes up the fetch stage.
+/// each instance pulls a slot from the concurrent queue, makes the block request, processes the response, and sends it to the transform stage.
+/// each block takes its share of the byte budget before it is sent, which it gives back once it is published.
+/// a slot that fails is recorded as a dead letter, and the worker moves on to the next one.
+#[allow(clippy::too_many_arguments)]
 async fn process_block_queue_stream(
+    config: Arc<Config>,
     _bigtable: Option<solana_storage_bigtable::LedgerStorage>,
-    publisher: blockchain_generic::output::publish::StreamPublisher,
+    fetched_sender: async_channel::Sender<InFlightBlock<etl_block::EtlBlock>>,
     thread_queue: async_channel::Receiver<u64>,
+    token_cache: Arc<TokenCache>,
+    pipeline_config: PipelineConfig,
+    byte_budget: Arc<Semaphore>,
+    checkpoints: Arc<CheckpointStore>,
+    dead_letters: Arc<DeadLetterSink>,
     metrics: Option<Metrics>,
 ) -> Result<(), Box<dyn Error>> {
     // NOTE: reqwest clients are not thread-safe, so we create a new one here
     let request_builder = {
-        let endpoint = dotenvy::var("ENDPOINT")
-            .expect("ENDPOINT should exist in .env file")
-            .parse::<String>()
-            .unwrap();
+        let endpoint = config.endpoint.clone().expect("ENDPOINT is validated");
         let connection_timeout =
             std::time::Duration::from_secs(blockchain_generic::constants::CONNECTION_TIMEOUT);
         let client_builder = reqwest::Client::builder().connect_timeout(connection_timeout);
 
-        let headers = request::get_headers();
+        let headers = request::get_headers(config.gcp_api_key.as_deref());
         let client = client_builder.build().unwrap();
         client.post(endpoint).headers(headers)
     };
     // Creates the request config
-    let request_config: RequestConfig = RequestConfig::ReqBldr(request_builder);
+    let request_config: RequestConfig =
+        RequestConfig::ReqBldr(request_builder, RpcFallback::from_config(&config));
 
-    let indexed_blocks_dir = Path::new("./indexed_blocks/");
+    // env:accounts_from_create_instruction = Whether to take the accounts' initial state from the
+    // createAccount instructions instead of requesting their current state from the RPC node
+    let accounts_from_create_instruction = config.accounts_from_create_instruction.unwrap_or(false);
+    // env:accounts_min_context_slot = Whether to use the block's slot as the minContextSlot of account requests
+    let accounts_min_context_slot = config.accounts_min_context_slot.unwrap_or(false);
+    let account_discovery = AccountDiscovery::from_config(&config);
 
     debug!("starting thread...");
-    // Extract the publisher(s)
-    #[cfg(feature = "SINGLE_PUBLISHER")]
-    let all_publisher = publisher.all;
-    #[cfg(feature = "SEPARATE_PUBLISHERS")]
-    let (
-        blocks_publisher,
-        block_rewards_publisher,
-        transactions_publisher,
-        instructions_publisher,
-        token_transfers_publisher,
-        tokens_publisher,
-        accounts_publisher,
-    ) = (
-        publisher.blocks,
-        publisher.block_rewards,
-        publisher.transactions,
-        publisher.instructions,
-        publisher.token_transfers,
-        publisher.tokens,
-        publisher.accounts,
-    );
-
-    // Creates the channel(s) for this thread (Rabbitmq-Classic specific)
-    // RabbitMQ Classic requires that we construct "channel" instances in the thread
-    // we intend to publish in.  Because of this, we need to recreate with the channel
-    // here.
-    #[cfg(feature = "SINGLE_PUBLISHER")]
-    #[cfg(feature = "RABBITMQ_CLASSIC")]
-    let all_publisher = all_publisher.with_channel().await;
-    #[cfg(feature = "SEPARATE_PUBLISHERS")]
-    #[cfg(feature = "RABBITMQ_CLASSIC")]
-    let (
-        blocks_publisher,
-        block_rewards_publisher,
-        transactions_publisher,
-        instructions_publisher,
-        token_transfers_publisher,
-        tokens_publisher,
-        accounts_publisher,
-    ) = (
-        blocks_publisher.with_channel().await,
-        block_rewards_publisher.with_channel().await,
-        transactions_publisher.with_channel().await,
-        instructions_publisher.with_channel().await,
-        token_transfers_publisher.with_channel().await,
-        tokens_publisher.with_channel().await,
-        accounts_publisher.with_channel().await,
-    );
-
     #[cfg(feature = "SOLANA_BIGTABLE")]
     let bigtable = _bigtable.unwrap();
 
@@ -188,9 +308,8 @@ async fn process_block_queue_stream(
             each slot is used to request a block,
             then the account pubkeys are used to request accounts data.
             account data is also requested for any token mints.
-            all of the above is packaged into a pub/sub message for easy transformation and insertion.
+            all of the above is packaged into a block for the transform stage.
     */
-    let mut prev_slot = None;
     loop {
         let slot: u64 = match thread_queue.recv().await {
             Ok(rec_i) => rec_i,
@@ -200,217 +319,599 @@ async fn process_block_queue_stream(
             }
         };
 
-        // Delete the previous indexing log, and create a new one for the current slot.
-        // NOTE: we do this before actually processing the block, in case the slot doesn't have a block and this iteration gets skipped.
-        {
-            if let Some(prev) = prev_slot {
-                let file_path = indexed_blocks_dir.join(format!("{}", prev));
-                match remove_file(file_path) {
-                    Ok(_) => info!("Successfully deleted the file"),
-                    Err(e) => panic!("FATAL: failed to delete the file: {:?}", e),
+        info!("received block task: {}", slot);
+
+        // a panic while fetching the slot is caught, so that the worker survives it
+        let fetched = AssertUnwindSafe(async {
+            #[cfg(feature = "SOLANA_BIGTABLE")]
+            let parsed_block = {
+                let confirmed_block =
+                    bigtable::call_get_confirmed_block(&bigtable, &config, slot).await;
+                match confirmed_block {
+                    Err(_) => {
+                        // only happens when there is no block at the slot
+                        checkpoints.record(slot, SlotStatus::Skipped);
+                        return Ok(None);
+                    }
+                    Ok(block) => bigtable::parse_block(block).into(),
                 }
-            }
+            };
 
-            let file_path = indexed_blocks_dir.join(format!("{}", slot));
+            #[cfg(not(feature = "SOLANA_BIGTABLE"))]
+            let parsed_block = {
+                let result =
+                    get_recent_block(request_config.try_clone().unwrap(), slot, metrics.clone())
+                        .await;
+                match result {
+                    None => {
+                        // only happens for skipped slots. safe to move past.
+                        checkpoints.record(slot, SlotStatus::Skipped);
+                        return Ok(None);
+                    }
+                    Some(b) => b,
+                }
+            };
+            checkpoints.record(slot, SlotStatus::Fetched);
 
-            match File::create(file_path) {
-                Ok(_) => info!("Successfully created the file"),
-                Err(e) => panic!("FATAL: failed to create the file: {:?}", e),
-            }
-            prev_slot = Some(slot);
-        }
+            // get the account public keys so that we can call the RPC method getMultipleAccounts()
+            let transactions = transactions::get_transactions_from_block(&parsed_block.block);
+            let all_account_pubkeys =
+                transactions::get_pubkeys_from_transactions(transactions, account_discovery);
+            let packed_accounts = if accounts_from_create_instruction {
+                get_accounts_from_create_instructions(all_account_pubkeys, slot)
+            } else {
+                get_accounts_and_tokens_for_block(
+                    all_account_pubkeys,
+                    accounts_min_context_slot.then_some(slot),
+                    &token_cache,
+                    request_config.try_clone().unwrap(),
+                    metrics.clone(),
+                )
+                .await
+            };
 
-        info!("received block task: {}", slot);
+            let all_accounts_and_tokens = packed_accounts
+                .into_iter()
+                .map(|(tx_signature, accounts, tokens)| {
+                    solana_account_protobuf::AccountInfo::new(tx_signature, accounts, tokens)
+                })
+                .collect();
 
-        #[cfg(feature = "SOLANA_BIGTABLE")]
-        let parsed_block = {
-            let confirmed_block = bigtable::call_get_confirmed_block(&bigtable, slot).await;
-            match confirmed_block {
-                Err(_) => continue, // only happens when there is no block at the slot
-                Ok(block) => bigtable::parse_block(block),
+            // a block that fails to be converted is kept, so that its conversion can be replayed
+            let fetched_block = FetchedBlock {
+                block: parsed_block,
+                accounts: all_accounts_and_tokens,
+            };
+            match fetched_block.to_etl_block(slot) {
+                Ok(packed_block) => Ok(Some(packed_block)),
+                Err(error) => Err(DeadLetter::new(
+                    slot,
+                    FailedStage::Convert,
+                    error.to_string(),
+                    Some(&fetched_block.to_dead_letter_input()),
+                )),
             }
-        };
-
-        #[cfg(not(feature = "SOLANA_BIGTABLE"))]
-        let parsed_block = {
-            let result =
-                get_recent_block(request_config.try_clone().unwrap(), slot, metrics.clone()).await;
-            match result {
-                None => continue, // only happens for skipped slots. safe to move past.
-                Some(b) => b,
+        })
+        .catch_unwind()
+        .await;
+        let packed_block = match fetched {
+            Ok(Ok(Some(packed_block))) => packed_block,
+            // the slot has no block, or failed
+            fetched => {
+                let letter = match fetched {
+                    Ok(Err(letter)) => Some(letter),
+                    Err(panic) => Some(DeadLetter::new(
+                        slot,
+                        FailedStage::Fetch,
+                        panic_message(&*panic),
+                        None,
+                    )),
+                    Ok(Ok(_)) => None,
+                };
+                if let Some(letter) = letter {
+                    dead_letters.record(&letter);
+                    checkpoints.record(slot, SlotStatus::Failed);
+                }
+                // the slot is still passed on, so that the reorder stage doesn't wait on it
+                fetched_sender
+                    .send(InFlightBlock {
+                        slot,
+                        block: None,
+                        permit: None,
+                    })
+                    .await
+                    .expect("transform stage is running");
+                continue;
             }
         };
 
-        let block_hash = parsed_block.blockhash.clone();
-        let previous_block_hash = parsed_block.previous_blockhash.clone();
-        let block_timestamp = parsed_block.block_time;
-
-        // get the account public keys so that we can call the RPC method getMultipleAccounts()
-        let transactions = transactions::get_transactions_from_block(&parsed_block);
-        let all_account_pubkeys = transactions::get_pubkeys_from_transactions(transactions);
-        let mut packed_accounts = Vec::new();
-        for TransactionAccounts {
-            tx_signature,
-            accounts,
-        } in all_account_pubkeys.into_iter()
-        {
-            let timestamped_accounts_data = call_getMultipleAccounts(
-                request_config.try_clone().unwrap(),
-                accounts.clone(),
-                metrics.clone(),
-            )
-            .await;
-
-            match timestamped_accounts_data {
-                Some(accounts_inner) => {
-                    let keyed_accounts =
-                        KeyedTimestampedAccounts::from_keys_and_accounts(accounts, accounts_inner);
-
-                    // this is the full account data and full token data associated with the current transaction
-                    let (accounts, tokens) = get_accounts_and_tokens_from_pubkeys(
-                        keyed_accounts,
-                        request_config.try_clone().unwrap(),
-                        metrics.clone(),
-                    )
-                    .await;
+        let bytes = packed_block.encoded_len();
+        send_within_budget(
+            &fetched_sender,
+            &byte_budget,
+            &pipeline_config,
+            slot,
+            packed_block,
+            bytes,
+        )
+        .await;
+    }
+}
 
-                    packed_accounts.push((tx_signature, accounts, tokens)); // the timestamps are stored within the accounts
+/// Compares the slots that produced a block between start (inclusive) and end (exclusive), as
+/// returned by `getBlocks`, with the slots published according to the checkpoint store.  Returns
+/// the produced slots that weren't published, in order.
+pub async fn find_gaps(
+    request_config: RequestConfig,
+    checkpoints: &CheckpointStore,
+    start: u64,
+    end: u64,
+    metrics: Option<Metrics>,
+) -> Vec<u64> {
+    let mut gaps = Vec::new();
+    let mut chunk_start = start;
+    while chunk_start < end {
+        // getBlocks includes the end slot
+        let chunk_end = (chunk_start + MAX_GET_BLOCKS_RANGE).min(end) - 1;
+        let produced_slots = blockchain_generic::call_getBlocks(
+            request_config.try_clone().unwrap(),
+            chunk_start,
+            chunk_end,
+            metrics.clone(),
+        )
+        .await;
+        info!(
+            "{} slots produced a block between slots {} and {}",
+            produced_slots.len(),
+            chunk_start,
+            chunk_end
+        );
+        gaps.extend(checkpoints.gaps(produced_slots));
+        chunk_start = chunk_end + 1;
+    }
+    gaps
+}
+
+/// this function is run by the transform stage's workers.  each instance transforms the fetched
+/// blocks into the serialized records of each table, on the blocking thread pool so that the
+/// transformations don't hold up the fetch and publish stages.  a block that fails to be
+/// transformed is recorded as a dead letter, along with the block itself.
+async fn run_transform_stage(
+    fetched_receiver: async_channel::Receiver<InFlightBlock<etl_block::EtlBlock>>,
+    transformed_sender: async_channel::Sender<InFlightBlock<TransformedBlock>>,
+    instruction_decoders: Arc<InstructionDecoderRegistry>,
+    checkpoints: Arc<CheckpointStore>,
+    dead_letters: Arc<DeadLetterSink>,
+) {
+    while let Ok(InFlightBlock {
+        slot,
+        block,
+        permit,
+    }) = fetched_receiver.recv().await
+    {
+        let block = match block {
+            Some(packed_block) => {
+                let instruction_decoders = instruction_decoders.clone();
+                let transformed = tokio::task::spawn_blocking(move || {
+                    // a panic is still caught, in case of a bug the transformations don't expect
+                    match std::panic::catch_unwind(AssertUnwindSafe(|| {
+                        TransformedBlock::new(&packed_block, &instruction_decoders)
+                    })) {
+                        Ok(Ok(transformed_block)) => Ok(transformed_block),
+                        Ok(Err(transform_error)) => {
+                            Err((transform_error.at_slot(slot).to_string(), packed_block))
+                        }
+                        Err(panic) => Err((panic_message(&*panic), packed_block)),
+                    }
+                })
+                .await
+                .expect("the transformation's panics are caught");
+                match transformed {
+                    Ok(transformed_block) => Some(transformed_block),
+                    Err((error, packed_block)) => {
+                        dead_letters.record(&DeadLetter::new(
+                            slot,
+                            FailedStage::Transform,
+                            error,
+                            Some(&packed_block.encode_to_vec()),
+                        ));
+                        checkpoints.record(slot, SlotStatus::Failed);
+                        None
+                    }
                 }
-                None => info!("No accounts found"),
             }
-        }
-
-        let all_accounts_and_tokens = packed_accounts
-            .into_iter()
-            .map(|(tx_signature, accounts, tokens)| {
-                solana_account_protobuf::AccountInfo::new(tx_signature, accounts, tokens)
+            None => None,
+        };
+        transformed_sender
+            .send(InFlightBlock {
+                slot,
+                block,
+                permit,
             })
-            .collect();
+            .await
+            .expect("publish stage is running");
+    }
+}
 
-        let (block_metadata, block_rewards, transactions) = parsed_block_to_proto(parsed_block);
-        let table_context = etl_block::TableContext {
-            block_hash,
-            previous_block_hash,
-            block_timestamp: block_timestamp.map(|ts| UnixTimestamp { timestamp: ts }),
-        };
+/// this function is run by the publish stage's workers, unless in ordered mode.  each instance
+/// publishes the transformed blocks in whatever order they come, giving their share of the byte
+/// budget back once they are sent.
+async fn run_publish_stage(
+    publisher: StreamPublisher,
+    transformed_receiver: async_channel::Receiver<InFlightBlock<TransformedBlock>>,
+    checkpoints: Arc<CheckpointStore>,
+) {
+    // Creates the channel(s) for this thread (Rabbitmq-Classic specific)
+    // RabbitMQ Classic requires that we construct "channel" instances in the thread
+    // we intend to publish in.  Because of this, we need to recreate with the channel
+    // here.
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    let publisher = publisher.with_channels().await;
 
-        let packed_block: etl_block::EtlBlock = etl_block::EtlBlock {
-            slot,
-            block: Some(block_metadata),
-            block_rewards,
-            transactions,
-            accounts: all_accounts_and_tokens,
-            table_context: Some(table_context),
-        };
+    let publish = {
+        let (publisher, checkpoints) = (&publisher, &checkpoints);
+        move |slot, transformed_block: TransformedBlock| async move {
+            transformed_block.publish(publisher).await;
+            checkpoints.record(slot, SlotStatus::Published);
+            info!("Sent block {} to stream queue", slot);
+        }
+    };
+    publish_unordered(&transformed_receiver, publish).await;
 
-        #[cfg(feature = "SINGLE_PUBLISHER")]
-        {
-            let serialized_block: Vec<u8> = packed_block.encode_to_vec();
-            all_publisher.publish(serialized_block).await;
-            info!("PUBLISHED BLOCK: {}", slot);
+    // makes sure every record published on this thread's channel(s) reached the server
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    publisher.close_channels().await;
+}
+
+/// publishes the transformed blocks in whatever order they come, giving their share of the byte
+/// budget back once they are sent.
+pub(crate) async fn publish_unordered<B, F, Fut>(
+    transformed_receiver: &async_channel::Receiver<InFlightBlock<B>>,
+    mut publish: F,
+) where
+    F: FnMut(u64, B) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    while let Ok(InFlightBlock {
+        slot,
+        block,
+        permit,
+    }) = transformed_receiver.recv().await
+    {
+        if let Some(block) = block {
+            publish(slot, block).await;
         }
+        drop(permit);
+    }
+}
 
-        #[cfg(feature = "SEPARATE_PUBLISHERS")]
-        {
-            async fn publish_records<RecordType: prost_message>(
-                publisher: &blockchain_generic::output::publish::StreamPublisherConnection,
-                records: Vec<RecordType>,
-            ) {
-                let serialized_records: Vec<Vec<u8>> =
-                    records.into_iter().map(|rec| rec.encode_to_vec()).collect();
-                #[cfg(feature = "GOOGLE_PUBSUB")]
-                publisher.publish_batch(serialized_records).await;
-                #[cfg(not(feature = "GOOGLE_PUBSUB"))]
-                for serialized_record in serialized_records.into_iter() {
-                    publisher.publish(serialized_record).await;
-                }
-            }
+/// Publishes the blocks that are ready to be released by the reorder buffer, freeing up their
+/// slots in the window.
+async fn publish_ready_blocks<B, F, Fut>(
+    buffer: &mut ReorderBuffer<B>,
+    window: &Semaphore,
+    publish: &mut F,
+) where
+    F: FnMut(u64, B) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    while let Some((slot, block)) = buffer.pop_ready() {
+        if let Some(block) = block {
+            publish(slot, block).await;
+        }
+        window.add_permits(1);
+    }
+}
 
-            // Here we unpack the data from the `packed_block` through the transform functions then
-            // serialize the data to be sent to the respective publisher.
-
-            // Block record
-            let block_record = transformation::block::transform_to_block_record(&packed_block);
-            let serialized_block_record = block_record.encode_to_vec();
-            blocks_publisher.publish(serialized_block_record).await;
-
-            // Block Rewards records
-            let serialized_block_reward_records: Vec<Vec<u8>> =
-                transformation::block::transform_to_block_reward_records(&packed_block)
-                    .into_iter()
-                    .map(|rec| rec.encode_to_vec())
-                    .collect();
-            // Publish
-            #[cfg(not(feature = "GOOGLE_PUBSUB"))]
-            for serialized in serialized_block_reward_records {
-                block_rewards_publisher.publish(serialized).await;
-            }
-            #[cfg(feature = "GOOGLE_PUBSUB")]
-            block_rewards_publisher
-                .publish_batch(serialized_block_reward_records)
-                .await;
+/// this function replaces the publish stage in ordered mode.  it receives the queued slots in
+/// order, and the transformed blocks in any order, then publishes the blocks in the order their
+/// slots were queued.
+///     - a slot that aborts the indexer stops the publishing.  the blocks still in flight are
+///       received without being published, so that the stages before this one can drain, and
+///       their slots are left to the next run.
+#[allow(clippy::too_many_arguments)]
+async fn run_reorder_stage(
+    config: ReorderConfig,
+    publisher: StreamPublisher,
+    order_receiver: async_channel::Receiver<u64>,
+    transformed_receiver: async_channel::Receiver<InFlightBlock<TransformedBlock>>,
+    window: Arc<Semaphore>,
+    checkpoints: Arc<CheckpointStore>,
+    shutdown: Shutdown,
+    abort_sender: oneshot::Sender<StuckSlotError>,
+) {
+    // RabbitMQ Classic requires that we construct "channel" instances in the thread
+    // we intend to publish in.
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    let publisher = publisher.with_channels().await;
 
-            // Transformation for Transactions, Instructions & token transfers.
-            let (transaction_records, instruction_records, token_transfer_records) =
-                transformation::transaction::transform_to_transaction_records(&packed_block);
+    let publish = {
+        let (publisher, checkpoints) = (&publisher, &checkpoints);
+        move |slot, transformed_block: TransformedBlock| async move {
+            transformed_block.publish(publisher).await;
+            checkpoints.record(slot, SlotStatus::Published);
+            info!("Sent block {} to stream queue", slot);
+        }
+    };
+    let reordered = reorder_blocks(
+        &config,
+        &order_receiver,
+        &transformed_receiver,
+        &window,
+        &shutdown,
+        publish,
+    )
+    .await;
 
-            // Transaction Records
-            publish_records(&transactions_publisher, transaction_records).await;
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    publisher.close_channels().await;
 
-            // Instruction Records
-            publish_records(&instructions_publisher, instruction_records).await;
+    if let Err(stuck_slot) = reordered {
+        let _ = abort_sender.send(stuck_slot);
+        while transformed_receiver.recv().await.is_ok() {}
+    }
+}
 
-            // Token Transfer Records
-            publish_records(&token_transfers_publisher, token_transfer_records).await;
+/// publishes the transformed blocks in the order their slots are received from `order_receiver`.
+///     - the window limits the slots in flight, and so the blocks held back, to the capacity.
+///       the blocks held back give their share of the byte budget back, so that they can't keep
+///       the fetch stage from finishing the slot they are waiting on.  the byte budget then only
+///       bounds the blocks being fetched and transformed, and the capacity bounds the rest.
+///     - a slot that isn't finished within the timeout is skipped or aborts the indexer.  a
+///       skipped slot's block is published out of order whenever it finishes.  an aborting slot
+///       requests a shutdown, and is returned as an error.
+pub(crate) async fn reorder_blocks<B, F, Fut>(
+    config: &ReorderConfig,
+    order_receiver: &async_channel::Receiver<u64>,
+    transformed_receiver: &async_channel::Receiver<InFlightBlock<B>>,
+    window: &Semaphore,
+    shutdown: &Shutdown,
+    mut publish: F,
+) -> Result<(), StuckSlotError>
+where
+    F: FnMut(u64, B) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut buffer = ReorderBuffer::default();
+    let mut order_closed = false;
+    loop {
+        publish_ready_blocks(&mut buffer, window, &mut publish).await;
 
-            let (account_records, token_records) =
-                transformation::account::transform_to_account_and_token_records(&packed_block);
+        if buffer.is_stuck(config.timeout) {
+            let slot = buffer.skip_head().expect("a stuck slot is queued");
+            match config.on_timeout {
+                StuckSlotPolicy::Skip => warn!(
+                    "slot {} did not finish within {:?}, moving on without it",
+                    slot, config.timeout
+                ),
+                StuckSlotPolicy::Abort => {
+                    error!(
+                        "FATAL: slot {} did not finish within {:?}, shutting down",
+                        slot, config.timeout
+                    );
+                    shutdown.request();
+                    return Err(StuckSlotError {
+                        slot,
+                        timeout: config.timeout,
+                    });
+                }
+            }
+            window.add_permits(1);
+            continue;
+        }
 
-            // Token Records
-            publish_records(&tokens_publisher, token_records).await;
+        // nothing can get stuck while no slot is queued
+        let time_until_stuck = buffer.time_until_stuck(config.timeout);
+        tokio::select! {
+            order = order_receiver.recv(), if !order_closed => match order {
+                Ok(slot) => buffer.expect(slot),
+                Err(_) => order_closed = true,
+            },
+            transformed = transformed_receiver.recv() => match transformed {
+                // the permit is dropped, giving the block's share of the byte budget back
+                Ok(InFlightBlock { slot, block, .. }) => {
+                    if let Some(block) = buffer.finish(slot, block) {
+                        warn!("publishing slot {} out of order", slot);
+                        publish(slot, block).await;
+                    }
+                }
+                // every transform worker has ended
+                Err(_) => break,
+            },
+            _ = sleep(time_until_stuck.unwrap_or_default()), if time_until_stuck.is_some() => {}
+        }
+    }
 
-            // Token Records
-            publish_records(&accounts_publisher, account_records).await;
+    // publish what remains in order, skipping the slots that no worker finished
+    while let Ok(slot) = order_receiver.try_recv() {
+        buffer.expect(slot);
+    }
+    loop {
+        publish_ready_blocks(&mut buffer, window, &mut publish).await;
+        match buffer.skip_head() {
+            Some(slot) => warn!("slot {} was not finished, leaving it to the next run", slot),
+            None => break,
         }
-        info!("Sent block {} to stream queue", slot);
     }
+    Ok(())
+}
+
+/// converts the fetched block kept by the dead letter of a block that failed to be converted.  a
+/// block that fails to be converted again is recorded as a new dead letter, and `None` is returned.
+fn replay_conversion(
+    slot: u64,
+    input: &[u8],
+    dead_letters: &DeadLetterSink,
+    checkpoints: &CheckpointStore,
+) -> Result<Option<etl_block::EtlBlock>, String> {
+    let fetched_block = FetchedBlock::from_dead_letter_input(input)?;
+    let error =
+        match std::panic::catch_unwind(AssertUnwindSafe(|| fetched_block.to_etl_block(slot))) {
+            Ok(Ok(block)) => return Ok(Some(block)),
+            Ok(Err(transform_error)) => transform_error.to_string(),
+            Err(panic) => panic_message(&*panic),
+        };
+    dead_letters.record(&DeadLetter::new(
+        slot,
+        FailedStage::Convert,
+        error,
+        Some(input),
+    ));
+    checkpoints.record(slot, SlotStatus::Failed);
+    Ok(None)
+}
+
+/// converts, transforms and publishes the fetched blocks kept by the dead letters of the convert
+/// and transform stages, without requesting them again.  returns the slots of the other dead
+/// letters, which have to be indexed again from scratch.  the blocks that fail to be converted or
+/// transformed again are recorded as new dead letters.
+pub async fn replay_dead_letters(
+    config: &Config,
+    letters: Vec<DeadLetter>,
+    publisher: StreamPublisher,
+    instruction_decoders: InstructionDecoderRegistry,
+    checkpoints: Arc<CheckpointStore>,
+) -> BTreeSet<u64> {
+    let dead_letters = Arc::new(DeadLetterSink::from_config(config));
+    let mut fetched_blocks = BTreeMap::new();
+    let mut failed_again = BTreeSet::new();
+    let mut slots = BTreeSet::new();
+    for letter in letters {
+        let replayed = match (letter.stage, letter.decoded_input()) {
+            (FailedStage::Convert, Some(input)) => {
+                replay_conversion(letter.slot, &input, &dead_letters, &checkpoints)
+            }
+            (FailedStage::Transform, Some(input)) => etl_block::EtlBlock::decode(input.as_slice())
+                .map(Some)
+                .map_err(|e| e.to_string()),
+            _ => {
+                slots.insert(letter.slot);
+                continue;
+            }
+        };
+        match replayed {
+            Ok(Some(block)) => {
+                fetched_blocks.insert(letter.slot, block);
+            }
+            Ok(None) => {
+                failed_again.insert(letter.slot);
+            }
+            Err(e) => {
+                warn!(
+                    "the dead letter of slot {} doesn't hold a block, fetching it again: {}",
+                    letter.slot, e
+                );
+                slots.insert(letter.slot);
+            }
+        }
+    }
+    // a slot that failed in several stages only has to be replayed
+    slots.retain(|slot| !fetched_blocks.contains_key(slot) && !failed_again.contains(slot));
+    info!(
+        "Replaying {} fetched blocks from the dead letters",
+        fetched_blocks.len()
+    );
+
+    // the replayed blocks go through the same transform and publish stages as fetched blocks
+    let (fetched_sender, fetched_receiver) = async_channel::unbounded();
+    let (transformed_sender, transformed_receiver) = async_channel::unbounded();
+    for (slot, block) in fetched_blocks {
+        checkpoints.record(slot, SlotStatus::Fetched);
+        fetched_sender
+            .send(InFlightBlock {
+                slot,
+                block: Some(block),
+                permit: None,
+            })
+            .await
+            .expect("transform stage is running");
+    }
+    drop(fetched_sender);
+    let transform_stage = tokio::task::spawn(run_transform_stage(
+        fetched_receiver,
+        transformed_sender,
+        Arc::new(instruction_decoders),
+        checkpoints.clone(),
+        dead_letters,
+    ));
+    run_publish_stage(publisher, transformed_receiver, checkpoints).await;
+    transform_stage
+        .await
+        .expect("transform stage doesn't panic");
+
+    slots
 }
 
 /// this function is run by the main program thread, and is part of the core logic.
 /// slot values are sent to a concurrent queue for processing by multiple worker threads.
-///     - the worker threads are spawned from here.
-///        - processed slots are logged here, and used for picking up from a shutdown or system crash.
+///     - the workers of each stage (fetch, transform, then publish) are spawned from here.
+///        - queued slots are recorded in the checkpoint store, which the workers update as they
+///          index them.  it is used for picking up from a shutdown or system crash.
+///     - the instruction decoders are used for the instructions of programs the RPC node can't parse.
+///     - once a shutdown is requested, no more slots are queued and the slots in flight are drained.
+///     - fails if a stuck slot aborted the indexer in ordered mode.
 #[allow(non_snake_case, clippy::too_many_arguments)]
 pub async fn extract<I>(
+    config: &Config,
     range: I,
-    request_builder: reqwest::RequestBuilder,
+    request_config: RequestConfig,
     bigtable: Option<solana_storage_bigtable::LedgerStorage>,
     thread_count: usize,
     publisher: blockchain_generic::output::publish::StreamPublisher,
+    instruction_decoders: InstructionDecoderRegistry,
+    checkpoints: Arc<CheckpointStore>,
+    shutdown: Shutdown,
     metrics: Option<Metrics>,
 ) -> Result<(), Box<dyn Error>>
 where
     I: Iterator<Item = u64>,
 {
-    // Save the request builder as a request config enum
-    let request_config = RequestConfig::ReqBldr(request_builder);
     info!("Starting the indexer...");
 
-    let interrupter = Arc::new(AtomicBool::new(true));
-    let (block_sender, block_receiver) = async_channel::unbounded::<u64>();
+    // the token metadata cache is shared by all of the workers
+    let token_cache = Arc::new(TokenCache::from_config(config));
+    let instruction_decoders = Arc::new(instruction_decoders);
+    // the slots that fail in the fetch or transform stage are recorded there, and skipped
+    let dead_letters = Arc::new(DeadLetterSink::from_config(config));
 
-    let threads = {
-        let mut threads: Vec<tokio::task::JoinHandle<()>> = Vec::with_capacity(thread_count);
-        for _t in 0..thread_count {
+    // the stages are connected by bounded queues, and the blocks in flight share the byte budget,
+    // so that a slow stage holds up the stages before it.
+    let pipeline_config = PipelineConfig::from_config(config, thread_count);
+    info!("Running the pipeline with {:?}", pipeline_config);
+    let (block_sender, block_receiver) =
+        async_channel::bounded::<u64>(pipeline_config.fetch_workers);
+    let (fetched_sender, fetched_receiver) =
+        async_channel::bounded(pipeline_config.transform_workers);
+    let (transformed_sender, transformed_receiver) =
+        async_channel::bounded(pipeline_config.publish_workers);
+    let byte_budget = Arc::new(Semaphore::new(pipeline_config.max_in_flight_bytes));
+
+    let fetch_workers = {
+        let config = Arc::new(config.clone());
+        let mut threads: Vec<tokio::task::JoinHandle<()>> =
+            Vec::with_capacity(pipeline_config.fetch_workers);
+        for _t in 0..pipeline_config.fetch_workers {
+            let cur_config = config.clone();
             let cur_bigtable = bigtable.clone();
-            let cur_publisher = publisher.clone();
+            let cur_fetched_sender = fetched_sender.clone();
             let cur_block_receiver = block_receiver.clone();
+            let cur_token_cache = token_cache.clone();
+            let cur_pipeline_config = pipeline_config.clone();
+            let cur_byte_budget = byte_budget.clone();
+            let cur_checkpoints = checkpoints.clone();
+            let cur_dead_letters = dead_letters.clone();
             let cur_metrics = metrics.clone();
             threads.push(tokio::task::spawn(async move {
                 process_block_queue_stream(
+                    cur_config,
                     cur_bigtable,
-                    cur_publisher,
+                    cur_fetched_sender,
                     cur_block_receiver,
+                    cur_token_cache,
+                    cur_pipeline_config,
+                    cur_byte_budget,
+                    cur_checkpoints,
+                    cur_dead_letters,
                     cur_metrics,
                 )
                 .await
@@ -420,14 +921,58 @@ where
         threads
     };
 
-    info!("Press 'CRTL-C' to terminate...");
-    let r = interrupter.clone();
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    })
-    .expect("Error setting Ctrl-C handler");
+    let transform_workers: Vec<tokio::task::JoinHandle<()>> = (0..pipeline_config
+        .transform_workers)
+        .map(|_| {
+            tokio::task::spawn(run_transform_stage(
+                fetched_receiver.clone(),
+                transformed_sender.clone(),
+                instruction_decoders.clone(),
+                checkpoints.clone(),
+                dead_letters.clone(),
+            ))
+        })
+        .collect();
+
+    // in ordered mode, the reorder stage takes the place of the publish stage, and publishes the
+    // blocks in the order their slots were queued.  the window bounds the slots in flight.
+    let (order_sender, order_receiver) = async_channel::unbounded::<u64>();
+    let (abort_sender, mut abort_receiver) = oneshot::channel();
+    let (publish_workers, window) = match ReorderConfig::from_config(config) {
+        Some(config) => {
+            info!("Publishing blocks in slot order...");
+            let window = Arc::new(Semaphore::new(config.capacity));
+            let reorder_stage = tokio::task::spawn(run_reorder_stage(
+                config,
+                publisher,
+                order_receiver,
+                transformed_receiver,
+                window.clone(),
+                checkpoints.clone(),
+                shutdown.clone(),
+                abort_sender,
+            ));
+            (vec![reorder_stage], Some(window))
+        }
+        None => {
+            let publish_workers = (0..pipeline_config.publish_workers)
+                .map(|_| {
+                    tokio::task::spawn(run_publish_stage(
+                        publisher.clone(),
+                        transformed_receiver.clone(),
+                        checkpoints.clone(),
+                    ))
+                })
+                .collect();
+            (publish_workers, None)
+        }
+    };
+    // each stage ends once every worker of the stage before it has dropped its sender
+    drop(fetched_sender);
+    drop(transformed_sender);
+
     let mut range = range;
-    while interrupter.load(Ordering::SeqCst) {
+    'queueing: while !shutdown.is_requested() {
         // Iterates through all confirmed blocks returned by the function
         let mut latest_slot =
             blockchain_generic::call_getSlot(request_config.try_clone().unwrap(), metrics.clone())
@@ -444,6 +989,9 @@ where
         // send each of the slot values to the concurrent task queue, ensuring that they don't exceed the node's maximum slot
         for i in peekable_subrange {
             while i > latest_slot {
+                if shutdown.is_requested() {
+                    break 'queueing;
+                }
                 latest_slot = blockchain_generic::call_getSlot(
                     request_config.try_clone().unwrap(),
                     metrics.clone(),
@@ -451,11 +999,24 @@ where
                 .await;
             }
 
+            if let Some(window) = &window {
+                tokio::select! {
+                    permit = window.acquire() => permit.expect("window is never closed").forget(),
+                    _ = shutdown.requested() => break 'queueing,
+                }
+            }
             info!("sending block task: {}", i);
-            block_sender
-                .send(i)
-                .await
-                .expect("block queue has not been disconnected");
+            checkpoints.record(i, SlotStatus::Queued);
+            tokio::select! {
+                sent = block_sender.send(i) => sent.expect("block queue has not been disconnected"),
+                _ = shutdown.requested() => break 'queueing,
+            }
+            if window.is_some() {
+                order_sender
+                    .send(i)
+                    .await
+                    .expect("reorder stage is running");
+            }
         }
         let seconds = time::Duration::from_secs(1);
         sleep(seconds).await;
@@ -463,14 +1024,57 @@ where
     info!("Shutting down...");
 
     block_sender.close();
+    order_sender.close();
+    if shutdown.is_requested() {
+        // the slots still in the queue are left to the next run, as queued
+        let mut abandoned = 0;
+        while block_receiver.try_recv().is_ok() {
+            abandoned += 1;
+        }
+        info!("Left {} queued slots to the next run", abandoned);
+    }
 
-    let worker_results = join_all(threads).await;
-
-    for result in worker_results {
-        if let Err(e) = result {
-            error!("Task failed to terminate: {:?}", e);
+    // the stages are drained in order, so that every slot in flight is published.  on shutdown,
+    // the slots that aren't published by the deadline are abandoned.
+    let mut stages = [
+        ("fetch", fetch_workers),
+        ("transform", transform_workers),
+        ("publish", publish_workers),
+    ];
+    let drain_stages = async {
+        for (stage, workers) in stages.iter_mut() {
+            for result in join_all(workers.iter_mut()).await {
+                if let Err(e) = result {
+                    error!("Task of the {} stage failed to terminate: {:?}", stage, e);
+                }
+            }
+        }
+    };
+    let drained = tokio::select! {
+        _ = drain_stages => true,
+        _ = shutdown.timed_out() => false,
+    };
+    if !drained {
+        warn!(
+            "The slots in flight were not published within {:?}, leaving them to the next run",
+            shutdown.timeout()
+        );
+        for (_, workers) in stages.iter() {
+            workers.iter().for_each(|worker| worker.abort());
+        }
+        for (_, workers) in stages.iter_mut() {
+            for worker in workers.iter_mut().filter(|worker| !worker.is_finished()) {
+                let _ = worker.await;
+            }
         }
     }
 
-    Ok(())
+    // everything recorded so far is persisted, so the next run resumes exactly
+    checkpoints.flush();
+    token_cache.save();
+
+    match abort_receiver.try_recv() {
+        Ok(stuck_slot) => Err(Box::new(stuck_slot)),
+        Err(_) => Ok(()),
+    }
 }
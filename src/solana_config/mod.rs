@@ -5,14 +5,21 @@ pub mod accounts;
 pub mod build_proto;
 pub mod constants;
 pub mod data_sources;
+pub mod instruction_decoders;
 pub mod lib;
+pub mod native_accounts;
+pub mod pipeline;
 /// The code in the following module is generated by build_proto, and should not be edited
 /// as it will be overwritten during compilation.
 pub mod proto_codegen;
 pub mod proto_conversions;
+pub mod reorder;
+pub mod stake;
 #[cfg(feature = "SEPARATE_PUBLISHERS")]
 pub mod streampublisher;
 pub mod tests;
+pub mod token_cache;
+pub mod token_extensions;
 pub mod tokens;
 pub mod transactions;
 pub mod transformation;
@@ -0,0 +1,104 @@
+//! this file contains helper functions for decoding the accounts of native programs: durable nonces,
+//! [address lookup tables](https://docs.solana.com/developing/lookup-tables), the program data of
+//! upgradeable programs and [sysvars](https://docs.solana.com/developing/runtime-facilities/sysvars).
+use super::{
+    accounts::parse_amount, proto_codegen::account_info as solana_account_protobuf,
+    types::account_response_types::AccountInfoInfoStructureResponse,
+};
+
+pub const NONCE_PROGRAM: &str = "nonce";
+pub const ADDRESS_LOOKUP_TABLE_PROGRAM: &str = "address-lookup-table";
+pub const BPF_UPGRADEABLE_LOADER_PROGRAM: &str = "bpf-upgradeable-loader";
+pub const SYSVAR_PROGRAM: &str = "sysvar";
+
+/// The decoded state of a native program account.
+pub enum NativeAccountState {
+    Nonce(solana_account_protobuf::NonceState),
+    LookupTable(solana_account_protobuf::LookupTableState),
+    ProgramData(solana_account_protobuf::ProgramDataState),
+    Sysvar(solana_account_protobuf::SysvarState),
+}
+
+fn lamports_per_signature(info: &AccountInfoInfoStructureResponse) -> Option<u64> {
+    info.feeCalculator.as_ref().and_then(|fee_calculator| {
+        parse_amount(
+            "lamports per signature",
+            &fee_calculator.lamportsPerSignature,
+        )
+    })
+}
+
+/// decodes the state of an account owned by a native program, given the program and account type
+/// returned by the jsonParsed encoding.  returns `None` for other accounts.
+pub fn decode_native_account(
+    program: &str,
+    account_type: &str,
+    info: &AccountInfoInfoStructureResponse,
+) -> Option<NativeAccountState> {
+    match (program, account_type) {
+        (NONCE_PROGRAM, "initialized") => Some(NativeAccountState::Nonce(
+            solana_account_protobuf::NonceState {
+                authority: info.authority.to_owned()?,
+                blockhash: info.blockhash.to_owned()?,
+                lamports_per_signature: lamports_per_signature(info),
+            },
+        )),
+        (ADDRESS_LOOKUP_TABLE_PROGRAM, "lookupTable") => Some(NativeAccountState::LookupTable(
+            solana_account_protobuf::LookupTableState {
+                authority: info.authority.to_owned(),
+                addresses: info.addresses.to_owned().unwrap_or_default(),
+                deactivation_slot: info
+                    .deactivationSlot
+                    .as_deref()
+                    .and_then(|slot| parse_amount("deactivation slot", slot))
+                    .filter(|slot| *slot != u64::MAX),
+                last_extended_slot: info
+                    .lastExtendedSlot
+                    .as_deref()
+                    .and_then(|slot| parse_amount("last extended slot", slot)),
+                last_extended_slot_start_index: info.lastExtendedSlotStartIndex.unwrap_or_default(),
+            },
+        )),
+        (BPF_UPGRADEABLE_LOADER_PROGRAM, "programData") => Some(NativeAccountState::ProgramData(
+            solana_account_protobuf::ProgramDataState {
+                slot: info.slot? as u64,
+                upgrade_authority: info.authority.to_owned(),
+            },
+        )),
+        (SYSVAR_PROGRAM, sysvar) => {
+            let mut state = solana_account_protobuf::SysvarState::default();
+            match sysvar {
+                "clock" => {
+                    state.clock_slot = info.slot.map(|slot| slot as u64);
+                    state.clock_epoch = info.epoch.map(|epoch| epoch as u64);
+                    state.clock_epoch_start_timestamp = info.epochStartTimestamp;
+                    state.clock_leader_schedule_epoch =
+                        info.leaderScheduleEpoch.map(|epoch| epoch as u64);
+                    state.clock_unix_timestamp = info.unixTimestamp;
+                }
+                "epochSchedule" => {
+                    state.slots_per_epoch = info.slotsPerEpoch;
+                    state.leader_schedule_slot_offset = info.leaderScheduleSlotOffset;
+                    state.warmup = info.warmup;
+                    state.first_normal_epoch = info.firstNormalEpoch;
+                    state.first_normal_slot = info.firstNormalSlot;
+                }
+                "fees" => state.lamports_per_signature = lamports_per_signature(info),
+                "rent" => {
+                    state.lamports_per_byte_year = info
+                        .lamportsPerByteYear
+                        .as_deref()
+                        .and_then(|lamports| parse_amount("lamports per byte year", lamports));
+                    state.exemption_threshold = info.exemptionThreshold;
+                    state.burn_percent = info.burnPercent;
+                }
+                "rewards" => state.validator_point_value = info.validatorPointValue,
+                "slotHistory" => state.next_slot = info.nextSlot,
+                "lastRestartSlot" => state.last_restart_slot = info.lastRestartSlot,
+                _ => return None,
+            }
+            Some(NativeAccountState::Sysvar(state))
+        }
+        _ => None,
+    }
+}
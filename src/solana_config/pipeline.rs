@@ -0,0 +1,320 @@
+//! this file contains the pieces of the indexing pipeline that sit between its stages:
+//!     - fetch: requests each block and its accounts from the RPC node (or bigtable).
+//!     - transform: turns each block into the serialized records of each table.
+//!     - publish: sends the serialized records to the stream queue(s).
+//! the stages are connected by bounded queues, and the blocks in flight share a byte budget, so
+//! a slow stage throttles the stages before it instead of letting memory grow.
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use prost::Message as prost_message;
+use solana_transaction_status::UiConfirmedBlock;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate as blockchain_generic;
+use blockchain_generic::config::Config;
+use blockchain_generic::output::publish::StreamPublisher;
+
+use super::instruction_decoders::InstructionDecoderRegistry;
+use super::proto_codegen::{
+    account_info::AccountInfo,
+    confirmed_block::UnixTimestamp,
+    etl_block::{EtlBlock, TableContext},
+};
+use super::proto_conversions::block::parsed_block_to_proto;
+use super::transformation::error::TransformError;
+use super::types::block_response_types::{BlockWithTransactionErrors, TransactionErrorResponse};
+
+#[cfg(feature = "SEPARATE_PUBLISHERS")]
+use super::transformation;
+
+/// The default size of the in-flight byte budget: 512 MiB.
+const DEFAULT_MAX_IN_FLIGHT_BYTES: usize = 512 * 1024 * 1024;
+
+/// The number of workers of each stage, and the byte budget of the blocks in flight.
+#[derive(Debug, Clone)]
+pub struct PipelineConfig {
+    pub fetch_workers: usize,
+    pub transform_workers: usize,
+    pub publish_workers: usize,
+    /// the total encoded size of the blocks that may be in flight between the fetch stage and
+    /// the publish stage.  in ordered mode, the blocks held back by the reorder stage are bounded
+    /// by its window instead.
+    pub max_in_flight_bytes: usize,
+}
+
+impl PipelineConfig {
+    /// Reads the size of the transform and publish stages and the byte budget from the config.
+    /// The fetch stage has `fetch_workers` workers, which also sets the default number of
+    /// publish workers.
+    pub fn from_config(config: &Config, fetch_workers: usize) -> PipelineConfig {
+        // env:transform_workers = The number of workers transforming blocks into records
+        let transform_workers = config.transform_workers.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|parallelism| parallelism.get())
+                .unwrap_or(1)
+        });
+        // env:publish_workers = The number of workers publishing records to the stream queue(s)
+        let publish_workers = config.publish_workers.unwrap_or(fetch_workers);
+        // env:max_in_flight_bytes = The total size of the blocks that may be in flight at once
+        let max_in_flight_bytes = config
+            .max_in_flight_bytes
+            .unwrap_or(DEFAULT_MAX_IN_FLIGHT_BYTES);
+
+        assert!(
+            fetch_workers > 0 && transform_workers > 0 && publish_workers > 0,
+            "each stage of the pipeline needs at least one worker"
+        );
+        assert!(
+            max_in_flight_bytes > 0,
+            "MAX_IN_FLIGHT_BYTES should be a positive number of bytes"
+        );
+
+        PipelineConfig {
+            fetch_workers,
+            transform_workers,
+            publish_workers,
+            max_in_flight_bytes,
+        }
+    }
+
+    /// The number of permits of the byte budget that a block of the given size takes.  A block
+    /// larger than the whole budget takes all of it, so that it can still go through on its own.
+    pub fn permits_for(&self, bytes: usize) -> u32 {
+        bytes
+            .clamp(1, self.max_in_flight_bytes)
+            .min(u32::MAX as usize) as u32
+    }
+}
+
+/// A slot moving through the pipeline.
+pub struct InFlightBlock<B> {
+    pub slot: u64,
+    /// `None` if the slot has no block
+    pub block: Option<B>,
+    /// the block's share of the byte budget, given back when the block is dropped
+    pub permit: Option<OwnedSemaphorePermit>,
+}
+
+/// A fetched block along with the accounts of its transactions, before it is converted to
+/// protobuf.  A block that fails to be converted is kept in its dead letter, so that the
+/// conversion can be replayed without fetching the block and its accounts again.
+#[derive(Clone)]
+pub struct FetchedBlock {
+    pub block: BlockWithTransactionErrors,
+    pub accounts: Vec<AccountInfo>,
+}
+
+/// The dead-letter input of a block that failed to be converted, as JSON.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct FetchedBlockInput {
+    block: UiConfirmedBlock,
+    transaction_errors: Vec<Option<TransactionErrorResponse>>,
+    /// the protobuf encoded account info of each transaction (base64 encoded)
+    accounts: Vec<String>,
+}
+
+impl FetchedBlock {
+    /// Converts the block to protobuf, packaged with its accounts for the transform stage.  The
+    /// fetched block is left as is, so that it can be kept if the conversion fails.
+    pub fn to_etl_block(&self, slot: u64) -> Result<EtlBlock, TransformError> {
+        let table_context = TableContext {
+            block_hash: self.block.block.blockhash.clone(),
+            previous_block_hash: self.block.block.previous_blockhash.clone(),
+            block_timestamp: self
+                .block
+                .block
+                .block_time
+                .map(|timestamp| UnixTimestamp { timestamp }),
+        };
+        let (block, block_rewards, transactions) =
+            parsed_block_to_proto(self.block.clone()).map_err(|error| error.at_slot(slot))?;
+
+        Ok(EtlBlock {
+            slot,
+            block: Some(block),
+            block_rewards,
+            transactions,
+            accounts: self.accounts.clone(),
+            table_context: Some(table_context),
+        })
+    }
+
+    /// Serializes the fetched block for its dead letter.
+    pub fn to_dead_letter_input(&self) -> Vec<u8> {
+        serde_json::to_vec(&FetchedBlockInput {
+            block: self.block.block.clone(),
+            transaction_errors: self.block.transaction_errors.clone(),
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account_info| BASE64.encode(account_info.encode_to_vec()))
+                .collect(),
+        })
+        .expect("fetched block is serializable")
+    }
+
+    /// Deserializes the fetched block kept by a dead letter.
+    pub fn from_dead_letter_input(input: &[u8]) -> Result<FetchedBlock, String> {
+        let input: FetchedBlockInput = serde_json::from_slice(input).map_err(|e| e.to_string())?;
+        let accounts = input
+            .accounts
+            .iter()
+            .map(|account_info| {
+                let account_info = BASE64.decode(account_info).map_err(|e| e.to_string())?;
+                AccountInfo::decode(account_info.as_slice()).map_err(|e| e.to_string())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FetchedBlock {
+            block: BlockWithTransactionErrors {
+                block: input.block,
+                transaction_errors: input.transaction_errors,
+            },
+            accounts,
+        })
+    }
+}
+
+/// Passes the block on to the next stage once it has taken its share of the byte budget, waiting
+/// for the blocks ahead to free up enough of it.
+pub async fn send_within_budget<B>(
+    sender: &async_channel::Sender<InFlightBlock<B>>,
+    byte_budget: &Arc<Semaphore>,
+    config: &PipelineConfig,
+    slot: u64,
+    block: B,
+    bytes: usize,
+) {
+    let permit = byte_budget
+        .clone()
+        .acquire_many_owned(config.permits_for(bytes))
+        .await
+        .expect("byte budget is never closed");
+    sender
+        .send(InFlightBlock {
+            slot,
+            block: Some(block),
+            permit: Some(permit),
+        })
+        .await
+        .expect("transform stage is running");
+}
+
+/// The serialized records of a block, ready to be published.
+pub struct TransformedBlock {
+    /// the whole block with a single publisher, or its block record with separate publishers
+    block: Vec<u8>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    block_rewards: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    transactions: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    instructions: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    token_transfers: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    events: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    native_transfers: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    stake_actions: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    tokens: Vec<Vec<u8>>,
+    #[cfg(feature = "SEPARATE_PUBLISHERS")]
+    accounts: Vec<Vec<u8>>,
+}
+
+#[cfg(feature = "SEPARATE_PUBLISHERS")]
+fn encode_records<RecordType: prost_message>(records: Vec<RecordType>) -> Vec<Vec<u8>> {
+    records.into_iter().map(|rec| rec.encode_to_vec()).collect()
+}
+
+#[cfg(feature = "SEPARATE_PUBLISHERS")]
+async fn publish_records(
+    publisher: &blockchain_generic::output::publish::StreamPublisherConnection,
+    serialized_records: Vec<Vec<u8>>,
+) {
+    #[cfg(feature = "GOOGLE_PUBSUB")]
+    publisher.publish_batch(serialized_records).await;
+    #[cfg(not(feature = "GOOGLE_PUBSUB"))]
+    for serialized_record in serialized_records.into_iter() {
+        publisher.publish(serialized_record).await;
+    }
+}
+
+impl TransformedBlock {
+    /// Serializes the block as a whole, or transforms it into the serialized records of each
+    /// table, depending on the publisher(s) enabled.  Fails if the block can't be transformed.
+    #[cfg_attr(feature = "SINGLE_PUBLISHER", allow(unused_variables))]
+    pub fn new(
+        packed_block: &EtlBlock,
+        instruction_decoders: &InstructionDecoderRegistry,
+    ) -> Result<TransformedBlock, TransformError> {
+        #[cfg(feature = "SINGLE_PUBLISHER")]
+        let transformed_block = TransformedBlock {
+            block: packed_block.encode_to_vec(),
+        };
+
+        // Here we unpack the data from the `packed_block` through the transform functions then
+        // serialize the data to be sent to the respective publisher.
+        #[cfg(feature = "SEPARATE_PUBLISHERS")]
+        let transformed_block = {
+            // Transformation for Transactions, Instructions, token transfers, events, native transfers
+            // & stake actions.
+            let (
+                transaction_records,
+                instruction_records,
+                token_transfer_records,
+                event_records,
+                native_transfer_records,
+                stake_action_records,
+            ) = transformation::transaction::transform_to_transaction_records(
+                packed_block,
+                instruction_decoders,
+            )?;
+            let (account_records, token_records) =
+                transformation::account::transform_to_account_and_token_records(
+                    packed_block,
+                    instruction_decoders,
+                )?;
+
+            TransformedBlock {
+                block: transformation::block::transform_to_block_record(packed_block)?
+                    .encode_to_vec(),
+                block_rewards: encode_records(
+                    transformation::block::transform_to_block_reward_records(packed_block)?,
+                ),
+                transactions: encode_records(transaction_records),
+                instructions: encode_records(instruction_records),
+                token_transfers: encode_records(token_transfer_records),
+                events: encode_records(event_records),
+                native_transfers: encode_records(native_transfer_records),
+                stake_actions: encode_records(stake_action_records),
+                tokens: encode_records(token_records),
+                accounts: encode_records(account_records),
+            }
+        };
+
+        Ok(transformed_block)
+    }
+
+    /// Publishes the serialized records to their stream queue(s).
+    pub async fn publish(self, publisher: &StreamPublisher) {
+        #[cfg(feature = "SINGLE_PUBLISHER")]
+        publisher.all.publish(self.block).await;
+
+        #[cfg(feature = "SEPARATE_PUBLISHERS")]
+        {
+            publisher.blocks.publish(self.block).await;
+            publish_records(&publisher.block_rewards, self.block_rewards).await;
+            publish_records(&publisher.transactions, self.transactions).await;
+            publish_records(&publisher.instructions, self.instructions).await;
+            publish_records(&publisher.token_transfers, self.token_transfers).await;
+            publish_records(&publisher.events, self.events).await;
+            publish_records(&publisher.native_transfers, self.native_transfers).await;
+            publish_records(&publisher.stake_actions, self.stake_actions).await;
+            publish_records(&publisher.tokens, self.tokens).await;
+            publish_records(&publisher.accounts, self.accounts).await;
+        }
+    }
+}
@@ -4,6 +4,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::solana_config::{
+    native_accounts::NativeAccountState,
     proto_codegen::{self, account_info as solana_account_protobuf},
     types::account_response_types::{
         AccountInfoAuthorizedEpochCreditsResponse, AccountInfoAuthorizedPriorVotersResponse,
@@ -37,6 +38,7 @@ impl solana_account_protobuf::AccountInfo {
 #[derive(Debug)]
 pub struct PackagedAccount {
     timestamp: u64,
+    context_slot: u64,
     pubkey: String,
     data: Option<EncodedData>,
     executable: bool,
@@ -63,6 +65,12 @@ pub struct PackagedAccount {
     last_timestamp: Option<AccountInfoLastTimestampResponse>,
     mint_authority: Option<String>,
     supply: Option<String>,
+    extensions: Option<solana_account_protobuf::TokenExtensions>,
+    stake: Option<solana_account_protobuf::StakeState>,
+    nonce: Option<solana_account_protobuf::NonceState>,
+    lookup_table: Option<solana_account_protobuf::LookupTableState>,
+    program_data_state: Option<solana_account_protobuf::ProgramDataState>,
+    sysvar: Option<solana_account_protobuf::SysvarState>,
 }
 
 impl From<PackagedAccount> for solana_account_protobuf::Account {
@@ -71,6 +79,7 @@ impl From<PackagedAccount> for solana_account_protobuf::Account {
             retrieval_timestamp: Some(proto_codegen::confirmed_block::UnixTimestamp {
                 timestamp: account_packaged.timestamp as i64,
             }),
+            context_slot: Some(account_packaged.context_slot),
             pubkey: account_packaged.pubkey,
             executable: account_packaged.executable,
             lamports: account_packaged.lamports,
@@ -139,6 +148,12 @@ impl From<PackagedAccount> for solana_account_protobuf::Account {
             }),
             mint_authority: account_packaged.mint_authority,
             supply: account_packaged.supply,
+            extensions: account_packaged.extensions,
+            stake: account_packaged.stake,
+            nonce: account_packaged.nonce,
+            lookup_table: account_packaged.lookup_table,
+            program_data_state: account_packaged.program_data_state,
+            sysvar: account_packaged.sysvar,
             data: account_packaged
                 .data
                 .map(|data| solana_account_protobuf::EncodedData {
@@ -152,6 +167,7 @@ impl From<PackagedAccount> for solana_account_protobuf::Account {
 impl PackagedAccount {
     pub fn new_account_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -164,9 +180,11 @@ impl PackagedAccount {
         token_amount_decimals: Option<i64>,
         is_native: Option<bool>,
         state: Option<String>,
+        extensions: Option<solana_account_protobuf::TokenExtensions>,
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -193,11 +211,18 @@ impl PackagedAccount {
             state,
             mint_authority: None,
             supply: None,
+            extensions,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 
     pub fn new_mint_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -208,9 +233,11 @@ impl PackagedAccount {
         token_amount_decimals: Option<i64>,
         mint_authority: Option<String>,
         supply: Option<String>,
+        extensions: Option<solana_account_protobuf::TokenExtensions>,
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -237,11 +264,18 @@ impl PackagedAccount {
             state: None,
             mint_authority,
             supply,
+            extensions,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 
     pub fn new_program_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -253,6 +287,7 @@ impl PackagedAccount {
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -279,11 +314,18 @@ impl PackagedAccount {
             state: None,
             mint_authority: None,
             supply: None,
+            extensions: None,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 
     pub fn new_vote_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -303,6 +345,7 @@ impl PackagedAccount {
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -329,11 +372,107 @@ impl PackagedAccount {
             state: None,
             mint_authority: None,
             supply: None,
+            extensions: None,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 
+    pub fn new_stake_type(
+        timestamp: u64,
+        context_slot: u64,
+        pubkey: String,
+        executable: bool,
+        lamports: u64,
+        owner: String,
+        rent_epoch: u64,
+        space: i64,
+        program: String,
+        account_type: String,
+        stake: solana_account_protobuf::StakeState,
+    ) -> PackagedAccount {
+        PackagedAccount {
+            timestamp,
+            context_slot,
+            pubkey,
+            executable,
+            lamports,
+            owner: Some(owner),
+            rent_epoch,
+            space: Some(space),
+            program: Some(program),
+            account_type: Some(account_type),
+            authorized_withdrawer: Some(stake.withdrawer.to_owned()),
+            stake: Some(stake),
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
+            data: None,
+            authorized_voters: None,
+            prior_voters: None,
+            node_pubkey: None,
+            commission: None,
+            epoch_credits: None,
+            votes: None,
+            root_slot: None,
+            last_timestamp: None,
+            mint: None,
+            program_data: None,
+            token_amount: None,
+            token_amount_decimals: None,
+            is_native: None,
+            state: None,
+            mint_authority: None,
+            supply: None,
+            extensions: None,
+        }
+    }
+
+    pub fn new_native_type(
+        timestamp: u64,
+        context_slot: u64,
+        pubkey: String,
+        executable: bool,
+        lamports: u64,
+        owner: String,
+        rent_epoch: u64,
+        space: i64,
+        program: String,
+        account_type: String,
+        state: NativeAccountState,
+    ) -> PackagedAccount {
+        let mut account = PackagedAccount::new_unused_type(
+            timestamp,
+            context_slot,
+            pubkey,
+            executable,
+            lamports,
+            owner,
+            rent_epoch,
+            space,
+            program,
+            account_type,
+        );
+        match state {
+            NativeAccountState::Nonce(nonce) => account.nonce = Some(nonce),
+            NativeAccountState::LookupTable(lookup_table) => {
+                account.lookup_table = Some(lookup_table)
+            }
+            NativeAccountState::ProgramData(program_data) => {
+                account.program_data_state = Some(program_data)
+            }
+            NativeAccountState::Sysvar(sysvar) => account.sysvar = Some(sysvar),
+        }
+        account
+    }
+
     pub fn new_other_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -346,6 +485,7 @@ impl PackagedAccount {
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -375,11 +515,18 @@ impl PackagedAccount {
             state: None,
             mint_authority: None,
             supply: None,
+            extensions: None,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 
     pub fn new_unused_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -391,6 +538,7 @@ impl PackagedAccount {
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -417,11 +565,18 @@ impl PackagedAccount {
             state: None,
             mint_authority: None,
             supply: None,
+            extensions: None,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 
     pub fn new_encoded_type(
         timestamp: u64,
+        context_slot: u64,
         pubkey: String,
         executable: bool,
         lamports: u64,
@@ -432,6 +587,7 @@ impl PackagedAccount {
     ) -> PackagedAccount {
         PackagedAccount {
             timestamp,
+            context_slot,
             pubkey,
             executable,
             lamports,
@@ -461,6 +617,61 @@ impl PackagedAccount {
             state: None,
             mint_authority: None,
             supply: None,
+            extensions: None,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
+        }
+    }
+
+    /// packages an account from the `createAccount` instruction that created it, rather than from
+    /// a live getMultipleAccounts call.  `context_slot` is the slot of the block the account was
+    /// created in, so the record describes the account's initial state.
+    pub fn new_created_type(
+        timestamp: u64,
+        context_slot: u64,
+        pubkey: String,
+        lamports: u64,
+        owner: String,
+        space: i64,
+    ) -> PackagedAccount {
+        PackagedAccount {
+            timestamp,
+            context_slot,
+            pubkey,
+            executable: false,
+            lamports,
+            owner: Some(owner),
+            rent_epoch: 0,
+            space: Some(space),
+            program: None,
+            account_type: None,
+            data: None,
+            authorized_voters: None,
+            authorized_withdrawer: None,
+            prior_voters: None,
+            node_pubkey: None,
+            commission: None,
+            epoch_credits: None,
+            votes: None,
+            root_slot: None,
+            last_timestamp: None,
+            mint: None,
+            program_data: None,
+            token_amount: None,
+            token_amount_decimals: None,
+            is_native: None,
+            state: None,
+            mint_authority: None,
+            supply: None,
+            extensions: None,
+            stake: None,
+            nonce: None,
+            lookup_table: None,
+            program_data_state: None,
+            sysvar: None,
         }
     }
 }
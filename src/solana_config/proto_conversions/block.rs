@@ -8,8 +8,10 @@ use crate::solana_config::proto_codegen::confirmed_block::{
     TransactionError, TransactionStatusMeta, UiTokenAmount as generated_token_amount,
     UnixTimestamp,
 };
+use crate::solana_config::transformation::error::{TransformError, TransformErrorKind};
 use crate::solana_config::types::block_response_types::{
-    InstructionError, ParsedType, TransactionErrorSolana,
+    BlockWithTransactionErrors, InstructionError, ParsedType, TransactionErrorResponse,
+    TransactionErrorSolana,
 };
 use solana_account_decoder::parse_token::UiTokenAmount;
 use solana_transaction_status::option_serializer::OptionSerializer;
@@ -17,23 +19,32 @@ use solana_transaction_status::UiParsedInstruction;
 
 use crate::solana_config::proto_codegen::transaction_by_addr::{self as tx_by_addr};
 use solana_sdk::message::MessageHeader;
+use solana_sdk::transaction::TransactionVersion;
 use solana_transaction_status::parse_accounts::ParsedAccount;
 use solana_transaction_status::{
     parse_accounts::ParsedAccountSource, EncodedTransaction, EncodedTransactionWithStatusMeta,
-    Reward, RewardType, UiAddressTableLookup, UiConfirmedBlock, UiInnerInstructions, UiInstruction,
-    UiMessage, UiTransaction, UiTransactionReturnData, UiTransactionStatusMeta,
-    UiTransactionTokenBalance,
+    Reward, RewardType, UiAddressTableLookup, UiInnerInstructions, UiInstruction, UiMessage,
+    UiTransaction, UiTransactionReturnData, UiTransactionStatusMeta, UiTransactionTokenBalance,
 };
 
 /// converts from a single solana block to protobuf types.
 /// block rewards and transactions are pulled out of the block.
+/// the transactions' errors are taken from `transaction_errors` rather than their status.
+/// fails if a transaction wasn't requested with the jsonParsed encoding, or doesn't match it.
+#[allow(clippy::type_complexity)]
 pub fn parsed_block_to_proto(
-    item: UiConfirmedBlock,
-) -> (
-    ConfirmedBlock,
-    Vec<generated_reward>,
-    Vec<ConfirmedTransaction>,
-) {
+    BlockWithTransactionErrors {
+        block: item,
+        transaction_errors,
+    }: BlockWithTransactionErrors,
+) -> Result<
+    (
+        ConfirmedBlock,
+        Vec<generated_reward>,
+        Vec<ConfirmedTransaction>,
+    ),
+    TransformError,
+> {
     let rewards_inner = item.rewards.unwrap_or_default();
     let transactions_inner = item.transactions.unwrap_or_default();
     let transaction_count = transactions_inner.len() as u32;
@@ -61,10 +72,21 @@ pub fn parsed_block_to_proto(
         .collect();
     let transactions = transactions_inner
         .into_iter()
-        .map(ConfirmedTransaction::from)
-        .collect();
+        .enumerate()
+        .map(|(index, transaction)| {
+            let mut transaction = ConfirmedTransaction::try_from(transaction)
+                .map_err(|error| error.at_transaction(index))?;
+            if let Some(meta) = transaction.meta.as_mut() {
+                meta.err = transaction_errors
+                    .get(index)
+                    .and_then(Option::as_ref)
+                    .map(TransactionError::from);
+            }
+            Ok(transaction)
+        })
+        .collect::<Result<_, TransformError>>()?;
 
-    (block, block_rewards, transactions)
+    Ok((block, block_rewards, transactions))
 }
 
 /// Type Conversion for BlockHeight
@@ -190,30 +212,36 @@ impl From<ParsedType> for Parsed {
     }
 }
 
-impl From<serde_json::Value> for ParsedType {
-    fn from(value: serde_json::Value) -> Self {
+impl TryFrom<serde_json::Value> for ParsedType {
+    type Error = TransformError;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
         serde_json::from_value(value)
-            .expect("ParsedInstruction's ParsedDict should match ParsedType format")
+            .map_err(|e| TransformErrorKind::InvalidParsedInstruction(e.to_string()).into())
     }
 }
 
-impl From<UiInstruction> for InnerInstruction {
-    fn from(item: UiInstruction) -> Self {
-        match item {
-            UiInstruction::Compiled(_) => panic!("FATAL: should be using JsonParsed encoding."),
+impl TryFrom<UiInstruction> for InnerInstruction {
+    type Error = TransformError;
+
+    fn try_from(item: UiInstruction) -> Result<Self, Self::Error> {
+        Ok(match item {
+            UiInstruction::Compiled(_) => {
+                return Err(TransformErrorKind::UnexpectedEncoding("the instruction").into())
+            }
             UiInstruction::Parsed(instruction) => match instruction {
                 UiParsedInstruction::Parsed(_instruction) => {
                     let (parsed_dict, parsed_string) = match _instruction.parsed {
                         serde_json::Value::String(s) => (None, Some(s)),
                         v => (
-                            Some(Parsed::from(ParsedType::from(serde_json::json!(v)))),
+                            Some(Parsed::from(ParsedType::try_from(serde_json::json!(v))?)),
                             None,
                         ),
                     };
                     InnerInstruction {
                         program: Some(_instruction.program),
                         program_id: Some(_instruction.program_id),
-                        stack_height: None, // TODO: from version solana version 1.16, _instruction.stack_height is available.
+                        stack_height: _instruction.stack_height,
                         parsed_dict,
                         parsed_string,
                         accounts: Vec::new(),
@@ -223,27 +251,34 @@ impl From<UiInstruction> for InnerInstruction {
                 UiParsedInstruction::PartiallyDecoded(_instruction) => InnerInstruction {
                     program: None,
                     program_id: Some(_instruction.program_id),
-                    stack_height: None, // TODO: from version solana version 1.16, _instruction.stack_height is available.
+                    stack_height: _instruction.stack_height,
                     parsed_dict: None,
                     parsed_string: None,
                     accounts: _instruction.accounts,
                     data: Some(_instruction.data),
                 },
             },
-        }
+        })
     }
 }
 
-impl From<UiInnerInstructions> for InnerInstructions {
-    fn from(item: UiInnerInstructions) -> Self {
-        InnerInstructions {
+impl TryFrom<UiInnerInstructions> for InnerInstructions {
+    type Error = TransformError;
+
+    fn try_from(item: UiInnerInstructions) -> Result<Self, Self::Error> {
+        let parent_index = item.index as usize;
+        Ok(InnerInstructions {
             index: item.index as u32,
             instructions: item
                 .instructions
                 .into_iter()
-                .map(InnerInstruction::from)
-                .collect(),
-        }
+                .enumerate()
+                .map(|(inner_index, instruction)| {
+                    InnerInstruction::try_from(instruction)
+                        .map_err(|error| error.at_inner_instruction(parent_index, inner_index))
+                })
+                .collect::<Result<_, _>>()?,
+        })
     }
 }
 
@@ -553,14 +588,32 @@ impl From<TransactionErrorSolana> for tx_by_addr::TransactionError {
     }
 }
 
-impl From<UiTransactionStatusMeta> for TransactionStatusMeta {
-    fn from(item: UiTransactionStatusMeta) -> Self {
-        TransactionStatusMeta {
+impl From<&TransactionErrorResponse> for TransactionError {
+    fn from(err: &TransactionErrorResponse) -> Self {
+        TransactionError {
+            err: err.message(),
+            error_type: err.error_type(),
+            failed_instruction_index: err.failed_instruction_index().map(u32::from),
+            instruction_error_type: err.instruction_error_type(),
+            custom_error_code: err.custom_error_code(),
+        }
+    }
+}
+
+impl From<solana_sdk::transaction::TransactionError> for TransactionError {
+    fn from(err: solana_sdk::transaction::TransactionError) -> Self {
+        TransactionError::from(&TransactionErrorResponse::from(err))
+    }
+}
+
+impl TryFrom<UiTransactionStatusMeta> for TransactionStatusMeta {
+    type Error = TransformError;
+
+    fn try_from(item: UiTransactionStatusMeta) -> Result<Self, Self::Error> {
+        Ok(TransactionStatusMeta {
             err: match item.status {
                 Ok(()) => None,
-                Err(err) => Some(TransactionError {
-                    err: err.to_string(),
-                }),
+                Err(err) => Some(TransactionError::from(err)),
             },
             fee: item.fee,
             pre_balances: item.pre_balances,
@@ -568,7 +621,10 @@ impl From<UiTransactionStatusMeta> for TransactionStatusMeta {
             inner_instructions_none: item.inner_instructions == OptionSerializer::None,
             inner_instructions: match item.inner_instructions {
                 OptionSerializer::None | OptionSerializer::Skip => Vec::new(),
-                OptionSerializer::Some(ii) => ii.into_iter().map(InnerInstructions::from).collect(),
+                OptionSerializer::Some(ii) => ii
+                    .into_iter()
+                    .map(InnerInstructions::try_from)
+                    .collect::<Result<_, _>>()?,
             },
             log_messages_none: item.log_messages == OptionSerializer::None,
             log_messages: match item.log_messages {
@@ -595,7 +651,17 @@ impl From<UiTransactionStatusMeta> for TransactionStatusMeta {
                 OptionSerializer::Some(rd) => Some(ReturnData::from(rd)),
             },
             compute_units_consumed: Option::<u64>::from(item.compute_units_consumed),
-        }
+            // NOTE: with the jsonParsed encoding, the loaded addresses are already part of the
+            // message's account keys and are skipped here.
+            loaded_writable_addresses: match &item.loaded_addresses {
+                OptionSerializer::None | OptionSerializer::Skip => Vec::new(),
+                OptionSerializer::Some(la) => la.writable.to_owned(),
+            },
+            loaded_readonly_addresses: match item.loaded_addresses {
+                OptionSerializer::None | OptionSerializer::Skip => Vec::new(),
+                OptionSerializer::Some(la) => la.readonly,
+            },
+        })
     }
 }
 
@@ -634,10 +700,12 @@ impl From<ParsedAccount> for CompiledAccount {
     }
 }
 
-impl From<UiMessage> for Message {
-    fn from(item: UiMessage) -> Self {
+impl TryFrom<UiMessage> for Message {
+    type Error = TransformError;
+
+    fn try_from(item: UiMessage) -> Result<Self, Self::Error> {
         match item {
-            UiMessage::Parsed(message) => Message {
+            UiMessage::Parsed(message) => Ok(Message {
                 header: None,
                 account_keys: message
                     .account_keys
@@ -648,8 +716,12 @@ impl From<UiMessage> for Message {
                 instructions: message
                     .instructions
                     .into_iter()
-                    .map(InnerInstruction::from)
-                    .collect(),
+                    .enumerate()
+                    .map(|(index, instruction)| {
+                        InnerInstruction::try_from(instruction)
+                            .map_err(|error| error.at_instruction(index))
+                    })
+                    .collect::<Result<_, _>>()?,
                 versioned: false, // TODO: what is this field for?
                 address_table_lookups: message
                     .address_table_lookups
@@ -657,43 +729,49 @@ impl From<UiMessage> for Message {
                     .into_iter()
                     .map(MessageAddressTableLookup::from)
                     .collect(),
-            },
-            UiMessage::Raw(_) => panic!("FATAL: should be using JsonParsed encoding."),
+            }),
+            UiMessage::Raw(_) => Err(TransformErrorKind::UnexpectedEncoding("the message").into()),
         }
     }
 }
 
-impl From<UiTransaction> for Transaction {
-    fn from(item: UiTransaction) -> Self {
-        Transaction {
+impl TryFrom<UiTransaction> for Transaction {
+    type Error = TransformError;
+
+    fn try_from(item: UiTransaction) -> Result<Self, Self::Error> {
+        Ok(Transaction {
             signatures: item.signatures,
-            message: Some(Message::from(item.message)),
-        }
+            message: Some(Message::try_from(item.message)?),
+        })
     }
 }
 
-impl From<EncodedTransaction> for Transaction {
-    fn from(item: EncodedTransaction) -> Self {
+impl TryFrom<EncodedTransaction> for Transaction {
+    type Error = TransformError;
+
+    fn try_from(item: EncodedTransaction) -> Result<Self, Self::Error> {
         match item {
-            EncodedTransaction::Json(t) => Transaction {
-                signatures: t.signatures,
-                message: Some(Message::from(t.message)),
-            },
-            EncodedTransaction::Accounts(_) => panic!("FATAL: should be using JsonParsed encoding"),
-            EncodedTransaction::LegacyBinary(_) | EncodedTransaction::Binary(..) => {
-                panic!("FATAL: should be using JsonParsed encoding")
+            EncodedTransaction::Json(t) => Transaction::try_from(t),
+            EncodedTransaction::Accounts(_)
+            | EncodedTransaction::LegacyBinary(_)
+            | EncodedTransaction::Binary(..) => {
+                Err(TransformErrorKind::UnexpectedEncoding("the transaction").into())
             }
         }
     }
 }
 
-impl From<EncodedTransactionWithStatusMeta> for ConfirmedTransaction {
-    fn from(item: EncodedTransactionWithStatusMeta) -> Self {
-        ConfirmedTransaction {
-            transaction: Some(Transaction::from(item.transaction)),
-            meta: item.meta.map(TransactionStatusMeta::from),
-            // NOTE: the transaction version is stored in the EncodedTransactionWithStatusMeta.
-            // we may want to carry that info forward to the generated type.
-        }
+impl TryFrom<EncodedTransactionWithStatusMeta> for ConfirmedTransaction {
+    type Error = TransformError;
+
+    fn try_from(item: EncodedTransactionWithStatusMeta) -> Result<Self, Self::Error> {
+        Ok(ConfirmedTransaction {
+            transaction: Some(Transaction::try_from(item.transaction)?),
+            meta: item.meta.map(TransactionStatusMeta::try_from).transpose()?,
+            version: item.version.map(|version| match version {
+                TransactionVersion::Legacy(_) => String::from("legacy"),
+                TransactionVersion::Number(number) => number.to_string(),
+            }),
+        })
     }
 }
@@ -0,0 +1,153 @@
+//! this file contains the reorder buffer used to publish blocks in the order their slots were
+//! queued, whatever order the workers finish them in.
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use crate::config::Config;
+
+/// The default number of slots that may be in flight between the queue and the reorder stage.
+const DEFAULT_CAPACITY: usize = 100;
+/// The default number of seconds the reorder stage waits for the next slot.
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+/// What the reorder stage does when the next slot takes longer than the timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StuckSlotPolicy {
+    /// move on without the slot, and publish its block out of order whenever it finishes
+    Skip,
+    /// stop the indexer
+    Abort,
+}
+
+/// A slot that did not finish within the timeout, and aborted the indexer.
+#[derive(Debug, Error)]
+#[error("slot {slot} did not finish within {timeout:?}")]
+pub struct StuckSlotError {
+    pub slot: u64,
+    pub timeout: Duration,
+}
+
+/// The settings of the ordered publishing mode.
+#[derive(Debug, Clone)]
+pub struct ReorderConfig {
+    /// the maximum number of slots in flight between the queue and the reorder stage, which
+    /// bounds the number of finished blocks held back
+    pub capacity: usize,
+    pub timeout: Duration,
+    pub on_timeout: StuckSlotPolicy,
+}
+
+impl ReorderConfig {
+    /// Reads the ordered publishing settings from the config, returning `None` unless
+    /// `ORDERED_PUBLISHING` is enabled.
+    pub fn from_config(config: &Config) -> Option<ReorderConfig> {
+        // env:ordered_publishing = Whether blocks are published in the order their slots were queued
+        if !config.ordered_publishing.unwrap_or(false) {
+            return None;
+        }
+        // env:ordered_publishing_capacity = The maximum number of slots in flight in ordered mode
+        let capacity = config
+            .ordered_publishing_capacity
+            .unwrap_or(DEFAULT_CAPACITY);
+        assert!(
+            capacity > 0,
+            "ORDERED_PUBLISHING_CAPACITY should be a positive integer"
+        );
+        // env:ordered_publishing_timeout = The number of seconds to wait for the next slot in ordered mode
+        let timeout_secs = config
+            .ordered_publishing_timeout
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        // env:ordered_publishing_on_timeout = Whether to `skip` a stuck slot or `abort` the indexer
+        let on_timeout = match config.ordered_publishing_on_timeout.as_deref() {
+            None | Some("skip") => StuckSlotPolicy::Skip,
+            Some("abort") => StuckSlotPolicy::Abort,
+            Some(policy) => panic!(
+                "ORDERED_PUBLISHING_ON_TIMEOUT should be either `skip` or `abort`, not {:?}",
+                policy
+            ),
+        };
+
+        Some(ReorderConfig {
+            capacity,
+            timeout: Duration::from_secs(timeout_secs),
+            on_timeout,
+        })
+    }
+}
+
+/// Holds back finished items until every slot queued before theirs is finished.
+pub struct ReorderBuffer<T> {
+    /// the slots that weren't released yet, in the order they were queued
+    expected: VecDeque<u64>,
+    /// the finished slots that weren't released yet, with `None` for slots without a block
+    finished: HashMap<u64, Option<T>>,
+    /// the slots that were skipped while unfinished, released as soon as they finish
+    late: HashSet<u64>,
+    /// when the reorder buffer started waiting for the slot at the head of the queue
+    head_since: Instant,
+}
+
+impl<T> Default for ReorderBuffer<T> {
+    fn default() -> Self {
+        ReorderBuffer {
+            expected: VecDeque::new(),
+            finished: HashMap::new(),
+            late: HashSet::new(),
+            head_since: Instant::now(),
+        }
+    }
+}
+
+impl<T> ReorderBuffer<T> {
+    /// Adds the slot to the end of the queue.
+    pub fn expect(&mut self, slot: u64) {
+        if self.expected.is_empty() {
+            self.head_since = Instant::now();
+        }
+        self.expected.push_back(slot);
+    }
+
+    /// Stores the finished item of the slot until it can be released.  Returns the item back if
+    /// the slot was skipped, since nothing is waiting for it anymore.
+    pub fn finish(&mut self, slot: u64, item: Option<T>) -> Option<T> {
+        if self.late.remove(&slot) {
+            return item;
+        }
+        self.finished.insert(slot, item);
+        None
+    }
+
+    /// Releases the slot at the head of the queue, if it is finished.
+    pub fn pop_ready(&mut self) -> Option<(u64, Option<T>)> {
+        let head = *self.expected.front()?;
+        let item = self.finished.remove(&head)?;
+        self.expected.pop_front();
+        self.head_since = Instant::now();
+        Some((head, item))
+    }
+
+    /// Gives up on the slot at the head of the queue.
+    pub fn skip_head(&mut self) -> Option<u64> {
+        let head = self.expected.pop_front()?;
+        self.late.insert(head);
+        self.head_since = Instant::now();
+        Some(head)
+    }
+
+    /// Whether the slot at the head of the queue has been waited on for longer than the timeout.
+    pub fn is_stuck(&self, timeout: Duration) -> bool {
+        !self.expected.is_empty() && self.head_since.elapsed() >= timeout
+    }
+
+    /// How long until the slot at the head of the queue is stuck, or `None` if no slot is queued.
+    pub fn time_until_stuck(&self, timeout: Duration) -> Option<Duration> {
+        if self.expected.is_empty() {
+            return None;
+        }
+        Some(timeout.saturating_sub(self.head_since.elapsed()))
+    }
+}
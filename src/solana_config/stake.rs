@@ -0,0 +1,39 @@
+//! this file contains helper functions for decoding [stake accounts](https://docs.solana.com/staking/stake-accounts).
+use super::{
+    accounts::parse_amount,
+    proto_codegen::account_info as solana_account_protobuf,
+    types::account_response_types::{StakeMetaResponse, StakeStakeResponse},
+};
+
+/// the name of the stake program, as returned by the jsonParsed encoding.
+pub const STAKE_PROGRAM: &str = "stake";
+
+/// parses an epoch returned as a string, where u64::MAX means the epoch was never reached.
+fn parse_epoch(field: &str, epoch: &str) -> Option<u64> {
+    parse_amount(field, epoch).filter(|epoch| *epoch != u64::MAX)
+}
+
+/// decodes the meta and (once delegated) the stake of a stake account.
+pub fn decode_stake_account(
+    meta: &StakeMetaResponse,
+    stake: Option<&StakeStakeResponse>,
+) -> solana_account_protobuf::StakeState {
+    let delegation = stake.map(|s| &s.delegation);
+    solana_account_protobuf::StakeState {
+        rent_exempt_reserve: parse_amount("stake rent exempt reserve", &meta.rentExemptReserve)
+            .unwrap_or_default(),
+        staker: meta.authorized.staker.to_owned(),
+        withdrawer: meta.authorized.withdrawer.to_owned(),
+        lockup_unix_timestamp: meta.lockup.unixTimestamp,
+        lockup_epoch: meta.lockup.epoch,
+        lockup_custodian: meta.lockup.custodian.to_owned(),
+        voter: delegation.map(|d| d.voter.to_owned()),
+        delegated_stake: delegation.and_then(|d| parse_amount("stake delegation", &d.stake)),
+        activation_epoch: delegation
+            .and_then(|d| parse_epoch("stake activation epoch", &d.activationEpoch)),
+        deactivation_epoch: delegation
+            .and_then(|d| parse_epoch("stake deactivation epoch", &d.deactivationEpoch)),
+        warmup_cooldown_rate: delegation.map(|d| d.warmupCooldownRate),
+        credits_observed: stake.map(|s| s.creditsObserved),
+    }
+}
@@ -4,6 +4,7 @@
 // Conditional imports
 use crate as blockchain_generic;
 
+use blockchain_generic::config::Config;
 use blockchain_generic::output::publish::StreamPublisherConnection;
 use log::info;
 
@@ -26,23 +27,67 @@ pub struct StreamPublisher {
     pub tokens: StreamPublisherConnection,
     pub token_transfers: StreamPublisherConnection,
     pub accounts: StreamPublisherConnection,
+    pub events: StreamPublisherConnection,
+    pub native_transfers: StreamPublisherConnection,
+    pub stake_actions: StreamPublisherConnection,
 }
 
 #[cfg(feature = "SEPARATE_PUBLISHERS")]
 impl StreamPublisher {
-    pub async fn new() -> StreamPublisher {
+    /// Connects to the queue of each table, named by the `QUEUE_NAME_*` settings.
+    pub async fn new(config: &Config) -> StreamPublisher {
         info!("Connecting to the publishers...");
+        let queue_name = |name: &Option<String>| {
+            name.clone()
+                .expect("the queue names of the separate publishers are validated")
+        };
         StreamPublisher {
-            blocks: connect("QUEUE_NAME_BLOCKS").await,
-            block_rewards: connect("QUEUE_NAME_BLOCK_REWARDS").await,
-            transactions: connect("QUEUE_NAME_TRANSACTIONS").await,
-            instructions: connect("QUEUE_NAME_INSTRUCTIONS").await,
-            token_transfers: connect("QUEUE_NAME_TOKEN_TRANSFERS").await,
-            tokens: connect("QUEUE_NAME_TOKENS").await,
-            accounts: connect("QUEUE_NAME_ACCOUNTS").await,
+            blocks: connect(config, &queue_name(&config.queue_name_blocks)).await,
+            block_rewards: connect(config, &queue_name(&config.queue_name_block_rewards)).await,
+            transactions: connect(config, &queue_name(&config.queue_name_transactions)).await,
+            instructions: connect(config, &queue_name(&config.queue_name_instructions)).await,
+            token_transfers: connect(config, &queue_name(&config.queue_name_token_transfers)).await,
+            tokens: connect(config, &queue_name(&config.queue_name_tokens)).await,
+            accounts: connect(config, &queue_name(&config.queue_name_accounts)).await,
+            events: connect(config, &queue_name(&config.queue_name_events)).await,
+            native_transfers: connect(config, &queue_name(&config.queue_name_native_transfers))
+                .await,
+            stake_actions: connect(config, &queue_name(&config.queue_name_stake_actions)).await,
         }
     }
 
+    /// Creates the channel of each publisher in the current thread (RabbitMQ-Classic specific).
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    pub async fn with_channels(self) -> StreamPublisher {
+        StreamPublisher {
+            blocks: self.blocks.with_channel().await,
+            block_rewards: self.block_rewards.with_channel().await,
+            transactions: self.transactions.with_channel().await,
+            instructions: self.instructions.with_channel().await,
+            token_transfers: self.token_transfers.with_channel().await,
+            tokens: self.tokens.with_channel().await,
+            accounts: self.accounts.with_channel().await,
+            events: self.events.with_channel().await,
+            native_transfers: self.native_transfers.with_channel().await,
+            stake_actions: self.stake_actions.with_channel().await,
+        }
+    }
+
+    /// Closes the channel of each publisher created by `with_channels` (RabbitMQ-Classic specific).
+    #[cfg(feature = "RABBITMQ_CLASSIC")]
+    pub async fn close_channels(self) {
+        self.blocks.close_channel().await;
+        self.block_rewards.close_channel().await;
+        self.transactions.close_channel().await;
+        self.instructions.close_channel().await;
+        self.token_transfers.close_channel().await;
+        self.tokens.close_channel().await;
+        self.accounts.close_channel().await;
+        self.events.close_channel().await;
+        self.native_transfers.close_channel().await;
+        self.stake_actions.close_channel().await;
+    }
+
     pub async fn disconnect(self) {
         info!("Disconnecting from publishers...");
         self.blocks.disconnect().await;
@@ -52,5 +97,8 @@ impl StreamPublisher {
         self.token_transfers.disconnect().await;
         self.tokens.disconnect().await;
         self.accounts.disconnect().await;
+        self.events.disconnect().await;
+        self.native_transfers.disconnect().await;
+        self.stake_actions.disconnect().await;
     }
 }
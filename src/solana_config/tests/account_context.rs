@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        accounts::{
+            contextual_accounts, package_accounts, KeyedTimestampedAccounts, TimestampedAccounts,
+        },
+        instruction_decoders::InstructionDecoderRegistry,
+        proto_codegen::{
+            account_info::AccountInfo,
+            etl_block::{EtlBlock, TableContext},
+        },
+        transformation::account::transform_to_account_and_token_records,
+        types::account_response_types::AccountResponse,
+    };
+
+    /// a getMultipleAccounts response evaluated at slot 1_050, with a field the RPC node may add
+    /// in a later version and without the optional `apiVersion`.
+    const ACCOUNTS_RESPONSE: &str = r#"{
+        "jsonrpc": "2.0",
+        "result": {
+            "context": {"slot": 1050, "unexpectedContextField": true},
+            "value": [
+                {
+                    "data": ["AQID", "base64"],
+                    "executable": false,
+                    "lamports": 1000000,
+                    "owner": "Owner11111111111111111111111111111111111111",
+                    "rentEpoch": 18446744073709551615,
+                    "space": 3,
+                    "unexpectedAccountField": "value"
+                },
+                null
+            ]
+        },
+        "id": 1
+    }"#;
+
+    /// the response to a second chunk of the same request, evaluated at a later slot.
+    const LATER_CHUNK_RESPONSE: &str = r#"{
+        "jsonrpc": "2.0",
+        "result": {
+            "context": {"slot": 1060},
+            "value": [
+                {
+                    "data": ["BAUG", "base64"],
+                    "executable": false,
+                    "lamports": 2000000,
+                    "owner": "Owner11111111111111111111111111111111111111",
+                    "rentEpoch": 18446744073709551615,
+                    "space": 3
+                }
+            ]
+        },
+        "id": 1
+    }"#;
+
+    fn timestamped_accounts(responses: &[&str]) -> TimestampedAccounts {
+        TimestampedAccounts {
+            timestamp: 1_700_000_000,
+            accounts: responses
+                .iter()
+                .flat_map(|response| {
+                    let response: AccountResponse = serde_json::from_str(response).unwrap();
+                    contextual_accounts(response.result.unwrap())
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_state_drift_from_the_context_slot() {
+        let keyed_accounts = KeyedTimestampedAccounts::from_keys_and_accounts(
+            vec![
+                String::from("account0"),
+                String::from("account1"),
+                String::from("account2"),
+            ],
+            timestamped_accounts(&[ACCOUNTS_RESPONSE, LATER_CHUNK_RESPONSE]),
+        );
+        let accounts = package_accounts(keyed_accounts);
+        // the missing account is skipped
+        assert_eq!(accounts.len(), 2);
+
+        let etl_block = EtlBlock {
+            slot: 1_000,
+            accounts: vec![AccountInfo::new(
+                String::from("signature"),
+                accounts,
+                Vec::new(),
+            )],
+            table_context: Some(TableContext::default()),
+            ..Default::default()
+        };
+        let (account_records, _) =
+            transform_to_account_and_token_records(&etl_block, &InstructionDecoderRegistry::new())
+                .unwrap();
+
+        assert_eq!(account_records.len(), 2);
+        assert_eq!(account_records[0].pubkey.as_deref(), Some("account0"));
+        assert_eq!(account_records[0].context_slot, Some(1_050));
+        assert_eq!(account_records[0].state_drift_slots, Some(50));
+        // each account keeps the context slot of its own chunk
+        assert_eq!(account_records[1].pubkey.as_deref(), Some("account2"));
+        assert_eq!(account_records[1].context_slot, Some(1_060));
+        assert_eq!(account_records[1].state_drift_slots, Some(60));
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use solana_transaction_status::{EncodedTransaction, EncodedTransactionWithStatusMeta};
+
+    use crate::solana_config::transactions::{
+        get_pubkeys_from_transactions, AccountDiscovery, TransactionAccounts,
+    };
+
+    /// a transaction that creates an account with a system instruction, then creates an associated
+    /// token account whose creation and initialization happen in inner instructions.
+    fn transaction() -> EncodedTransactionWithStatusMeta {
+        serde_json::from_value(serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "accountKeys": [
+                        {"pubkey": "payer", "writable": true, "signer": true, "source": "transaction"},
+                        {"pubkey": "new_account", "writable": true, "signer": true, "source": "transaction"},
+                        {"pubkey": "associated_account", "writable": true, "signer": false, "source": "transaction"},
+                        {"pubkey": "counter", "writable": true, "signer": false, "source": "lookupTable"},
+                        {"pubkey": "mint", "writable": false, "signer": false, "source": "transaction"}
+                    ],
+                    "recentBlockhash": "blockhash",
+                    "instructions": [
+                        {
+                            "program": "system",
+                            "programId": "11111111111111111111111111111111",
+                            "parsed": {
+                                "type": "createAccount",
+                                "info": {
+                                    "source": "payer",
+                                    "newAccount": "new_account",
+                                    "lamports": 1461600,
+                                    "space": 82,
+                                    "owner": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+                                }
+                            },
+                            "stackHeight": null
+                        },
+                        {
+                            "program": "spl-associated-token-account",
+                            "programId": "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+                            "parsed": {
+                                "type": "create",
+                                "info": {
+                                    "source": "payer",
+                                    "account": "associated_account",
+                                    "wallet": "payer",
+                                    "mint": "mint"
+                                }
+                            },
+                            "stackHeight": null
+                        }
+                    ]
+                }
+            },
+            "meta": {
+                "err": null,
+                "status": {"Ok": null},
+                "fee": 5000,
+                "preBalances": [],
+                "postBalances": [],
+                "innerInstructions": [{
+                    "index": 1,
+                    "instructions": [
+                        {
+                            "program": "system",
+                            "programId": "11111111111111111111111111111111",
+                            "parsed": {
+                                "type": "createAccountWithSeed",
+                                "info": {
+                                    "source": "payer",
+                                    "newAccount": "seeded_account",
+                                    "base": "payer",
+                                    "seed": "seed",
+                                    "lamports": 2039280,
+                                    "space": 165,
+                                    "owner": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+                                }
+                            },
+                            "stackHeight": 2
+                        },
+                        {
+                            "program": "spl-token",
+                            "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+                            "parsed": {
+                                "type": "initializeAccount3",
+                                "info": {"account": "associated_account", "mint": "mint", "owner": "payer"}
+                            },
+                            "stackHeight": 2
+                        }
+                    ]
+                }],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            }
+        }))
+        .unwrap()
+    }
+
+    fn discover(discovery: AccountDiscovery) -> Vec<TransactionAccounts> {
+        let transaction_with_meta = transaction();
+        let EncodedTransaction::Json(transaction) = &transaction_with_meta.transaction else {
+            panic!("expected a json transaction");
+        };
+        get_pubkeys_from_transactions(
+            vec![(transaction, transaction_with_meta.meta.as_ref())],
+            discovery,
+        )
+    }
+
+    #[test]
+    fn test_discover_created_accounts() {
+        let transaction_accounts = discover(AccountDiscovery::Created);
+
+        assert_eq!(transaction_accounts.len(), 1);
+        assert_eq!(transaction_accounts[0].tx_signature, "signature");
+        assert_eq!(
+            transaction_accounts[0].accounts,
+            vec!["new_account", "associated_account", "seeded_account"]
+        );
+    }
+
+    #[test]
+    fn test_discover_created_accounts_initial_state() {
+        let transaction_accounts = discover(AccountDiscovery::Created);
+        let created_accounts = &transaction_accounts[0].created_accounts;
+
+        // the associated token account isn't created by the system program
+        assert_eq!(created_accounts.len(), 2);
+        assert_eq!(created_accounts[0].pubkey, "new_account");
+        assert_eq!(created_accounts[0].lamports, 1461600);
+        assert_eq!(created_accounts[0].space, 82);
+        assert_eq!(created_accounts[1].pubkey, "seeded_account");
+        assert_eq!(created_accounts[1].lamports, 2039280);
+        assert_eq!(
+            created_accounts[1].owner,
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+        );
+    }
+
+    #[test]
+    fn test_discover_writable_accounts() {
+        let transaction_accounts = discover(AccountDiscovery::Writable);
+
+        assert_eq!(
+            transaction_accounts[0].accounts,
+            vec![
+                "new_account",
+                "associated_account",
+                "seeded_account",
+                "payer",
+                "counter"
+            ]
+        );
+        assert_eq!(transaction_accounts[0].created_accounts.len(), 2);
+    }
+}
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        constants,
+        proto_codegen::confirmed_block::InnerInstruction,
+        transformation::compute_budget::{base_fee, parse_compute_budget, ComputeBudget},
+    };
+
+    fn compute_budget_instruction(data: Vec<u8>) -> InnerInstruction {
+        InnerInstruction {
+            program_id: Some(constants::COMPUTE_BUDGET_PROGRAM_ID_STR.to_string()),
+            data: Some(solana_sdk::bs58::encode(data).into_string()),
+            ..Default::default()
+        }
+    }
+
+    fn other_instruction() -> InnerInstruction {
+        InnerInstruction {
+            program_id: Some(String::from("11111111111111111111111111111111")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_priority_fee_with_limit() {
+        let mut set_limit = vec![2];
+        set_limit.extend_from_slice(&300_000u32.to_le_bytes());
+        let mut set_price = vec![3];
+        set_price.extend_from_slice(&10_001u64.to_le_bytes());
+        let mut heap_frame = vec![1];
+        heap_frame.extend_from_slice(&(256 * 1024u32).to_le_bytes());
+
+        let compute_budget = parse_compute_budget(&[
+            compute_budget_instruction(set_limit),
+            compute_budget_instruction(set_price),
+            compute_budget_instruction(heap_frame),
+            other_instruction(),
+        ]);
+        assert_eq!(
+            compute_budget,
+            ComputeBudget {
+                compute_unit_limit: Some(300_000),
+                compute_unit_price: Some(10_001),
+                heap_frame_bytes: Some(256 * 1024),
+                // 300,000 CU * 10,001 micro-lamports = 3,000.3 lamports, rounded up
+                priority_fee: 3_001,
+            }
+        );
+    }
+
+    #[test]
+    fn test_priority_fee_with_default_limit() {
+        let mut set_price = vec![3];
+        set_price.extend_from_slice(&1_000_000u64.to_le_bytes());
+
+        let compute_budget = parse_compute_budget(&[
+            compute_budget_instruction(set_price),
+            other_instruction(),
+            other_instruction(),
+        ]);
+        assert_eq!(compute_budget.compute_unit_limit, None);
+        // two instructions at 200,000 CU each, at 1 lamport per CU
+        assert_eq!(compute_budget.priority_fee, 400_000);
+
+        assert_eq!(parse_compute_budget(&[other_instruction()]).priority_fee, 0);
+        assert_eq!(base_fee(2), 10_000);
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use borsh::BorshSerialize;
+    use serde_json::json;
+
+    use crate::solana_config::{
+        instruction_decoders::{anchor::AnchorDecoder, InstructionDecoderRegistry},
+        tests::fixtures::common_table_fields,
+        transformation::event::transform_to_event_records,
+    };
+
+    const OUTER_PROGRAM_ID: &str = "Outer11111111111111111111111111111111111111";
+    const EMITTER_PROGRAM_ID: &str = "Emitter1111111111111111111111111111111111111";
+
+    const EMITTER_IDL: &str = r#"{
+        "address": "Emitter1111111111111111111111111111111111111",
+        "metadata": {"name": "emitter"},
+        "events": [{"name": "Deposited", "discriminator": [1, 2, 3, 4, 5, 6, 7, 8]}],
+        "types": [
+            {
+                "name": "Deposited",
+                "type": {"kind": "struct", "fields": [{"name": "amount", "type": "u64"}]}
+            }
+        ]
+    }"#;
+
+    fn emitter_registry() -> InstructionDecoderRegistry {
+        let mut registry = InstructionDecoderRegistry::new();
+        registry.register(Box::new(
+            AnchorDecoder::new(serde_json::from_str(EMITTER_IDL).unwrap()).unwrap(),
+        ));
+        registry
+    }
+
+    #[test]
+    fn test_event_records() {
+        let registry = emitter_registry();
+
+        let mut deposited = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        500u64.serialize(&mut deposited).unwrap();
+        let encode = |data: &[u8]| base64::engine::general_purpose::STANDARD.encode(data);
+
+        let logs: Vec<String> = vec![
+            format!("Program {} invoke [1]", OUTER_PROGRAM_ID),
+            format!("Program {} invoke [2]", EMITTER_PROGRAM_ID),
+            format!("Program data: {}", encode(&deposited)),
+            format!(
+                "Program {} consumed 2000 of 190000 compute units",
+                EMITTER_PROGRAM_ID
+            ),
+            format!("Program {} success", EMITTER_PROGRAM_ID),
+            format!("Program data: {} {}", encode(b"ab"), encode(b"cd")),
+            format!("Program {} success", OUTER_PROGRAM_ID),
+        ];
+
+        let records = transform_to_event_records(common_table_fields(), &logs, &registry);
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0].program_id.as_deref(), Some(EMITTER_PROGRAM_ID));
+        assert_eq!(records[0].index, Some(0));
+        assert_eq!(records[0].data.as_deref(), Some(deposited.as_slice()));
+        assert_eq!(records[0].event_type.as_deref(), Some("Deposited"));
+        assert_eq!(
+            records[0].decoded_data,
+            Some(json!({"amount": 500}).to_string())
+        );
+
+        // the inner program has returned, so the event belongs to the outer program
+        assert_eq!(records[1].program_id.as_deref(), Some(OUTER_PROGRAM_ID));
+        assert_eq!(records[1].index, Some(1));
+        assert_eq!(records[1].data.as_deref(), Some(b"abcd".as_slice()));
+        assert_eq!(records[1].event_type, None);
+    }
+
+    #[test]
+    fn test_program_log_is_not_an_invocation_end() {
+        let registry = emitter_registry();
+        let encode = |data: &[u8]| base64::engine::general_purpose::STANDARD.encode(data);
+
+        let logs: Vec<String> = vec![
+            format!("Program {} invoke [1]", OUTER_PROGRAM_ID),
+            format!("Program {} invoke [2]", EMITTER_PROGRAM_ID),
+            format!("Program data: {}", encode(b"ab")),
+            String::from("Program log: success"),
+            format!("Program data: {}", encode(b"cd")),
+            format!("Program {} success", EMITTER_PROGRAM_ID),
+            format!("Program {} success", OUTER_PROGRAM_ID),
+        ];
+
+        let records = transform_to_event_records(common_table_fields(), &logs, &registry);
+        assert_eq!(records.len(), 2);
+        // the program's own log line does not end its invocation
+        assert_eq!(records[0].program_id.as_deref(), Some(EMITTER_PROGRAM_ID));
+        assert_eq!(records[1].program_id.as_deref(), Some(EMITTER_PROGRAM_ID));
+    }
+}
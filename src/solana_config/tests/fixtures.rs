@@ -0,0 +1,44 @@
+//! Builders for the records and instructions shared by the transformation tests.
+use crate::solana_config::{
+    proto_codegen::confirmed_block::{CompiledAccount, InnerInstruction, Parsed},
+    transformation::transaction::CommonTableFields,
+};
+
+pub fn common_table_fields() -> CommonTableFields {
+    CommonTableFields {
+        block_slot: Some(1),
+        block_hash: None,
+        block_timestamp: None,
+        tx_signature: Some(String::from("signature")),
+    }
+}
+
+pub fn account(pubkey: &str) -> CompiledAccount {
+    CompiledAccount {
+        pubkey: pubkey.to_string(),
+        ..Default::default()
+    }
+}
+
+/// an instruction as parsed by the RPC node, with its `info` as a JSON string.
+pub fn parsed_instruction(
+    program: &str,
+    program_id: &str,
+    r#type: &str,
+    info: &str,
+) -> InnerInstruction {
+    InnerInstruction {
+        program: Some(program.to_string()),
+        program_id: Some(program_id.to_string()),
+        parsed_dict: Some(Parsed {
+            r#type: Some(r#type.to_string()),
+            info: Some(info.to_string()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+pub fn system_instruction(r#type: &str, info: &str) -> InnerInstruction {
+    parsed_instruction("system", "11111111111111111111111111111111", r#type, info)
+}
@@ -0,0 +1,236 @@
+#[cfg(test)]
+mod tests {
+    use base64::Engine;
+    use borsh::BorshSerialize;
+    use serde_json::{json, Value};
+
+    use crate::solana_config::{
+        constants,
+        instruction_decoders::{
+            anchor::AnchorDecoder, DecodedInstruction, InstructionDecoder,
+            InstructionDecoderRegistry,
+        },
+        proto_codegen::confirmed_block::InnerInstruction,
+    };
+
+    fn unparsed_instruction(program_id: &str, data: &[u8]) -> InnerInstruction {
+        InnerInstruction {
+            program_id: Some(program_id.to_string()),
+            data: Some(solana_sdk::bs58::encode(data).into_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode_compute_budget_instructions() {
+        let registry = InstructionDecoderRegistry::with_builtin_decoders();
+
+        let mut data = vec![3];
+        data.extend_from_slice(&25_000u64.to_le_bytes());
+        let (program, decoded) = registry
+            .decode(&unparsed_instruction(
+                constants::COMPUTE_BUDGET_PROGRAM_ID_STR,
+                &data,
+            ))
+            .unwrap();
+        assert_eq!(program, "compute-budget");
+        assert_eq!(decoded.instruction_type, "setComputeUnitPrice");
+        assert_eq!(
+            decoded.params,
+            vec![(String::from("microLamports"), Value::from(25_000u64))]
+        );
+
+        // truncated data is not decoded
+        let instruction = unparsed_instruction(constants::COMPUTE_BUDGET_PROGRAM_ID_STR, &[2, 1]);
+        assert!(registry.decode(&instruction).is_none());
+    }
+
+    #[test]
+    fn test_decode_token_metadata_instruction_types() {
+        let registry = InstructionDecoderRegistry::with_builtin_decoders();
+
+        let (_, decoded) = registry
+            .decode(&unparsed_instruction(
+                constants::METADATA_PROGRAM_ID_STR,
+                &[33, 0, 0],
+            ))
+            .unwrap();
+        assert_eq!(decoded.instruction_type, "createMetadataAccountV3");
+
+        let (_, decoded) = registry
+            .decode(&unparsed_instruction(
+                constants::METADATA_PROGRAM_ID_STR,
+                &[44, 2],
+            ))
+            .unwrap();
+        assert_eq!(decoded.instruction_type, "delegateTransferV1");
+    }
+
+    struct MemoDecoder;
+
+    impl InstructionDecoder for MemoDecoder {
+        fn program_id(&self) -> &str {
+            "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"
+        }
+
+        fn program_name(&self) -> &str {
+            "memo"
+        }
+
+        fn decode(&self, data: &[u8], _accounts: &[String]) -> Option<DecodedInstruction> {
+            Some(DecodedInstruction {
+                instruction_type: String::from("memo"),
+                params: vec![(
+                    String::from("memo"),
+                    Value::from(String::from_utf8(data.to_vec()).ok()?),
+                )],
+            })
+        }
+    }
+
+    #[test]
+    fn test_registered_decoders() {
+        let mut registry = InstructionDecoderRegistry::new();
+        let instruction =
+            unparsed_instruction("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", b"hello");
+        assert!(registry.decode(&instruction).is_none());
+
+        registry.register(Box::new(MemoDecoder));
+        let (program, decoded) = registry.decode(&instruction).unwrap();
+        assert_eq!(program, "memo");
+        assert_eq!(
+            decoded.params,
+            vec![(String::from("memo"), Value::from("hello"))]
+        );
+    }
+
+    #[test]
+    fn test_missing_idl_directory_is_skipped() {
+        let mut registry = InstructionDecoderRegistry::with_builtin_decoders();
+        registry.load_anchor_idls(std::path::Path::new("this/idl/directory/does/not/exist"));
+        assert!(registry
+            .get(constants::COMPUTE_BUDGET_PROGRAM_ID_STR)
+            .is_some());
+    }
+
+    const COUNTER_PROGRAM_ID: &str = "Counter111111111111111111111111111111111111";
+
+    /// a legacy (Anchor < 0.30) IDL, without explicit discriminators
+    const COUNTER_IDL: &str = r#"{
+        "version": "0.1.0",
+        "name": "counter",
+        "instructions": [
+            {
+                "name": "setCount",
+                "accounts": [
+                    {"name": "counter", "isMut": true, "isSigner": false},
+                    {"name": "authority", "isMut": false, "isSigner": true}
+                ],
+                "args": [
+                    {"name": "count", "type": "u64"},
+                    {"name": "label", "type": {"option": "string"}},
+                    {"name": "mode", "type": {"defined": "Mode"}}
+                ]
+            }
+        ],
+        "accounts": [
+            {
+                "name": "Counter",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        {"name": "authority", "type": "publicKey"},
+                        {"name": "count", "type": "u64"},
+                        {"name": "history", "type": {"vec": "u16"}}
+                    ]
+                }
+            }
+        ],
+        "types": [
+            {"name": "Mode", "type": {"kind": "enum", "variants": [{"name": "Add"}, {"name": "Set"}]}}
+        ],
+        "metadata": {"address": "Counter111111111111111111111111111111111111"}
+    }"#;
+
+    /// the first 8 bytes of sha256(preimage), as Anchor derives its discriminators
+    fn discriminator(preimage: &str) -> Vec<u8> {
+        solana_sdk::hash::hash(preimage.as_bytes()).to_bytes()[..8].to_vec()
+    }
+
+    fn counter_registry() -> InstructionDecoderRegistry {
+        let mut registry = InstructionDecoderRegistry::new();
+        let decoder = AnchorDecoder::new(serde_json::from_str(COUNTER_IDL).unwrap()).unwrap();
+        registry.register(Box::new(decoder));
+        registry
+    }
+
+    #[test]
+    fn test_anchor_instruction() {
+        let registry = counter_registry();
+
+        let mut data = discriminator("global:set_count");
+        42u64.serialize(&mut data).unwrap();
+        Some(String::from("hello")).serialize(&mut data).unwrap();
+        1u8.serialize(&mut data).unwrap();
+        let mut instruction = unparsed_instruction(COUNTER_PROGRAM_ID, &data);
+        instruction.accounts = vec![String::from("CounterAccount"), String::from("Authority")];
+
+        let (program, decoded) = registry.decode(&instruction).unwrap();
+        assert_eq!(program, "counter");
+        assert_eq!(decoded.instruction_type, "setCount");
+        assert_eq!(
+            decoded.params,
+            vec![
+                (String::from("counter"), json!("CounterAccount")),
+                (String::from("authority"), json!("Authority")),
+                (String::from("count"), json!(42)),
+                (String::from("label"), json!("hello")),
+                (String::from("mode"), json!("Set")),
+            ]
+        );
+
+        // unknown discriminators are not decoded
+        let instruction = unparsed_instruction(COUNTER_PROGRAM_ID, &[0; 8]);
+        assert!(registry.decode(&instruction).is_none());
+    }
+
+    #[test]
+    fn test_anchor_account() {
+        let registry = counter_registry();
+
+        let authority = [7u8; 32];
+        let mut data = discriminator("account:Counter");
+        authority.serialize(&mut data).unwrap();
+        9u64.serialize(&mut data).unwrap();
+        vec![1u16, 2u16].serialize(&mut data).unwrap();
+        let data = base64::engine::general_purpose::STANDARD.encode(data);
+
+        let decoded = registry.decode_account(COUNTER_PROGRAM_ID, &data).unwrap();
+        assert_eq!(decoded.account_type, "Counter");
+        assert_eq!(
+            decoded.fields,
+            Some(json!({
+                "authority": solana_sdk::bs58::encode(authority).into_string(),
+                "count": 9,
+                "history": [1, 2],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_anchor_vec_length_is_bounded_by_the_data() {
+        let registry = counter_registry();
+
+        // a vec claiming more elements than the bytes left could hold
+        let mut data = discriminator("account:Counter");
+        [7u8; 32].serialize(&mut data).unwrap();
+        9u64.serialize(&mut data).unwrap();
+        u32::MAX.serialize(&mut data).unwrap();
+        1u16.serialize(&mut data).unwrap();
+        let data = base64::engine::general_purpose::STANDARD.encode(data);
+
+        let decoded = registry.decode_account(COUNTER_PROGRAM_ID, &data).unwrap();
+        assert_eq!(decoded.account_type, "Counter");
+        assert_eq!(decoded.fields, None);
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        proto_codegen::confirmed_block::{InnerInstruction, InnerInstructions},
+        transformation::invocation::{build_invocation_tree, parse_invocation_logs},
+    };
+
+    fn instruction(program_id: &str, stack_height: Option<u32>) -> InnerInstruction {
+        InnerInstruction {
+            program_id: Some(program_id.to_string()),
+            stack_height,
+            ..Default::default()
+        }
+    }
+
+    const ROUTER: &str = "Router1111111111111111111111111111111111111";
+    const POOL: &str = "Pair111111111111111111111111111111111111111";
+    const TOKEN: &str = "Token11111111111111111111111111111111111111";
+
+    fn logs() -> Vec<String> {
+        vec![
+            String::from("Program ComputeBudget111111111111111111111111111111 invoke [1]"),
+            String::from("Program ComputeBudget111111111111111111111111111111 success"),
+            format!("Program {} invoke [1]", ROUTER),
+            String::from("Program log: Instruction: Swap"),
+            format!("Program {} invoke [2]", POOL),
+            format!("Program {} invoke [3]", TOKEN),
+            format!("Program {} consumed 4645 of 180000 compute units", TOKEN),
+            format!("Program {} success", TOKEN),
+            format!("Program {} invoke [3]", TOKEN),
+            format!("Program {} consumed 4736 of 170000 compute units", TOKEN),
+            format!("Program {} failed: insufficient funds", TOKEN),
+            format!("Program {} consumed 30000 of 190000 compute units", POOL),
+            format!("Program {} failed: insufficient funds", POOL),
+            format!("Program {} consumed 40000 of 200000 compute units", ROUTER),
+            format!("Program {} failed: insufficient funds", ROUTER),
+        ]
+    }
+
+    #[test]
+    fn test_parse_invocation_logs() {
+        let invocations = parse_invocation_logs(&logs());
+
+        assert_eq!(invocations.len(), 5);
+        assert_eq!(invocations[0].success, Some(true));
+        assert_eq!(invocations[0].compute_units_consumed, None);
+        assert_eq!(invocations[2].program_id, POOL);
+        assert_eq!(invocations[2].depth, Some(2));
+        assert_eq!(invocations[2].compute_units_consumed, Some(30000));
+        assert_eq!(invocations[3].success, Some(true));
+        assert_eq!(invocations[4].success, Some(false));
+        assert_eq!(invocations[4].err.as_deref(), Some("insufficient funds"));
+    }
+
+    #[test]
+    fn test_build_invocation_tree() {
+        let instructions = vec![
+            instruction("ComputeBudget111111111111111111111111111111", None),
+            instruction(ROUTER, None),
+        ];
+        let inner_instructions = vec![InnerInstructions {
+            index: 1,
+            instructions: vec![
+                instruction(POOL, Some(2)),
+                instruction(TOKEN, Some(3)),
+                instruction(TOKEN, Some(3)),
+            ],
+        }];
+
+        let tree = build_invocation_tree(&instructions, &inner_instructions, &logs());
+
+        let router = tree.top_level(1).unwrap();
+        assert_eq!(router.stack_height, 1);
+        assert_eq!(router.invocation_path, "1");
+        assert_eq!(
+            router.log.as_ref().unwrap().compute_units_consumed,
+            Some(40000)
+        );
+
+        let paths: Vec<&str> = tree.inner[&1]
+            .iter()
+            .map(|invocation| invocation.invocation_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["1.0", "1.0.0", "1.0.1"]);
+
+        let failed_transfer = tree.inner(1, 2).unwrap();
+        assert_eq!(failed_transfer.stack_height, 3);
+        assert_eq!(failed_transfer.log.as_ref().unwrap().success, Some(false));
+    }
+
+    #[test]
+    fn test_stack_height_from_logs() {
+        let instructions = vec![
+            instruction("ComputeBudget111111111111111111111111111111", None),
+            instruction(ROUTER, None),
+        ];
+        let inner_instructions = vec![InnerInstructions {
+            index: 1,
+            instructions: vec![
+                instruction(POOL, None),
+                instruction(TOKEN, None),
+                instruction(TOKEN, None),
+            ],
+        }];
+
+        let tree = build_invocation_tree(&instructions, &inner_instructions, &[]);
+        let flat_paths: Vec<&str> = tree.inner[&1]
+            .iter()
+            .map(|invocation| invocation.invocation_path.as_str())
+            .collect();
+        assert_eq!(flat_paths, vec!["1.0", "1.1", "1.2"]);
+
+        let tree = build_invocation_tree(&instructions, &inner_instructions, &logs());
+        let paths: Vec<&str> = tree.inner[&1]
+            .iter()
+            .map(|invocation| invocation.invocation_path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["1.0", "1.0.0", "1.0.1"]);
+    }
+}
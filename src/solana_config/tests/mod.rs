@@ -1 +1,20 @@
+pub mod account_context;
+pub mod account_discovery;
+pub mod compute_budget;
+pub mod events;
+#[cfg(test)]
+pub mod fixtures;
+pub mod instruction_decoders;
+pub mod invocations;
+pub mod native_accounts;
+pub mod native_transfers;
+pub mod pipeline;
+pub mod reorder;
 pub mod solana;
+pub mod stake;
+pub mod token_cache;
+pub mod token_extensions;
+pub mod token_transfers;
+pub mod transaction_errors;
+pub mod transactions;
+pub mod transformation_errors;
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        native_accounts::{decode_native_account, NativeAccountState},
+        types::account_response_types::AccountInfoInfoStructureResponse,
+    };
+
+    fn decode(
+        program: &str,
+        account_type: &str,
+        info: serde_json::Value,
+    ) -> Option<NativeAccountState> {
+        let info: AccountInfoInfoStructureResponse = serde_json::from_value(info).unwrap();
+        decode_native_account(program, account_type, &info)
+    }
+
+    #[test]
+    fn test_decode_nonce_account() {
+        let Some(NativeAccountState::Nonce(nonce)) = decode(
+            "nonce",
+            "initialized",
+            serde_json::json!({
+                "authority": "authority",
+                "blockhash": "blockhash",
+                "feeCalculator": {"lamportsPerSignature": "5000"}
+            }),
+        ) else {
+            panic!("expected a nonce account");
+        };
+
+        assert_eq!(nonce.authority, "authority");
+        assert_eq!(nonce.blockhash, "blockhash");
+        assert_eq!(nonce.lamports_per_signature, Some(5000));
+    }
+
+    #[test]
+    fn test_decode_uninitialized_nonce_account() {
+        assert!(decode("nonce", "uninitialized", serde_json::json!({})).is_none());
+    }
+
+    #[test]
+    fn test_decode_active_lookup_table() {
+        let Some(NativeAccountState::LookupTable(lookup_table)) = decode(
+            "address-lookup-table",
+            "lookupTable",
+            serde_json::json!({
+                "addresses": ["address0", "address1"],
+                "authority": "authority",
+                "deactivationSlot": "18446744073709551615",
+                "lastExtendedSlot": "212345678",
+                "lastExtendedSlotStartIndex": 1
+            }),
+        ) else {
+            panic!("expected a lookup table");
+        };
+
+        assert_eq!(lookup_table.authority.as_deref(), Some("authority"));
+        assert_eq!(lookup_table.addresses, vec!["address0", "address1"]);
+        assert_eq!(lookup_table.deactivation_slot, None);
+        assert_eq!(lookup_table.last_extended_slot, Some(212345678));
+        assert_eq!(lookup_table.last_extended_slot_start_index, 1);
+    }
+
+    #[test]
+    fn test_decode_frozen_deactivated_lookup_table() {
+        let Some(NativeAccountState::LookupTable(lookup_table)) = decode(
+            "address-lookup-table",
+            "lookupTable",
+            serde_json::json!({
+                "addresses": [],
+                "deactivationSlot": "212000000",
+                "lastExtendedSlot": "0",
+                "lastExtendedSlotStartIndex": 0
+            }),
+        ) else {
+            panic!("expected a lookup table");
+        };
+
+        assert_eq!(lookup_table.authority, None);
+        assert_eq!(lookup_table.deactivation_slot, Some(212000000));
+    }
+
+    #[test]
+    fn test_decode_program_data() {
+        let Some(NativeAccountState::ProgramData(program_data)) = decode(
+            "bpf-upgradeable-loader",
+            "programData",
+            serde_json::json!({
+                "authority": "upgrade_authority",
+                "data": ["", "base64"],
+                "slot": 123456
+            }),
+        ) else {
+            panic!("expected program data");
+        };
+
+        assert_eq!(program_data.slot, 123456);
+        assert_eq!(
+            program_data.upgrade_authority.as_deref(),
+            Some("upgrade_authority")
+        );
+    }
+
+    #[test]
+    fn test_decode_program_is_not_program_data() {
+        assert!(decode(
+            "bpf-upgradeable-loader",
+            "program",
+            serde_json::json!({"programData": "program_data"}),
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_decode_clock_sysvar() {
+        let Some(NativeAccountState::Sysvar(sysvar)) = decode(
+            "sysvar",
+            "clock",
+            serde_json::json!({
+                "epoch": 500,
+                "epochStartTimestamp": 1700000000,
+                "leaderScheduleEpoch": 501,
+                "slot": 216000000,
+                "unixTimestamp": 1700100000
+            }),
+        ) else {
+            panic!("expected a sysvar");
+        };
+
+        assert_eq!(sysvar.clock_slot, Some(216000000));
+        assert_eq!(sysvar.clock_epoch, Some(500));
+        assert_eq!(sysvar.clock_epoch_start_timestamp, Some(1700000000));
+        assert_eq!(sysvar.clock_leader_schedule_epoch, Some(501));
+        assert_eq!(sysvar.clock_unix_timestamp, Some(1700100000));
+        assert_eq!(sysvar.slots_per_epoch, None);
+    }
+
+    #[test]
+    fn test_decode_rent_sysvar() {
+        let Some(NativeAccountState::Sysvar(sysvar)) = decode(
+            "sysvar",
+            "rent",
+            serde_json::json!({
+                "burnPercent": 50,
+                "exemptionThreshold": 2.0,
+                "lamportsPerByteYear": "3480"
+            }),
+        ) else {
+            panic!("expected a sysvar");
+        };
+
+        assert_eq!(sysvar.lamports_per_byte_year, Some(3480));
+        assert_eq!(sysvar.exemption_threshold, Some(2.0));
+        assert_eq!(sysvar.burn_percent, Some(50));
+        assert_eq!(sysvar.clock_slot, None);
+    }
+
+    #[test]
+    fn test_decode_unsupported_sysvar() {
+        assert!(decode("sysvar", "stakeHistory", serde_json::json!({})).is_none());
+    }
+}
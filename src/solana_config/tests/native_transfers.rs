@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        proto_codegen::confirmed_block::{
+            InnerInstruction, InnerInstructions, TransactionError, TransactionStatusMeta,
+        },
+        tests::fixtures::{account, common_table_fields, system_instruction},
+        transformation::{
+            invocation::build_invocation_tree,
+            native_transfer::transform_to_native_transfer_records,
+        },
+    };
+
+    fn program_instruction() -> InnerInstruction {
+        InnerInstruction {
+            program_id: Some(String::from("Program")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_native_transfers() {
+        let account_keys = vec![
+            account("payer"),
+            account("recipient"),
+            account("new_account"),
+            account("vault"),
+        ];
+        let instructions = vec![
+            system_instruction(
+                "transfer",
+                r#"{"source":"payer","destination":"recipient","lamports":1000}"#,
+            ),
+            program_instruction(),
+        ];
+        let meta = TransactionStatusMeta {
+            fee: 5000,
+            // the program also moves 300 lamports from its vault to the payer
+            pre_balances: vec![100_000, 0, 0, 1000],
+            post_balances: vec![100_000 - 5000 - 1000 - 2000 + 300, 1000, 2000, 700],
+            inner_instructions: vec![InnerInstructions {
+                index: 1,
+                instructions: vec![system_instruction(
+                    "createAccount",
+                    r#"{"source":"payer","newAccount":"new_account","lamports":2000,"space":0,"owner":"Program"}"#,
+                )],
+            }],
+            ..Default::default()
+        };
+        let invocation_tree =
+            build_invocation_tree(&instructions, &meta.inner_instructions, &meta.log_messages);
+
+        let records = transform_to_native_transfer_records(
+            common_table_fields(),
+            &account_keys,
+            &instructions,
+            &meta,
+            &invocation_tree,
+        );
+        let movements: Vec<(Option<&str>, Option<&str>, u64, &str, Option<&str>)> = records
+            .iter()
+            .map(|r| {
+                (
+                    r.source.as_deref(),
+                    r.destination.as_deref(),
+                    r.lamports.unwrap(),
+                    r.kind.as_deref().unwrap(),
+                    r.instruction_path.as_deref(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            movements,
+            vec![
+                (Some("payer"), None, 5000, "fee", None),
+                (
+                    Some("payer"),
+                    Some("recipient"),
+                    1000,
+                    "transfer",
+                    Some("0")
+                ),
+                (
+                    Some("payer"),
+                    Some("new_account"),
+                    2000,
+                    "createAccount",
+                    Some("1.0")
+                ),
+                (None, Some("payer"), 300, "unattributed", None),
+                (Some("vault"), None, 300, "unattributed", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_failed_transaction_only_pays_the_fee() {
+        let account_keys = vec![account("payer"), account("recipient")];
+        let instructions = vec![system_instruction(
+            "transfer",
+            r#"{"source":"payer","destination":"recipient","lamports":1000}"#,
+        )];
+        let meta = TransactionStatusMeta {
+            err: Some(TransactionError::default()),
+            fee: 5000,
+            pre_balances: vec![100_000, 0],
+            post_balances: vec![95_000, 0],
+            ..Default::default()
+        };
+
+        let records = transform_to_native_transfer_records(
+            common_table_fields(),
+            &account_keys,
+            &instructions,
+            &meta,
+            &Default::default(),
+        );
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind.as_deref(), Some("fee"));
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::{sync::Semaphore, time::sleep};
+
+    use crate::solana_config::{
+        lib::publish_unordered,
+        pipeline::{send_within_budget, FetchedBlock, PipelineConfig},
+        proto_codegen::account_info::AccountInfo,
+        types::block_response_types::BlockResponse,
+    };
+
+    fn config(max_in_flight_bytes: usize) -> PipelineConfig {
+        PipelineConfig {
+            fetch_workers: 1,
+            transform_workers: 1,
+            publish_workers: 1,
+            max_in_flight_bytes,
+        }
+    }
+
+    #[test]
+    fn test_permits_for_block_sizes() {
+        let config = config(1000);
+        assert_eq!(config.permits_for(250), 250);
+        // an empty block still takes a permit, and a huge one takes the whole budget
+        assert_eq!(config.permits_for(0), 1);
+        assert_eq!(config.permits_for(5000), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_slow_publisher_throttles_fetching() {
+        let config = config(1000);
+        let byte_budget = Arc::new(Semaphore::new(config.max_in_flight_bytes));
+        let (fetched_sender, fetched_receiver) = async_channel::bounded(10);
+        let published = Arc::new(AtomicUsize::new(0));
+
+        let publish_stage = tokio::spawn({
+            let published = published.clone();
+            async move {
+                publish_unordered(&fetched_receiver, |_, _: ()| {
+                    let published = published.clone();
+                    async move {
+                        sleep(Duration::from_millis(10)).await;
+                        published.fetch_add(1, Ordering::SeqCst);
+                    }
+                })
+                .await
+            }
+        });
+
+        for slot in 0..4 {
+            // each block takes more than half of the budget, so the fetch stage waits for the
+            // block ahead to be published, although the queue has room for more
+            send_within_budget(&fetched_sender, &byte_budget, &config, slot, (), 600).await;
+            assert!(published.load(Ordering::SeqCst) >= slot as usize);
+        }
+        drop(fetched_sender);
+        publish_stage.await.unwrap();
+
+        assert_eq!(published.load(Ordering::SeqCst), 4);
+        assert_eq!(byte_budget.available_permits(), 1000);
+    }
+
+    /// a getBlock response with a transaction that wasn't requested with the jsonParsed encoding.
+    const BINARY_BLOCK_RESPONSE: &str = r#"{
+        "jsonrpc": "2.0",
+        "result": {
+            "blockhash": "blockhash",
+            "previousBlockhash": "previous_blockhash",
+            "parentSlot": 99,
+            "blockTime": 1700000000,
+            "blockHeight": 90,
+            "transactions": [
+                {
+                    "transaction": ["AQID", "base64"],
+                    "meta": null
+                }
+            ]
+        },
+        "id": 1
+    }"#;
+
+    #[test]
+    fn test_unconverted_block_is_kept_for_replay() {
+        let response: BlockResponse = serde_json::from_str(BINARY_BLOCK_RESPONSE).unwrap();
+        let fetched_block = FetchedBlock {
+            block: response.result.unwrap(),
+            accounts: vec![AccountInfo::new(
+                String::from("signature"),
+                Vec::new(),
+                Vec::new(),
+            )],
+        };
+        assert!(fetched_block.to_etl_block(100).is_err());
+
+        // the dead letter keeps the block and its accounts, so the conversion can be replayed
+        let replayed =
+            FetchedBlock::from_dead_letter_input(&fetched_block.to_dead_letter_input()).unwrap();
+        assert_eq!(replayed.block.block.blockhash, "blockhash");
+        assert_eq!(replayed.accounts, fetched_block.accounts);
+        let error = replayed.to_etl_block(100).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            fetched_block.to_etl_block(100).unwrap_err().to_string()
+        );
+    }
+}
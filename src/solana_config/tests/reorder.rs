@@ -0,0 +1,141 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::Semaphore;
+
+    use crate::{
+        shutdown::Shutdown,
+        solana_config::{
+            lib::reorder_blocks,
+            pipeline::InFlightBlock,
+            reorder::{ReorderBuffer, ReorderConfig, StuckSlotPolicy},
+        },
+    };
+
+    fn drain(buffer: &mut ReorderBuffer<&'static str>) -> Vec<(u64, Option<&'static str>)> {
+        std::iter::from_fn(|| buffer.pop_ready()).collect()
+    }
+
+    #[test]
+    fn test_reorder_buffer_releases_in_queue_order() {
+        let mut buffer = ReorderBuffer::default();
+        for slot in [10, 11, 12, 13] {
+            buffer.expect(slot);
+        }
+
+        assert_eq!(buffer.finish(12, Some("block 12")), None);
+        assert_eq!(buffer.finish(11, None), None);
+        assert_eq!(drain(&mut buffer), vec![]);
+
+        assert_eq!(buffer.finish(10, Some("block 10")), None);
+        assert_eq!(
+            drain(&mut buffer),
+            vec![(10, Some("block 10")), (11, None), (12, Some("block 12"))]
+        );
+
+        buffer.finish(13, Some("block 13"));
+        assert_eq!(drain(&mut buffer), vec![(13, Some("block 13"))]);
+    }
+
+    #[test]
+    fn test_reorder_buffer_releases_in_reverse_order() {
+        let mut buffer = ReorderBuffer::default();
+        for slot in [5, 4, 3] {
+            buffer.expect(slot);
+        }
+        buffer.finish(3, Some("block 3"));
+        buffer.finish(4, Some("block 4"));
+        buffer.finish(5, Some("block 5"));
+
+        assert_eq!(
+            drain(&mut buffer),
+            vec![
+                (5, Some("block 5")),
+                (4, Some("block 4")),
+                (3, Some("block 3"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reorder_buffer_accepts_blocks_before_their_slot_is_queued() {
+        let mut buffer = ReorderBuffer::default();
+        buffer.finish(7, Some("block 7"));
+        assert_eq!(drain(&mut buffer), vec![]);
+
+        buffer.expect(7);
+        assert_eq!(drain(&mut buffer), vec![(7, Some("block 7"))]);
+    }
+
+    #[test]
+    fn test_reorder_buffer_skips_stuck_slots() {
+        let mut buffer = ReorderBuffer::default();
+        for slot in [1, 2] {
+            buffer.expect(slot);
+        }
+        buffer.finish(2, Some("block 2"));
+
+        assert!(!buffer.is_stuck(Duration::from_secs(60)));
+        assert!(buffer.is_stuck(Duration::ZERO));
+        assert_eq!(buffer.skip_head(), Some(1));
+        assert_eq!(drain(&mut buffer), vec![(2, Some("block 2"))]);
+
+        // the stuck slot is handed back as soon as it finishes
+        assert_eq!(buffer.finish(1, Some("block 1")), Some("block 1"));
+        assert_eq!(drain(&mut buffer), vec![]);
+        assert!(!buffer.is_stuck(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_reorder_buffer_without_queued_slots_never_gets_stuck() {
+        let mut buffer = ReorderBuffer::default();
+        assert_eq!(buffer.time_until_stuck(Duration::ZERO), None);
+
+        buffer.expect(1);
+        assert_eq!(
+            buffer.time_until_stuck(Duration::ZERO),
+            Some(Duration::ZERO)
+        );
+        assert!(buffer.time_until_stuck(Duration::from_secs(60)).is_some());
+
+        buffer.finish(1, Some("block 1"));
+        assert_eq!(drain(&mut buffer), vec![(1, Some("block 1"))]);
+        assert_eq!(buffer.time_until_stuck(Duration::ZERO), None);
+        assert!(!buffer.is_stuck(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_stuck_slot_aborts_the_indexer() {
+        let config = ReorderConfig {
+            capacity: 2,
+            timeout: Duration::ZERO,
+            on_timeout: StuckSlotPolicy::Abort,
+        };
+        let (order_sender, order_receiver) = async_channel::unbounded();
+        let (_transformed_sender, transformed_receiver) =
+            async_channel::unbounded::<InFlightBlock<&'static str>>();
+        let window = Semaphore::new(0);
+        let shutdown = Shutdown::new(Duration::ZERO);
+        order_sender.send(1).await.unwrap();
+
+        let mut published = Vec::new();
+        let reordered = reorder_blocks(
+            &config,
+            &order_receiver,
+            &transformed_receiver,
+            &window,
+            &shutdown,
+            |slot, _| {
+                published.push(slot);
+                async {}
+            },
+        )
+        .await;
+
+        // the slot that never finished is reported, and the queueing is asked to stop
+        assert_eq!(reordered.unwrap_err().slot, 1);
+        assert!(shutdown.is_requested());
+        assert!(published.is_empty());
+    }
+}
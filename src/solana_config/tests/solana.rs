@@ -32,7 +32,7 @@ mod tests {
             .unwrap();
 
         let request_builder = http_request_client.post(endpoint).headers(headers);
-        let request_config = RequestConfig::ReqBldr(request_builder);
+        let request_config = RequestConfig::ReqBldr(request_builder, None);
 
         let slot_val = call_getBlockHeight(request_config.try_clone().unwrap(), None).await;
         println!("Slot is {}", slot_val);
@@ -61,7 +61,7 @@ mod tests {
             .unwrap();
 
         let request_builder = http_request_client.post(endpoint).headers(headers);
-        let request_config = RequestConfig::ReqBldr(request_builder);
+        let request_config = RequestConfig::ReqBldr(request_builder, None);
 
         let slot_val = call_getSlot(request_config.try_clone().unwrap(), None).await;
         println!("Slot is {}", slot_val);
@@ -90,7 +90,7 @@ mod tests {
             .unwrap();
 
         let request_builder = http_request_client.post(endpoint).headers(headers);
-        let request_config = RequestConfig::ReqBldr(request_builder);
+        let request_config = RequestConfig::ReqBldr(request_builder, None);
         println!("making a request for the genesis block...");
 
         let block_response: block_response_types::BlockResponse =
@@ -120,7 +120,7 @@ mod tests {
             .unwrap();
 
         let request_builder = http_request_client.post(endpoint).headers(headers);
-        let request_config = RequestConfig::ReqBldr(request_builder);
+        let request_config = RequestConfig::ReqBldr(request_builder, None);
 
         let five_blocks: Vec<u64> = Vec::from([1, 100, 100_000, 100_000_000, 200_000_000]);
         println!("making 5 requests for known blocks...");
@@ -157,7 +157,7 @@ mod tests {
             .unwrap();
 
         let request_builder = http_request_client.post(endpoint).headers(headers);
-        let request_config = RequestConfig::ReqBldr(request_builder);
+        let request_config = RequestConfig::ReqBldr(request_builder, None);
 
         println!("making a request for the accounts...");
 
@@ -169,6 +169,7 @@ mod tests {
                 String::from("7WduLbRfYhTJktjLw5FDEyrqoEv61aTTCuGAetgLjzN5"),
             ],
             None,
+            None,
         )
         .await;
         dbg!(account_response.unwrap());
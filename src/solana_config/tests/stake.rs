@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        proto_codegen::confirmed_block::{
+            InnerInstruction, InnerInstructions, TransactionStatusMeta,
+        },
+        stake::decode_stake_account,
+        tests::fixtures::{common_table_fields, parsed_instruction},
+        transformation::{
+            invocation::build_invocation_tree, stake_action::transform_to_stake_action_records,
+        },
+        types::account_response_types::AccountInfoInfoStructureResponse,
+    };
+
+    #[test]
+    fn test_decode_delegated_stake_account() {
+        let info: AccountInfoInfoStructureResponse = serde_json::from_value(serde_json::json!({
+            "meta": {
+                "authorized": {"staker": "staker", "withdrawer": "withdrawer"},
+                "lockup": {"custodian": "custodian", "epoch": 0, "unixTimestamp": 0},
+                "rentExemptReserve": "2282880"
+            },
+            "stake": {
+                "creditsObserved": 169965713,
+                "delegation": {
+                    "activationEpoch": "386",
+                    "deactivationEpoch": "18446744073709551615",
+                    "stake": "8993138046",
+                    "voter": "voter",
+                    "warmupCooldownRate": 0.25
+                }
+            }
+        }))
+        .unwrap();
+
+        let stake = decode_stake_account(info.meta.as_ref().unwrap(), info.stake.as_ref());
+
+        assert_eq!(stake.rent_exempt_reserve, 2282880);
+        assert_eq!(stake.staker, "staker");
+        assert_eq!(stake.withdrawer, "withdrawer");
+        assert_eq!(stake.lockup_custodian, "custodian");
+        assert_eq!(stake.voter.as_deref(), Some("voter"));
+        assert_eq!(stake.delegated_stake, Some(8993138046));
+        assert_eq!(stake.activation_epoch, Some(386));
+        assert_eq!(stake.deactivation_epoch, None);
+        assert_eq!(stake.warmup_cooldown_rate, Some(0.25));
+        assert_eq!(stake.credits_observed, Some(169965713));
+    }
+
+    #[test]
+    fn test_decode_initialized_stake_account() {
+        let info: AccountInfoInfoStructureResponse = serde_json::from_value(serde_json::json!({
+            "meta": {
+                "authorized": {"staker": "staker", "withdrawer": "withdrawer"},
+                "lockup": {"custodian": "custodian", "epoch": 10, "unixTimestamp": 1700000000},
+                "rentExemptReserve": "2282880"
+            },
+            "stake": null
+        }))
+        .unwrap();
+
+        let stake = decode_stake_account(info.meta.as_ref().unwrap(), info.stake.as_ref());
+
+        assert_eq!(stake.lockup_epoch, 10);
+        assert_eq!(stake.lockup_unix_timestamp, 1700000000);
+        assert_eq!(stake.voter, None);
+        assert_eq!(stake.delegated_stake, None);
+    }
+
+    fn stake_instruction(r#type: &str, info: serde_json::Value) -> InnerInstruction {
+        parsed_instruction(
+            "stake",
+            "Stake11111111111111111111111111111111111111",
+            r#type,
+            &info.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_stake_actions() {
+        let instructions = vec![
+            stake_instruction(
+                "split",
+                serde_json::json!({
+                    "stakeAccount": "stake",
+                    "newSplitAccount": "split",
+                    "stakeAuthority": "staker",
+                    "lamports": 1000
+                }),
+            ),
+            InnerInstruction {
+                program_id: Some(String::from("StakePool")),
+                ..Default::default()
+            },
+        ];
+        let meta = TransactionStatusMeta {
+            inner_instructions: vec![InnerInstructions {
+                index: 1,
+                instructions: vec![
+                    stake_instruction(
+                        "delegate",
+                        serde_json::json!({
+                            "stakeAccount": "split",
+                            "voteAccount": "voter",
+                            "stakeAuthority": "staker"
+                        }),
+                    ),
+                    // not a stake action
+                    stake_instruction("authorize", serde_json::json!({"stakeAccount": "split"})),
+                ],
+            }],
+            ..Default::default()
+        };
+        let invocation_tree =
+            build_invocation_tree(&instructions, &meta.inner_instructions, &meta.log_messages);
+
+        let records = transform_to_stake_action_records(
+            common_table_fields(),
+            &instructions,
+            &meta,
+            &invocation_tree,
+        );
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].action.as_deref(), Some("split"));
+        assert_eq!(records[0].stake_account.as_deref(), Some("stake"));
+        assert_eq!(records[0].destination.as_deref(), Some("split"));
+        assert_eq!(records[0].lamports, Some(1000));
+        assert_eq!(records[1].action.as_deref(), Some("delegate"));
+        assert_eq!(records[1].vote_account.as_deref(), Some("voter"));
+        assert_eq!(records[1].authority.as_deref(), Some("staker"));
+        assert_eq!(records[1].instruction_path.as_deref(), Some("1.0"));
+    }
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        proto_codegen::{account_info::Token, confirmed_block::UnixTimestamp},
+        token_cache::TokenCache,
+    };
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn token_retrieved_at(mint: &str, timestamp: i64) -> Token {
+        Token {
+            mint: String::from(mint),
+            retrieval_timestamp: Some(UnixTimestamp { timestamp }),
+            ..Default::default()
+        }
+    }
+
+    fn now() -> i64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64
+    }
+
+    #[test]
+    fn test_token_cache_evicts_least_recently_used() {
+        let cache = TokenCache::new(2, 60, None);
+        cache.insert(String::from("a"), token_retrieved_at("a", now()));
+        cache.insert(String::from("b"), token_retrieved_at("b", now()));
+
+        // using "a" makes "b" the least recently used
+        assert!(cache.get("a").is_some());
+        cache.insert(String::from("c"), token_retrieved_at("c", now()));
+
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_token_cache_expires_old_tokens() {
+        let cache = TokenCache::new(2, 60, None);
+        cache.insert(String::from("a"), token_retrieved_at("a", now() - 120));
+
+        assert!(cache.get("a").is_none());
+    }
+
+    #[test]
+    fn test_token_cache_persists_to_disk() {
+        let path = std::env::temp_dir().join("test_token_cache_persists_to_disk.bin");
+        let cache = TokenCache::new(2, 60, Some(path.clone()));
+        cache.insert(String::from("a"), token_retrieved_at("a", now()));
+        cache.save();
+
+        let reloaded = TokenCache::open(2, 60, Some(path.clone()));
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            reloaded.get("a").map(|token| token.mint),
+            Some(String::from("a"))
+        );
+    }
+}
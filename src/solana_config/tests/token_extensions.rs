@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        token_extensions::{decode_extensions, token_from_metadata_extension},
+        types::account_response_types::AccountInfoExtensionResponse,
+    };
+
+    fn mint_extensions() -> Vec<AccountInfoExtensionResponse> {
+        serde_json::from_str(
+            r#"[
+                {
+                    "extension": "transferFeeConfig",
+                    "state": {
+                        "transferFeeConfigAuthority": "FeeAuthority1111111111111111111111111111111",
+                        "withdrawWithheldAuthority": null,
+                        "withheldAmount": 25,
+                        "olderTransferFee": {"epoch": 500, "maximumFee": 1000, "transferFeeBasisPoints": 50},
+                        "newerTransferFee": {"epoch": 600, "maximumFee": 2000, "transferFeeBasisPoints": 100}
+                    }
+                },
+                {
+                    "extension": "metadataPointer",
+                    "state": {"authority": null, "metadataAddress": "Mint111111111111111111111111111111111111111"}
+                },
+                {
+                    "extension": "tokenMetadata",
+                    "state": {
+                        "updateAuthority": "Update1111111111111111111111111111111111111",
+                        "mint": "Mint111111111111111111111111111111111111111",
+                        "name": "Example",
+                        "symbol": "EX",
+                        "uri": "https://example.com/token.json",
+                        "additionalMetadata": [["color", "blue"]]
+                    }
+                },
+                {"extension": "nonTransferable"},
+                {"extension": "mintCloseAuthority", "state": {"closeAuthority": null}}
+            ]"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_decode_extensions() {
+        let decoded = decode_extensions(&mint_extensions());
+
+        assert_eq!(
+            decoded.extension_types,
+            vec![
+                "transferFeeConfig",
+                "metadataPointer",
+                "tokenMetadata",
+                "nonTransferable",
+                "mintCloseAuthority"
+            ]
+        );
+        let transfer_fee_config = decoded.transfer_fee_config.unwrap();
+        assert_eq!(transfer_fee_config.withheld_amount, 25);
+        assert_eq!(
+            transfer_fee_config
+                .newer_transfer_fee
+                .unwrap()
+                .transfer_fee_basis_points,
+            100
+        );
+        assert_eq!(
+            decoded
+                .metadata_pointer
+                .unwrap()
+                .metadata_address
+                .as_deref(),
+            Some("Mint111111111111111111111111111111111111111")
+        );
+        assert_eq!(decoded.token_metadata.unwrap().additional_metadata.len(), 1);
+        assert!(decoded.non_transferable);
+        assert!(decoded.interest_bearing_config.is_none());
+    }
+
+    #[test]
+    fn test_token_from_metadata_extension() {
+        let token = token_from_metadata_extension(&mint_extensions(), false, 1_700_000_000, 42)
+            .expect("mint has a tokenMetadata extension");
+
+        assert_eq!(token.name, "Example");
+        assert_eq!(token.symbol, "EX");
+        assert_eq!(token.metadata_source.as_deref(), Some("token-2022"));
+        assert_eq!(token.context_slot, Some(42));
+        assert!(token.is_mutable);
+    }
+}
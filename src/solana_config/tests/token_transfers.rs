@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        proto_codegen::confirmed_block::{TokenBalance, TokenTransferInstruction, UiTokenAmount},
+        tests::fixtures::account,
+        transformation::{
+            token_transfer::{
+                enrich_token_transfer_record, token_accounts_from_balances,
+                transform_to_token_transfer_record,
+            },
+            transaction::CommonTableFields,
+        },
+    };
+
+    fn balance(account_index: u32, owner: &str) -> TokenBalance {
+        TokenBalance {
+            account_index,
+            mint: String::from("mint"),
+            ui_token_amount: Some(UiTokenAmount {
+                decimals: 6,
+                ..Default::default()
+            }),
+            owner: owner.to_string(),
+            program_id: String::from("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"),
+        }
+    }
+
+    #[test]
+    fn test_transfer_is_enriched_from_token_balances() {
+        let mut token_transfer_record = transform_to_token_transfer_record(
+            CommonTableFields {
+                block_slot: Some(1),
+                block_hash: None,
+                block_timestamp: None,
+                tx_signature: None,
+            },
+            "transfer",
+            String::from("spl-token"),
+            Some(TokenTransferInstruction {
+                amount: Some(100),
+                source: Some(String::from("source")),
+                destination: Some(String::from("destination")),
+                authority: Some(String::from("alice")),
+                ..Default::default()
+            }),
+            None,
+        )
+        .unwrap();
+        assert_eq!(token_transfer_record.mint, None);
+
+        // the destination account is created by the transaction, so it only has a post balance
+        let token_accounts = token_accounts_from_balances(
+            &[account("alice"), account("source"), account("destination")],
+            &[balance(1, "alice")],
+            &[balance(1, "alice"), balance(2, "bob")],
+        );
+        enrich_token_transfer_record(&mut token_transfer_record, &token_accounts);
+
+        assert_eq!(token_transfer_record.mint.as_deref(), Some("mint"));
+        assert_eq!(token_transfer_record.decimals, Some(6));
+        assert_eq!(token_transfer_record.source_owner.as_deref(), Some("alice"));
+        assert_eq!(
+            token_transfer_record.destination_owner.as_deref(),
+            Some("bob")
+        );
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        proto_codegen::confirmed_block::TransactionError,
+        proto_conversions::block::parsed_block_to_proto,
+        types::block_response_types::{BlockResponse, TransactionErrorResponse},
+    };
+    use solana_sdk::{instruction::InstructionError, transaction};
+
+    #[test]
+    fn test_custom_instruction_error() {
+        let err = TransactionError::from(transaction::TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(6001),
+        ));
+
+        assert_eq!(err.error_type, "InstructionError");
+        assert_eq!(err.failed_instruction_index, Some(2));
+        assert_eq!(err.instruction_error_type.as_deref(), Some("Custom"));
+        assert_eq!(err.custom_error_code, Some(6001));
+    }
+
+    #[test]
+    fn test_transaction_error_without_instruction() {
+        let err = TransactionError::from(transaction::TransactionError::InsufficientFundsForRent {
+            account_index: 1,
+        });
+
+        assert_eq!(err.error_type, "InsufficientFundsForRent");
+        assert_eq!(err.failed_instruction_index, None);
+        assert_eq!(err.instruction_error_type, None);
+        assert_eq!(err.custom_error_code, None);
+    }
+
+    #[test]
+    fn test_unknown_errors_are_bucketed() {
+        let unknown: TransactionErrorResponse =
+            serde_json::from_str(r#"{"SomeFutureError":{"account_index":3}}"#).unwrap();
+        assert_eq!(unknown.error_type(), "Unknown");
+        assert_eq!(unknown.failed_instruction_index(), None);
+
+        let unknown_instruction_error: TransactionErrorResponse =
+            serde_json::from_str(r#"{"InstructionError":[4,"SomeFutureInstructionError"]}"#)
+                .unwrap();
+        assert_eq!(unknown_instruction_error.error_type(), "InstructionError");
+        assert_eq!(
+            unknown_instruction_error.failed_instruction_index(),
+            Some(4)
+        );
+        assert_eq!(
+            unknown_instruction_error
+                .instruction_error_type()
+                .as_deref(),
+            Some("Unknown")
+        );
+        assert_eq!(unknown_instruction_error.custom_error_code(), None);
+    }
+
+    #[test]
+    fn test_sdk_errors_unknown_to_the_indexer_keep_their_name() {
+        let err: TransactionErrorResponse =
+            serde_json::from_str(r#""ResanitizationNeeded""#).unwrap();
+        assert!(matches!(err, TransactionErrorResponse::Unknown(_)));
+        assert_eq!(err.error_type(), "ResanitizationNeeded");
+    }
+
+    /// a failed transaction in the jsonParsed encoding of a getBlock response.
+    fn failed_transaction(err: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "transaction": {
+                "signatures": ["signature"],
+                "message": {
+                    "accountKeys": [
+                        {"pubkey": "payer", "writable": true, "signer": true, "source": "transaction"}
+                    ],
+                    "recentBlockhash": "blockhash",
+                    "instructions": []
+                }
+            },
+            "meta": {
+                "err": err,
+                "status": {"Err": err},
+                "fee": 5000,
+                "preBalances": [1000000],
+                "postBalances": [995000],
+                "innerInstructions": [],
+                "logMessages": [],
+                "preTokenBalances": [],
+                "postTokenBalances": [],
+                "rewards": []
+            },
+            "version": "legacy"
+        })
+    }
+
+    #[test]
+    fn test_get_block_response_with_unknown_errors() {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": {
+                "blockhash": "blockhash",
+                "previousBlockhash": "previous_blockhash",
+                "parentSlot": 99,
+                "blockTime": 1700000000,
+                "blockHeight": 90,
+                "rewards": [],
+                "transactions": [
+                    failed_transaction(serde_json::json!({"SomeFutureError": {"account_index": 3}})),
+                    failed_transaction(serde_json::json!({"InstructionError": [1, {"Custom": 6001}]})),
+                    failed_transaction(serde_json::json!({"InstructionError": [0, "SomeFutureInstructionError"]}))
+                ]
+            },
+            "id": 1
+        })
+        .to_string();
+
+        let response: BlockResponse = serde_json::from_str(&body).unwrap();
+        let (_, _, transactions) = parsed_block_to_proto(response.result.unwrap()).unwrap();
+        let errors: Vec<TransactionError> = transactions
+            .into_iter()
+            .map(|transaction| transaction.meta.unwrap().err.unwrap())
+            .collect();
+
+        assert_eq!(errors[0].error_type, "Unknown");
+        assert_eq!(errors[0].err, r#"{"SomeFutureError":{"account_index":3}}"#);
+        assert_eq!(errors[1].error_type, "InstructionError");
+        assert_eq!(errors[1].failed_instruction_index, Some(1));
+        assert_eq!(errors[1].custom_error_code, Some(6001));
+        assert_eq!(
+            errors[1].err,
+            "Error processing Instruction 1: custom program error: 0x1771"
+        );
+        assert_eq!(errors[2].failed_instruction_index, Some(0));
+        assert_eq!(errors[2].instruction_error_type.as_deref(), Some("Unknown"));
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        instruction_decoders::InstructionDecoderRegistry,
+        proto_codegen::{
+            confirmed_block::{
+                CompiledAccount, ConfirmedTransaction, Message, Transaction, TransactionStatusMeta,
+            },
+            etl_block::{EtlBlock, TableContext},
+        },
+        transformation::transaction::transform_to_transaction_records,
+    };
+
+    fn static_account(pubkey: &str, signer: bool, writable: bool) -> CompiledAccount {
+        CompiledAccount {
+            pubkey: pubkey.to_string(),
+            signer,
+            source: String::from("Transaction"),
+            writable,
+        }
+    }
+
+    #[test]
+    fn test_loaded_addresses_are_merged() {
+        let etl_block = EtlBlock {
+            slot: 1,
+            transactions: vec![ConfirmedTransaction {
+                transaction: Some(Transaction {
+                    signatures: vec![String::from("signature")],
+                    message: Some(Message {
+                        account_keys: vec![
+                            static_account("payer", true, true),
+                            static_account("program", false, false),
+                        ],
+                        ..Default::default()
+                    }),
+                }),
+                meta: Some(TransactionStatusMeta {
+                    pre_balances: vec![10, 1, 20, 30],
+                    post_balances: vec![5, 1, 25, 30],
+                    loaded_writable_addresses: vec![String::from("loaded_writable")],
+                    loaded_readonly_addresses: vec![String::from("loaded_readonly")],
+                    ..Default::default()
+                }),
+                version: Some(String::from("0")),
+            }],
+            table_context: Some(TableContext::default()),
+            ..Default::default()
+        };
+
+        let (transaction_records, ..) =
+            transform_to_transaction_records(&etl_block, &InstructionDecoderRegistry::new())
+                .unwrap();
+        let record = &transaction_records[0];
+
+        assert_eq!(record.version.as_deref(), Some("0"));
+        let accounts: Vec<(&str, bool, &str)> = record
+            .accounts
+            .iter()
+            .map(|a| {
+                (
+                    a.pubkey.as_deref().unwrap(),
+                    a.writable.unwrap(),
+                    a.source.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            accounts,
+            vec![
+                ("payer", true, "Transaction"),
+                ("program", false, "Transaction"),
+                ("loaded_writable", true, "LookupTable"),
+                ("loaded_readonly", false, "LookupTable"),
+            ]
+        );
+
+        // the balances line up with the loaded addresses
+        let balance_change = &record.balance_changes[2];
+        assert_eq!(balance_change.account.as_deref(), Some("loaded_writable"));
+        assert_eq!(
+            (balance_change.before, balance_change.after),
+            (Some(20), Some(25))
+        );
+    }
+}
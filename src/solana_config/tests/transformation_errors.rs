@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use crate::solana_config::{
+        instruction_decoders::InstructionDecoderRegistry,
+        proto_codegen::{
+            confirmed_block::{
+                ConfirmedTransaction, InnerInstruction, Message, Parsed, Reward, Transaction,
+            },
+            etl_block::{EtlBlock, TableContext},
+        },
+        transformation::{
+            block::{transform_to_block_record, transform_to_block_reward_records},
+            error::{TransformError, TransformErrorKind},
+            transaction::transform_to_transaction_records,
+        },
+    };
+
+    #[test]
+    fn test_missing_block_is_an_error() {
+        let etl_block = EtlBlock {
+            slot: 7,
+            ..Default::default()
+        };
+
+        let error = transform_to_block_record(&etl_block).unwrap_err();
+        assert_eq!(
+            error.kind,
+            TransformErrorKind::MissingField("etl_block.block")
+        );
+        assert_eq!(error.slot, Some(7));
+    }
+
+    #[test]
+    fn test_unexpected_reward_type_is_an_error() {
+        let etl_block = EtlBlock {
+            slot: 7,
+            block_rewards: vec![Reward {
+                pubkey: String::from("validator"),
+                reward_type: 9,
+                ..Default::default()
+            }],
+            table_context: Some(TableContext::default()),
+            ..Default::default()
+        };
+
+        let error = transform_to_block_reward_records(&etl_block).unwrap_err();
+        assert_eq!(error.kind, TransformErrorKind::UnexpectedRewardType(9));
+        assert_eq!(error.account.as_deref(), Some("validator"));
+    }
+
+    #[test]
+    fn test_invalid_instruction_params_are_an_error_in_context() {
+        let parsed_instruction = |info: &str| InnerInstruction {
+            parsed_dict: Some(Parsed {
+                r#type: Some(String::from("transfer")),
+                info: Some(info.to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let transaction = |instructions| ConfirmedTransaction {
+            transaction: Some(Transaction {
+                signatures: vec![String::from("signature")],
+                message: Some(Message {
+                    instructions,
+                    ..Default::default()
+                }),
+            }),
+            ..Default::default()
+        };
+        let etl_block = EtlBlock {
+            slot: 7,
+            transactions: vec![
+                transaction(vec![parsed_instruction("{}")]),
+                transaction(vec![
+                    parsed_instruction("{\"lamports\": 1}"),
+                    parsed_instruction("[1, 2]"),
+                ]),
+            ],
+            table_context: Some(TableContext::default()),
+            ..Default::default()
+        };
+
+        let error =
+            transform_to_transaction_records(&etl_block, &InstructionDecoderRegistry::new())
+                .unwrap_err();
+        assert_eq!(error.kind, TransformErrorKind::InstructionParamsNotAnObject);
+        assert_eq!(
+            (error.slot, error.transaction_index, error.instruction_index),
+            (Some(7), Some(1), Some(1))
+        );
+    }
+
+    #[test]
+    fn test_error_display_leads_with_the_context() {
+        let error = TransformError::from(TransformErrorKind::UnexpectedRewardType(9));
+        assert_eq!(error.to_string(), "unexpected reward type 9");
+
+        let error = TransformError::from(TransformErrorKind::InstructionParamsNotAnObject)
+            .at_inner_instruction(2, 1)
+            .at_transaction(3)
+            .at_slot(5)
+            // the innermost context is kept
+            .at_instruction(4)
+            .at_slot(6);
+        assert_eq!(
+            error.to_string(),
+            "slot 5, transaction 3, inner instruction 2.1: the instruction parameters are not a key-value pair"
+        );
+    }
+}
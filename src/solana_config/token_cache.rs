@@ -0,0 +1,217 @@
+//! # Token metadata cache
+//! A process-wide cache of decoded token metadata, keyed by the token's mint.  Popular mints
+//! show up in a large number of blocks, so rather than requesting their metadata account from
+//! the RPC node every time, the decoded [Token] is kept in memory and shared between workers.
+//!
+//! The cache is bounded both in size (least-recently-used entries are evicted first) and in
+//! age (entries older than the TTL, based on their retrieval timestamp, are refetched).  The
+//! cache can also be persisted to disk between runs.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+use prost::Message;
+
+use crate::config::Config;
+
+use super::proto_codegen::account_info::Token;
+
+/// The default maximum number of tokens kept in the cache.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// The default number of seconds a cached token is valid for.
+const DEFAULT_TTL_SECS: u64 = 86_400;
+
+/// A cached token along with the tick at which it was last used.
+struct CachedToken {
+    token: Token,
+    last_used: u64,
+}
+
+/// The mutable state of the cache, guarded by the [TokenCache]'s mutex.
+#[derive(Default)]
+struct TokenCacheInner {
+    /// The cached tokens, keyed by mint.
+    entries: HashMap<String, CachedToken>,
+    /// The mints ordered from least to most recently used, keyed by their last used tick.
+    recency: BTreeMap<u64, String>,
+    /// Monotonically increasing counter used to order the entries by use.
+    tick: u64,
+}
+
+impl TokenCacheInner {
+    /// marks the mint as the most recently used entry.
+    fn touch(&mut self, mint: &str) {
+        self.tick += 1;
+        if let Some(cached) = self.entries.get_mut(mint) {
+            self.recency.remove(&cached.last_used);
+            cached.last_used = self.tick;
+            self.recency.insert(self.tick, mint.to_string());
+        }
+    }
+
+    /// removes the mint from the cache, if present.
+    fn remove(&mut self, mint: &str) {
+        if let Some(cached) = self.entries.remove(mint) {
+            self.recency.remove(&cached.last_used);
+        }
+    }
+}
+
+/// A size and age bounded cache of token metadata, safe to share between workers.
+pub struct TokenCache {
+    inner: Mutex<TokenCacheInner>,
+    /// The maximum number of tokens kept in the cache.  A capacity of 0 disables the cache.
+    capacity: usize,
+    /// The number of seconds a cached token is valid for, from its retrieval timestamp.
+    ttl_secs: u64,
+    /// Where the cache is persisted between runs, if anywhere.
+    path: Option<PathBuf>,
+}
+
+impl TokenCache {
+    /// Creates an empty cache.
+    pub fn new(capacity: usize, ttl_secs: u64, path: Option<PathBuf>) -> TokenCache {
+        TokenCache {
+            inner: Mutex::new(TokenCacheInner::default()),
+            capacity,
+            ttl_secs,
+            path,
+        }
+    }
+
+    /// Creates the cache from the `TOKEN_CACHE_CAPACITY`, `TOKEN_CACHE_TTL` and
+    /// `TOKEN_CACHE_PATH` settings, loading any previously persisted tokens.
+    pub fn from_config(config: &Config) -> TokenCache {
+        // env:token_cache_capacity = The maximum number of tokens kept in the cache
+        let capacity = config.token_cache_capacity.unwrap_or(DEFAULT_CAPACITY);
+        // env:token_cache_ttl = The number of seconds a cached token is valid for
+        let ttl_secs = config.token_cache_ttl.unwrap_or(DEFAULT_TTL_SECS);
+        // env:token_cache_path = Where the cache is persisted between runs
+        let path = config.token_cache_path.as_ref().map(PathBuf::from);
+
+        TokenCache::open(capacity, ttl_secs, path)
+    }
+
+    /// Creates the cache, loading any tokens previously persisted to `path`.
+    pub fn open(capacity: usize, ttl_secs: u64, path: Option<PathBuf>) -> TokenCache {
+        let cache = TokenCache::new(capacity, ttl_secs, path);
+        cache.load();
+        cache
+    }
+
+    /// Returns the cached token for the mint, if it is present and has not expired.
+    pub fn get(&self, mint: &str) -> Option<Token> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        let expired = match inner.entries.get(mint) {
+            None => return None,
+            Some(cached) => self.is_expired(&cached.token),
+        };
+        if expired {
+            inner.remove(mint);
+            return None;
+        }
+        inner.touch(mint);
+        inner.entries.get(mint).map(|cached| cached.token.clone())
+    }
+
+    /// Caches the token under its mint, evicting the least recently used tokens if the cache is full.
+    pub fn insert(&self, mint: String, token: Token) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.remove(&mint);
+        inner.entries.insert(
+            mint.clone(),
+            CachedToken {
+                token,
+                last_used: 0,
+            },
+        );
+        inner.touch(&mint);
+
+        while inner.entries.len() > self.capacity {
+            match inner.recency.pop_first() {
+                Some((_, evicted)) => {
+                    inner.entries.remove(&evicted);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Loads previously persisted tokens from `path`, skipping any that have expired.
+    fn load(&self) {
+        let path = match &self.path {
+            Some(path) if path.exists() => path,
+            _ => return,
+        };
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("could not read the token cache at {:?}: {:?}", path, e);
+                return;
+            }
+        };
+
+        let mut buf = bytes.as_slice();
+        let mut loaded = 0;
+        while !buf.is_empty() {
+            match Token::decode_length_delimited(&mut buf) {
+                Ok(token) => {
+                    if !self.is_expired(&token) {
+                        self.insert(token.mint.clone(), token);
+                        loaded += 1;
+                    }
+                }
+                Err(e) => {
+                    warn!("token cache at {:?} is corrupted: {:?}", path, e);
+                    break;
+                }
+            }
+        }
+        info!("Loaded {} tokens from the token cache", loaded);
+    }
+
+    /// Persists the cached tokens to `path`, if set.
+    pub fn save(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+        let bytes: Vec<u8> = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .recency
+                .values()
+                .filter_map(|mint| inner.entries.get(mint))
+                .flat_map(|cached| cached.token.encode_length_delimited_to_vec())
+                .collect()
+        };
+        match fs::write(path, bytes) {
+            Ok(_) => info!("Saved the token cache to {:?}", path),
+            Err(e) => warn!("could not save the token cache to {:?}: {:?}", path, e),
+        }
+    }
+
+    /// Whether the token was retrieved more than `ttl_secs` ago.
+    fn is_expired(&self, token: &Token) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("time only moves forward")
+            .as_secs() as i64;
+        match &token.retrieval_timestamp {
+            Some(retrieved) => now - retrieved.timestamp > self.ttl_secs as i64,
+            None => true,
+        }
+    }
+}
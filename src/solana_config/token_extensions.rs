@@ -0,0 +1,181 @@
+//! this file contains helper functions for decoding the [Token-2022 extensions](https://spl.solana.com/token-2022/extensions)
+//! of mint and token accounts.
+use log::warn;
+use serde::de::DeserializeOwned;
+
+use super::{
+    proto_codegen::{account_info as solana_account_protobuf, confirmed_block::UnixTimestamp},
+    types::account_response_types::{
+        AccountInfoExtensionResponse, ExtensionInterestBearingConfigResponse,
+        ExtensionMetadataPointerResponse, ExtensionPermanentDelegateResponse,
+        ExtensionTokenMetadataResponse, ExtensionTransferFeeAmountResponse,
+        ExtensionTransferFeeConfigResponse, ExtensionTransferFeeResponse,
+        ExtensionTransferHookResponse,
+    },
+};
+
+const TRANSFER_FEE_CONFIG: &str = "transferFeeConfig";
+const TRANSFER_FEE_AMOUNT: &str = "transferFeeAmount";
+const METADATA_POINTER: &str = "metadataPointer";
+const TOKEN_METADATA: &str = "tokenMetadata";
+const INTEREST_BEARING_CONFIG: &str = "interestBearingConfig";
+const PERMANENT_DELEGATE: &str = "permanentDelegate";
+const TRANSFER_HOOK: &str = "transferHook";
+const NON_TRANSFERABLE: &str = "nonTransferable";
+
+/// the source of token metadata read from the `tokenMetadata` extension.
+pub const TOKEN_2022_METADATA_SOURCE: &str = "token-2022";
+
+/// deserializes the state of an extension into its response struct.
+/// returns `None` (and logs) if the extension has no state or an unexpected shape.
+fn decode_state<T: DeserializeOwned>(extension: &AccountInfoExtensionResponse) -> Option<T> {
+    let state = extension.state.clone()?;
+    match serde_json::from_value(state) {
+        Ok(decoded) => Some(decoded),
+        Err(e) => {
+            warn!(
+                "Failed to decode the {} extension: {:?}",
+                extension.extension, e
+            );
+            None
+        }
+    }
+}
+
+fn transfer_fee_to_proto(
+    fee: ExtensionTransferFeeResponse,
+) -> solana_account_protobuf::TransferFee {
+    solana_account_protobuf::TransferFee {
+        epoch: fee.epoch,
+        maximum_fee: fee.maximumFee,
+        transfer_fee_basis_points: fee.transferFeeBasisPoints,
+    }
+}
+
+fn token_metadata_to_proto(
+    metadata: ExtensionTokenMetadataResponse,
+) -> solana_account_protobuf::TokenMetadata {
+    solana_account_protobuf::TokenMetadata {
+        update_authority: metadata.updateAuthority,
+        mint: metadata.mint,
+        name: metadata.name,
+        symbol: metadata.symbol,
+        uri: metadata.uri,
+        additional_metadata: metadata
+            .additionalMetadata
+            .into_iter()
+            .map(|(key, value)| solana_account_protobuf::AdditionalMetadata { key, value })
+            .collect(),
+    }
+}
+
+/// decodes the extensions of a mint or token account.  every extension name is kept in
+/// `extension_types`, even if its state is not decoded.
+pub fn decode_extensions(
+    extensions: &[AccountInfoExtensionResponse],
+) -> solana_account_protobuf::TokenExtensions {
+    let mut decoded = solana_account_protobuf::TokenExtensions::default();
+
+    for extension in extensions {
+        decoded.extension_types.push(extension.extension.clone());
+        match extension.extension.as_str() {
+            TRANSFER_FEE_CONFIG => {
+                decoded.transfer_fee_config =
+                    decode_state::<ExtensionTransferFeeConfigResponse>(extension).map(|config| {
+                        solana_account_protobuf::TransferFeeConfig {
+                            transfer_fee_config_authority: config.transferFeeConfigAuthority,
+                            withdraw_withheld_authority: config.withdrawWithheldAuthority,
+                            withheld_amount: config.withheldAmount,
+                            older_transfer_fee: Some(transfer_fee_to_proto(
+                                config.olderTransferFee,
+                            )),
+                            newer_transfer_fee: Some(transfer_fee_to_proto(
+                                config.newerTransferFee,
+                            )),
+                        }
+                    })
+            }
+            TRANSFER_FEE_AMOUNT => {
+                decoded.withheld_amount =
+                    decode_state::<ExtensionTransferFeeAmountResponse>(extension)
+                        .map(|amount| amount.withheldAmount)
+            }
+            METADATA_POINTER => {
+                decoded.metadata_pointer =
+                    decode_state::<ExtensionMetadataPointerResponse>(extension).map(|pointer| {
+                        solana_account_protobuf::MetadataPointer {
+                            authority: pointer.authority,
+                            metadata_address: pointer.metadataAddress,
+                        }
+                    })
+            }
+            TOKEN_METADATA => {
+                decoded.token_metadata = decode_state::<ExtensionTokenMetadataResponse>(extension)
+                    .map(token_metadata_to_proto)
+            }
+            INTEREST_BEARING_CONFIG => {
+                decoded.interest_bearing_config = decode_state::<
+                    ExtensionInterestBearingConfigResponse,
+                >(extension)
+                .map(|config| solana_account_protobuf::InterestBearingConfig {
+                    rate_authority: config.rateAuthority,
+                    initialization_timestamp: config.initializationTimestamp,
+                    pre_update_average_rate: config.preUpdateAverageRate,
+                    last_update_timestamp: config.lastUpdateTimestamp,
+                    current_rate: config.currentRate,
+                })
+            }
+            PERMANENT_DELEGATE => {
+                decoded.permanent_delegate =
+                    decode_state::<ExtensionPermanentDelegateResponse>(extension)
+                        .and_then(|delegate| delegate.delegate)
+            }
+            TRANSFER_HOOK => {
+                decoded.transfer_hook = decode_state::<ExtensionTransferHookResponse>(extension)
+                    .map(|hook| solana_account_protobuf::TransferHook {
+                        authority: hook.authority,
+                        program_id: hook.programId,
+                    })
+            }
+            NON_TRANSFERABLE => decoded.non_transferable = true,
+            _ => (),
+        }
+    }
+
+    decoded
+}
+
+/// creates a token from the on-chain `tokenMetadata` extension of a Token-2022 mint, if it has one.
+/// fields that only exist in Metaplex metadata (creators, royalties, etc.) are left empty.
+pub fn token_from_metadata_extension(
+    extensions: &[AccountInfoExtensionResponse],
+    is_nft: bool,
+    token_retrieval_timestamp: u64,
+    token_context_slot: u64,
+) -> Option<solana_account_protobuf::Token> {
+    let metadata = extensions
+        .iter()
+        .find(|extension| extension.extension == TOKEN_METADATA)
+        .and_then(decode_state::<ExtensionTokenMetadataResponse>)?;
+    let is_mutable = metadata.updateAuthority.is_some();
+    let metadata = token_metadata_to_proto(metadata);
+
+    Some(solana_account_protobuf::Token {
+        is_nft,
+        mint: metadata.mint,
+        update_authority: metadata.update_authority.unwrap_or_default(),
+        name: metadata.name,
+        symbol: metadata.symbol,
+        uri: metadata.uri,
+        seller_fee_basis_points: 0,
+        creators: Vec::new(),
+        primary_sale_happened: false,
+        is_mutable,
+        retrieval_timestamp: Some(UnixTimestamp {
+            timestamp: token_retrieval_timestamp as i64,
+        }),
+        context_slot: Some(token_context_slot),
+        metadata_source: Some(String::from(TOKEN_2022_METADATA_SOURCE)),
+        additional_metadata: metadata.additional_metadata,
+    })
+}
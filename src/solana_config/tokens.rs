@@ -4,11 +4,19 @@ use super::proto_codegen::confirmed_block::UnixTimestamp;
 use super::{
     accounts::{KeyedAccountInfoResponse, KeyedTimestampedAccounts},
     proto_codegen::account_info as solana_account_protobuf,
+    token_extensions,
     types::account_response_types::{AccountDataEnumResponse, AccountInfoInfoEnumResponse},
 };
-use crate::solana_config::constants;
+use crate::solana_config::{
+    constants,
+    transformation::error::{TransformError, TransformErrorKind},
+};
 use mpl_token_metadata::accounts::Metadata;
-use std::str::FromStr;
+use solana_sdk::pubkey::Pubkey;
+use std::{str::FromStr, sync::OnceLock};
+
+/// the source of token metadata read from the Metaplex metadata account.
+pub const METAPLEX_METADATA_SOURCE: &str = "metaplex";
 
 /// Used to facilitate token data retrieval from the RPC Node, the struct contains
 /// mint data for tokens and whether it is a NFT
@@ -16,10 +24,21 @@ use std::str::FromStr;
 pub struct PackedTokenData {
     /// The mint associated with this token.
     pub mint: String,
+    /// The Metaplex metadata account (program derived address) of the mint.
+    pub metadata_account: String,
     /// Whether the token is a non-fungible token.
     pub is_nft: bool,
 }
 
+/// the metadata program id, parsed once on first use.
+fn metadata_program_id() -> &'static Pubkey {
+    static METADATA_PROGRAM_ID: OnceLock<Pubkey> = OnceLock::new();
+    METADATA_PROGRAM_ID.get_or_init(|| {
+        Pubkey::from_str(constants::METADATA_PROGRAM_ID_STR)
+            .expect("METADATA_PROGRAM_ID is parseable into a key")
+    })
+}
+
 /// retrieves the tokens associated with the mint accounts.
 /// if there are no tokens, then an empty vector is returned.
 pub fn get_tokens_from_mint_accounts(
@@ -30,6 +49,7 @@ pub fn get_tokens_from_mint_accounts(
     for KeyedAccountInfoResponse {
         pubkey,
         accounts_data,
+        ..
     } in keyed_accounts.accounts.value.into_iter()
     {
         if let AccountDataEnumResponse::AccountObject(p) = accounts_data.data {
@@ -38,25 +58,21 @@ pub fn get_tokens_from_mint_accounts(
                 if account_type == "mint" {
                     let token_amount_decimals = s.decimals;
                     if token_amount_decimals.is_some() {
-                        // TODO: when Rust stabilizes the LazyCell feature, we can initialize this once at startup.
-                        let metadata_program_id = solana_sdk::pubkey::Pubkey::from_str(
-                            constants::METADATA_PROGRAM_ID_STR,
-                        )
-                        .expect("METADATA_PROGRAM_ID is parseable into a key");
-                        let mint_key = solana_sdk::pubkey::Pubkey::from_str(&pubkey)
+                        let metadata_program_id = metadata_program_id();
+                        let mint_key = Pubkey::from_str(&pubkey)
                             .expect("account pubkey is parseable into a key");
-                        let (metadata_account, _) =
-                            solana_sdk::pubkey::Pubkey::find_program_address(
-                                &[
-                                    constants::METADATA_BYTES,
-                                    &metadata_program_id.to_bytes(),
-                                    &mint_key.to_bytes(),
-                                ],
-                                &metadata_program_id,
-                            );
+                        let (metadata_account, _) = Pubkey::find_program_address(
+                            &[
+                                constants::METADATA_BYTES,
+                                &metadata_program_id.to_bytes(),
+                                &mint_key.to_bytes(),
+                            ],
+                            metadata_program_id,
+                        );
                         let is_nft = token_amount_decimals.map(|decimals| decimals == 0).unwrap();
                         packed_token_data.push(PackedTokenData {
-                            mint: metadata_account.to_string(),
+                            mint: pubkey,
+                            metadata_account: metadata_account.to_string(),
                             is_nft,
                         });
                     };
@@ -68,24 +84,63 @@ pub fn get_tokens_from_mint_accounts(
     packed_token_data
 }
 
+/// retrieves the tokens whose metadata is stored on-chain in the `tokenMetadata` extension of
+/// their Token-2022 mint account.  these do not need a separate request for a metadata account.
+pub fn get_tokens_from_metadata_extensions(
+    keyed_accounts: &KeyedTimestampedAccounts,
+) -> Vec<solana_account_protobuf::Token> {
+    let mut tokens = Vec::new();
+
+    for KeyedAccountInfoResponse {
+        accounts_data,
+        context_slot,
+        ..
+    } in keyed_accounts.accounts.value.iter()
+    {
+        if let AccountDataEnumResponse::AccountObject(p) = &accounts_data.data {
+            if let Some(AccountInfoInfoEnumResponse::Structure(s)) = &p.parsed.info {
+                if let (true, Some(extensions), Some(decimals)) =
+                    (p.parsed.r#type == "mint", &s.extensions, s.decimals)
+                {
+                    tokens.extend(token_extensions::token_from_metadata_extension(
+                        extensions,
+                        decimals == 0,
+                        keyed_accounts.timestamp,
+                        *context_slot,
+                    ));
+                }
+            }
+        }
+    }
+
+    tokens
+}
+
 /// decodes the base64-encoded token mint account data, and returns the token data associated with it
 pub fn unpack_token_account(
     token_data: AccountDataEnumResponse,
     is_nft: bool,
     token_retrieval_timestamp: u64,
-) -> solana_account_protobuf::Token {
+    token_context_slot: u64,
+) -> Result<solana_account_protobuf::Token, TransformError> {
+    let invalid_metadata = |reason: String| TransformErrorKind::InvalidTokenMetadata(reason);
     match token_data {
         AccountDataEnumResponse::Array(strings) => {
-            let string_slice: [String; 2] = strings.try_into().expect("accounts.data is 2 strings");
+            let string_slice: [String; 2] = strings
+                .try_into()
+                .map_err(|_| invalid_metadata("the account data should be 2 strings".into()))?;
             let [encoded_data, _encoding] = string_slice;
             let base64_decoded_data =
-                base64::Engine::decode(&base64::prelude::BASE64_STANDARD, encoded_data).unwrap();
-            let metadata = Metadata::from_bytes(&base64_decoded_data).unwrap();
+                base64::Engine::decode(&base64::prelude::BASE64_STANDARD, encoded_data)
+                    .map_err(|e| invalid_metadata(e.to_string()))?;
+            let metadata = Metadata::from_bytes(&base64_decoded_data)
+                .map_err(|e| invalid_metadata(e.to_string()))?;
 
-            solana_account_protobuf::Token {
+            Ok(solana_account_protobuf::Token {
                 retrieval_timestamp: Some(UnixTimestamp {
                     timestamp: token_retrieval_timestamp as i64,
                 }),
+                context_slot: Some(token_context_slot),
                 is_nft,
                 mint: metadata.mint.to_string(),
                 update_authority: metadata.update_authority.to_string(),
@@ -106,8 +161,10 @@ pub fn unpack_token_account(
                 },
                 primary_sale_happened: metadata.primary_sale_happened,
                 is_mutable: metadata.is_mutable,
-            }
+                metadata_source: Some(String::from(METAPLEX_METADATA_SOURCE)),
+                additional_metadata: Vec::new(),
+            })
         }
-        _ => panic!("token data should be a list of strings"),
+        _ => Err(invalid_metadata("the account data should be a list of strings".into()).into()),
     }
 }
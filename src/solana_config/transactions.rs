@@ -1,81 +1,176 @@
 //! This file contains various helper functions for interacting with transaction data.
-use super::types::block_response_types::ParsedType;
-use solana_transaction_status::{UiConfirmedBlock, UiTransaction};
+use std::collections::HashSet;
+
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiConfirmedBlock, UiInstruction, UiMessage,
+    UiParsedInstruction, UiTransaction, UiTransactionStatusMeta,
+};
+
+use crate::config::Config;
 
 pub struct TransactionAccounts {
     pub tx_signature: String,
     pub accounts: Vec<String>,
+    /// the initial state of each created account, as given by its `createAccount` instruction.
+    pub created_accounts: Vec<CreatedAccount>,
+}
+
+/// the initial state of an account, taken from the `createAccount` instruction that created it.
+pub struct CreatedAccount {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub owner: String,
+    pub space: u64,
+}
+
+/// Which accounts of each transaction are requested for the accounts table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountDiscovery {
+    /// the accounts created or initialized by the transaction's instructions, including the
+    /// instructions invoked by other programs.
+    Created,
+    /// the created accounts, plus every other account the transaction can write to.
+    Writable,
+}
+
+impl AccountDiscovery {
+    /// Reads the policy from the `ACCOUNT_DISCOVERY` setting, defaulting to `created`.
+    pub fn from_config(config: &Config) -> AccountDiscovery {
+        // env:account_discovery = Which accounts of a transaction to index, either `created` or `writable`
+        match config.account_discovery.as_deref() {
+            None | Some("created") => AccountDiscovery::Created,
+            Some("writable") => AccountDiscovery::Writable,
+            Some(policy) => panic!(
+                "ACCOUNT_DISCOVERY should be either `created` or `writable`, not {:?}",
+                policy
+            ),
+        }
+    }
 }
 
 use log::debug;
 
-/// retrieves a list of created accounts from a list of transactions.
-/// if there are no created accounts, then an empty vector is returned.
+/// returns the info key holding the account that an instruction creates or initializes.
+fn created_account_key(program: &str, instruction_type: &str) -> Option<&'static str> {
+    match (program, instruction_type) {
+        ("system", "createAccount" | "createAccountWithSeed") => Some("newAccount"),
+        ("spl-associated-token-account", "create" | "createIdempotent") => Some("account"),
+        (
+            "spl-token" | "spl-token-2022",
+            "initializeAccount" | "initializeAccount2" | "initializeAccount3",
+        ) => Some("account"),
+        ("spl-token" | "spl-token-2022", "initializeMint" | "initializeMint2") => Some("mint"),
+        _ => None,
+    }
+}
+
+/// The accounts discovered so far in a transaction, in the order they were found.
+#[derive(Default)]
+struct DiscoveredAccounts {
+    seen: HashSet<String>,
+    accounts: Vec<String>,
+    created_accounts: Vec<CreatedAccount>,
+}
+
+impl DiscoveredAccounts {
+    fn push(&mut self, pubkey: &str) -> bool {
+        if pubkey.is_empty() || !self.seen.insert(pubkey.to_string()) {
+            return false;
+        }
+        self.accounts.push(pubkey.to_string());
+        true
+    }
+
+    /// records the account an instruction creates or initializes, if any.  only the system
+    /// program's instructions give the initial lamports, owner and space of the account.
+    fn push_instruction(&mut self, instruction: &UiInstruction) {
+        let parsed_instruction = match instruction {
+            UiInstruction::Parsed(UiParsedInstruction::Parsed(parsed_instruction)) => {
+                parsed_instruction
+            }
+            UiInstruction::Parsed(UiParsedInstruction::PartiallyDecoded(_)) => return,
+            UiInstruction::Compiled(_) => panic!("should be requesting jsonParsed data"),
+        };
+        let (Some(instruction_type), Some(serde_json::Value::Object(info))) = (
+            parsed_instruction
+                .parsed
+                .get("type")
+                .and_then(serde_json::Value::as_str),
+            parsed_instruction.parsed.get("info"),
+        ) else {
+            return;
+        };
+        let Some(key) = created_account_key(&parsed_instruction.program, instruction_type)
+            .and_then(|account_key| info.get(account_key))
+            .and_then(serde_json::Value::as_str)
+        else {
+            return;
+        };
+
+        if self.push(key) && parsed_instruction.program == "system" {
+            self.created_accounts.push(CreatedAccount {
+                pubkey: key.to_string(),
+                lamports: info["lamports"].as_u64().unwrap_or_default(),
+                owner: info["owner"].as_str().unwrap_or_default().to_string(),
+                space: info["space"].as_u64().unwrap_or_default(),
+            });
+        }
+    }
+}
+
+/// retrieves the accounts to index from a list of transactions, following the discovery policy.
+/// the created accounts of each transaction come first, in execution order.
+/// transactions without any accounts to index are left out.
 pub fn get_pubkeys_from_transactions(
-    transactions: Vec<&UiTransaction>,
+    transactions: Vec<(&UiTransaction, Option<&UiTransactionStatusMeta>)>,
+    discovery: AccountDiscovery,
 ) -> Vec<TransactionAccounts> {
     let mut all_transaction_accounts = Vec::new();
-    for transaction in transactions.into_iter() {
-        match &transaction.message {
-            solana_transaction_status::UiMessage::Parsed(parsed_message) => {
-                let instructions = parsed_message.instructions.clone();
-                let mut new_account_pubkeys = Vec::new();
-                for instruction in instructions {
-                    match instruction {
-                        solana_transaction_status::UiInstruction::Parsed(
-                            parsed_or_partially_instruction,
-                        ) => {
-                            if let solana_transaction_status::UiParsedInstruction::Parsed(
-                                parsed_instruction,
-                            ) = parsed_or_partially_instruction
-                            {
-                                if parsed_instruction.parsed.is_object() {
-                                    let instruction_parsed_type: ParsedType =
-                                        parsed_instruction.parsed.into();
-                                    let instruction_type = instruction_parsed_type.r#type;
-                                    let instruction_info: Option<serde_json::Value> =
-                                        instruction_parsed_type.info;
-
-                                    if let (Some(t), Some(i)) = (instruction_type, instruction_info)
-                                    {
-                                        if parsed_instruction.program == "system"
-                                            && t == "createAccount"
-                                        {
-                                            if let serde_json::Value::Object(info) = i {
-                                                if let serde_json::Value::String(key) =
-                                                    &info["newAccount"]
-                                                {
-                                                    if !key.is_empty() {
-                                                        new_account_pubkeys.push(key.clone());
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        _ => panic!("should be requesting jsonParsed data"),
-                    }
-                }
-                if !new_account_pubkeys.is_empty() {
-                    let tx_signature = String::from(&transaction.signatures[0]);
-                    let transaction_account = TransactionAccounts {
-                        tx_signature,
-                        accounts: new_account_pubkeys,
-                    };
-                    all_transaction_accounts.push(transaction_account);
+    for (transaction, meta) in transactions.into_iter() {
+        let UiMessage::Parsed(parsed_message) = &transaction.message else {
+            continue;
+        };
+        let inner_instructions = match meta.map(|meta| &meta.inner_instructions) {
+            Some(OptionSerializer::Some(inner_instructions)) => inner_instructions.as_slice(),
+            _ => &[],
+        };
+
+        let mut discovered = DiscoveredAccounts::default();
+        for (index, instruction) in parsed_message.instructions.iter().enumerate() {
+            discovered.push_instruction(instruction);
+            for inner in inner_instructions
+                .iter()
+                .filter(|inner| inner.index as usize == index)
+            {
+                for inner_instruction in &inner.instructions {
+                    discovered.push_instruction(inner_instruction);
                 }
             }
-            _ => continue,
-        };
+        }
+        if discovery == AccountDiscovery::Writable {
+            for account_key in parsed_message.account_keys.iter().filter(|a| a.writable) {
+                discovered.push(&account_key.pubkey);
+            }
+        }
+
+        if !discovered.accounts.is_empty() {
+            let tx_signature = String::from(&transaction.signatures[0]);
+            let transaction_account = TransactionAccounts {
+                tx_signature,
+                accounts: discovered.accounts,
+                created_accounts: discovered.created_accounts,
+            };
+            all_transaction_accounts.push(transaction_account);
+        }
     }
     all_transaction_accounts
 }
 
-/// retrieves the transactions from a block.
+/// retrieves the transactions from a block, along with their status metadata.
 /// if there are no transactions, then an empty vector is returned.
-pub fn get_transactions_from_block(block: &UiConfirmedBlock) -> Vec<&UiTransaction> {
+pub fn get_transactions_from_block(
+    block: &UiConfirmedBlock,
+) -> Vec<(&UiTransaction, Option<&UiTransactionStatusMeta>)> {
     if let Some(transactions) = &block.transactions {
         let transaction_len = transactions.len();
         let blockhash = &block.blockhash;
@@ -85,10 +180,12 @@ pub fn get_transactions_from_block(block: &UiConfirmedBlock) -> Vec<&UiTransacti
         .transactions
         .iter()
         .flatten()
-        .map(|transaction_with_meta| &transaction_with_meta.transaction)
-        .map(|t| match t {
-            solana_transaction_status::EncodedTransaction::Json(transaction) => transaction,
-            _ => panic!("Unexpected transaction encoding"),
+        .map(|transaction_with_meta| {
+            let transaction = match &transaction_with_meta.transaction {
+                solana_transaction_status::EncodedTransaction::Json(transaction) => transaction,
+                _ => panic!("Unexpected transaction encoding"),
+            };
+            (transaction, transaction_with_meta.meta.as_ref())
         })
         .collect()
 }
@@ -1,24 +1,45 @@
-use crate::solana_config::proto_codegen::etl_block::EtlBlock;
+use crate::solana_config::{
+    instruction_decoders::InstructionDecoderRegistry,
+    proto_codegen::{
+        account_info::{AdditionalMetadata, TransferFee},
+        etl_block::EtlBlock,
+    },
+};
 
 #[cfg(feature = "STRING_TIMESTAMP")]
-use {
-    crate::solana_config::proto_codegen::records_string_timestamp::{
-        AccountRecord, AuthorizedVoterRecord, CreatorRecord, DataRecord, EpochCreditRecord,
-        PriorVoterRecord, TimestampRecord, TokenRecord, VoteRecord,
-    },
-    chrono::{NaiveDateTime, TimeZone, Utc},
+use crate::solana_config::proto_codegen::records_string_timestamp::{
+    AccountRecord, AdditionalMetadataRecord, AuthorizedVoterRecord, CreatorRecord, DataRecord,
+    EpochCreditRecord, InterestBearingConfigRecord, MetadataPointerRecord, PriorVoterRecord,
+    TimestampRecord, TokenMetadataRecord, TokenRecord, TransferFeeConfigRecord, TransferFeeRecord,
+    TransferHookRecord, VoteRecord,
 };
 
 #[cfg(feature = "INT_TIMESTAMP")]
 use crate::solana_config::proto_codegen::records_int_timestamp::{
-    AccountRecord, AuthorizedVoterRecord, CreatorRecord, DataRecord, EpochCreditRecord,
-    PriorVoterRecord, TimestampRecord, TokenRecord, VoteRecord,
+    AccountRecord, AdditionalMetadataRecord, AuthorizedVoterRecord, CreatorRecord, DataRecord,
+    EpochCreditRecord, InterestBearingConfigRecord, MetadataPointerRecord, PriorVoterRecord,
+    TimestampRecord, TokenMetadataRecord, TokenRecord, TransferFeeConfigRecord, TransferFeeRecord,
+    TransferHookRecord, VoteRecord,
 };
 
+use super::error::{record_timestamp, TransformError, TransformErrorKind};
+
 pub fn transform_to_account_and_token_records(
     etl_block: &EtlBlock,
-) -> (Vec<AccountRecord>, Vec<TokenRecord>) {
-    let table_context = etl_block.table_context.to_owned().unwrap();
+    instruction_decoders: &InstructionDecoderRegistry,
+) -> Result<(Vec<AccountRecord>, Vec<TokenRecord>), TransformError> {
+    let at_slot = |kind| TransformError::from(kind).at_slot(etl_block.slot);
+    let table_context = etl_block
+        .table_context
+        .as_ref()
+        .ok_or(TransformErrorKind::MissingField("etl_block.table_context"))
+        .map_err(at_slot)?;
+    let block_timestamp = table_context
+        .block_timestamp
+        .as_ref()
+        .map(|bt| record_timestamp(bt.timestamp))
+        .transpose()
+        .map_err(at_slot)?;
 
     let mut account_records: Vec<AccountRecord> = Vec::with_capacity(etl_block.accounts.len());
     let mut token_records: Vec<TokenRecord> = Vec::with_capacity(
@@ -34,29 +55,29 @@ pub fn transform_to_account_and_token_records(
         let block_slot = Some(etl_block.slot as i64);
         let block_hash = Some(table_context.block_hash.to_owned());
 
-        #[cfg(feature = "STRING_TIMESTAMP")]
-        let block_timestamp = table_context.block_timestamp.as_ref().map(|bt| {
-            Utc.from_utc_datetime(&NaiveDateTime::from_timestamp_opt(bt.timestamp, 0).unwrap())
-                .to_rfc3339()
-        });
-        #[cfg(feature = "INT_TIMESTAMP")]
-        let block_timestamp = table_context
-            .block_timestamp
-            .to_owned()
-            .map(|bt| bt.timestamp * 1_000_000);
-
         for account_data in &accounts_and_tokens.accounts {
-            #[cfg(feature = "STRING_TIMESTAMP")]
-            let retrieval_timestamp = account_data.retrieval_timestamp.as_ref().map(|bt| {
-                Utc.from_utc_datetime(&NaiveDateTime::from_timestamp_opt(bt.timestamp, 0).unwrap())
-                    .to_rfc3339()
-            });
-            #[cfg(feature = "INT_TIMESTAMP")]
+            let for_account = |kind| at_slot(kind).for_account(&account_data.pubkey);
             let retrieval_timestamp = account_data
                 .retrieval_timestamp
-                .to_owned()
-                .map(|bt| bt.timestamp * 1_000_000);
+                .as_ref()
+                .map(|bt| record_timestamp(bt.timestamp))
+                .transpose()
+                .map_err(for_account)?;
 
+            // the RPC node returns the data of accounts it can't parse as base64,
+            // which may be decoded by the decoder registered for the account's owner
+            let decoded_account = match (&account_data.owner, &account_data.data) {
+                (Some(owner), Some(data)) if data.encoding == "base64" => {
+                    instruction_decoders.decode_account(owner, &data.raw)
+                }
+                _ => None,
+            };
+
+            let stake = account_data.stake.as_ref();
+            let nonce = account_data.nonce.as_ref();
+            let lookup_table = account_data.lookup_table.as_ref();
+            let program_data = account_data.program_data_state.as_ref();
+            let sysvar = account_data.sysvar.as_ref();
             let account_record = AccountRecord {
                 block_slot,
                 block_hash: block_hash.to_owned(),
@@ -70,7 +91,11 @@ pub fn transform_to_account_and_token_records(
                 rent_epoch: Some(account_data.rent_epoch as i64),
                 program: account_data.program.to_owned(),
                 space: account_data.space,
-                account_type: account_data.account_type.to_owned(),
+                account_type: account_data.account_type.to_owned().or_else(|| {
+                    decoded_account
+                        .as_ref()
+                        .map(|decoded| decoded.account_type.to_owned())
+                }),
                 is_native: account_data.is_native,
                 mint: account_data.mint.to_owned(),
                 state: account_data.state.to_owned(),
@@ -122,20 +147,13 @@ pub fn transform_to_account_and_token_records(
                     .last_timestamp
                     .iter()
                     .map(|lt| {
-                        #[cfg(feature = "STRING_TIMESTAMP")]
-                        let timestamp = Utc
-                            .from_utc_datetime(
-                                &NaiveDateTime::from_timestamp_opt(lt.timestamp, 0).unwrap(),
-                            )
-                            .to_rfc3339();
-                        #[cfg(feature = "INT_TIMESTAMP")]
-                        let timestamp = lt.timestamp * 1_000_000;
-                        TimestampRecord {
+                        Ok(TimestampRecord {
                             slot: Some(lt.slot),
-                            timestamp: Some(timestamp),
-                        }
+                            timestamp: Some(record_timestamp(lt.timestamp)?),
+                        })
                     })
-                    .collect(),
+                    .collect::<Result<_, _>>()
+                    .map_err(for_account)?,
                 data: account_data
                     .data
                     .iter()
@@ -144,21 +162,154 @@ pub fn transform_to_account_and_token_records(
                         encoding: Some(d.encoding.to_owned()),
                     })
                     .collect(),
+                context_slot: account_data.context_slot.map(|slot| slot as i64),
+                state_drift_slots: account_data
+                    .context_slot
+                    .map(|slot| slot as i64 - etl_block.slot as i64),
+                extension_types: account_data
+                    .extensions
+                    .iter()
+                    .flat_map(|e| e.extension_types.to_owned())
+                    .collect(),
+                transfer_fee_config: account_data
+                    .extensions
+                    .iter()
+                    .flat_map(|e| e.transfer_fee_config.as_ref())
+                    .map(|tfc| TransferFeeConfigRecord {
+                        transfer_fee_config_authority: tfc.transfer_fee_config_authority.to_owned(),
+                        withdraw_withheld_authority: tfc.withdraw_withheld_authority.to_owned(),
+                        withheld_amount: Some(tfc.withheld_amount),
+                        older_transfer_fee: tfc
+                            .older_transfer_fee
+                            .iter()
+                            .map(transfer_fee_record)
+                            .collect(),
+                        newer_transfer_fee: tfc
+                            .newer_transfer_fee
+                            .iter()
+                            .map(transfer_fee_record)
+                            .collect(),
+                    })
+                    .collect(),
+                withheld_amount: account_data
+                    .extensions
+                    .as_ref()
+                    .and_then(|e| e.withheld_amount),
+                metadata_pointer: account_data
+                    .extensions
+                    .iter()
+                    .flat_map(|e| e.metadata_pointer.as_ref())
+                    .map(|mp| MetadataPointerRecord {
+                        authority: mp.authority.to_owned(),
+                        metadata_address: mp.metadata_address.to_owned(),
+                    })
+                    .collect(),
+                token_metadata: account_data
+                    .extensions
+                    .iter()
+                    .flat_map(|e| e.token_metadata.as_ref())
+                    .map(|tm| TokenMetadataRecord {
+                        update_authority: tm.update_authority.to_owned(),
+                        mint: Some(tm.mint.to_owned()),
+                        name: Some(tm.name.to_owned()),
+                        symbol: Some(tm.symbol.to_owned()),
+                        uri: Some(tm.uri.to_owned()),
+                        additional_metadata: tm
+                            .additional_metadata
+                            .iter()
+                            .map(additional_metadata_record)
+                            .collect(),
+                    })
+                    .collect(),
+                interest_bearing_config: account_data
+                    .extensions
+                    .iter()
+                    .flat_map(|e| e.interest_bearing_config.as_ref())
+                    .map(|ibc| {
+                        Ok(InterestBearingConfigRecord {
+                            rate_authority: ibc.rate_authority.to_owned(),
+                            initialization_timestamp: Some(record_timestamp(
+                                ibc.initialization_timestamp,
+                            )?),
+                            pre_update_average_rate: Some(ibc.pre_update_average_rate),
+                            last_update_timestamp: Some(record_timestamp(
+                                ibc.last_update_timestamp,
+                            )?),
+                            current_rate: Some(ibc.current_rate),
+                        })
+                    })
+                    .collect::<Result<_, _>>()
+                    .map_err(for_account)?,
+                permanent_delegate: account_data
+                    .extensions
+                    .as_ref()
+                    .and_then(|e| e.permanent_delegate.to_owned()),
+                transfer_hook: account_data
+                    .extensions
+                    .iter()
+                    .flat_map(|e| e.transfer_hook.as_ref())
+                    .map(|th| TransferHookRecord {
+                        authority: th.authority.to_owned(),
+                        program_id: th.program_id.to_owned(),
+                    })
+                    .collect(),
+                non_transferable: account_data.extensions.as_ref().map(|e| e.non_transferable),
+                decoded_data: decoded_account
+                    .and_then(|decoded| decoded.fields)
+                    .map(|fields| fields.to_string()),
+                authorized_staker: stake.map(|s| s.staker.to_owned()),
+                rent_exempt_reserve: stake.map(|s| s.rent_exempt_reserve),
+                lockup_unix_timestamp: stake.map(|s| s.lockup_unix_timestamp),
+                lockup_epoch: stake.map(|s| s.lockup_epoch),
+                lockup_custodian: stake.map(|s| s.lockup_custodian.to_owned()),
+                delegation_voter: stake.and_then(|s| s.voter.to_owned()),
+                delegated_stake: stake.and_then(|s| s.delegated_stake),
+                activation_epoch: stake.and_then(|s| s.activation_epoch),
+                deactivation_epoch: stake.and_then(|s| s.deactivation_epoch),
+                warmup_cooldown_rate: stake.and_then(|s| s.warmup_cooldown_rate),
+                credits_observed: stake.and_then(|s| s.credits_observed),
+                nonce_authority: nonce.map(|n| n.authority.to_owned()),
+                nonce_blockhash: nonce.map(|n| n.blockhash.to_owned()),
+                lamports_per_signature: nonce
+                    .and_then(|n| n.lamports_per_signature)
+                    .or_else(|| sysvar.and_then(|s| s.lamports_per_signature)),
+                lookup_table_authority: lookup_table.and_then(|l| l.authority.to_owned()),
+                lookup_table_addresses: lookup_table
+                    .map(|l| l.addresses.to_owned())
+                    .unwrap_or_default(),
+                deactivation_slot: lookup_table.and_then(|l| l.deactivation_slot),
+                last_extended_slot: lookup_table.and_then(|l| l.last_extended_slot),
+                last_extended_slot_start_index: lookup_table
+                    .map(|l| l.last_extended_slot_start_index),
+                upgrade_authority: program_data.and_then(|p| p.upgrade_authority.to_owned()),
+                program_data_slot: program_data.map(|p| p.slot),
+                clock_slot: sysvar.and_then(|s| s.clock_slot),
+                clock_epoch: sysvar.and_then(|s| s.clock_epoch),
+                clock_epoch_start_timestamp: sysvar.and_then(|s| s.clock_epoch_start_timestamp),
+                clock_leader_schedule_epoch: sysvar.and_then(|s| s.clock_leader_schedule_epoch),
+                clock_unix_timestamp: sysvar.and_then(|s| s.clock_unix_timestamp),
+                slots_per_epoch: sysvar.and_then(|s| s.slots_per_epoch),
+                leader_schedule_slot_offset: sysvar.and_then(|s| s.leader_schedule_slot_offset),
+                warmup: sysvar.and_then(|s| s.warmup),
+                first_normal_epoch: sysvar.and_then(|s| s.first_normal_epoch),
+                first_normal_slot: sysvar.and_then(|s| s.first_normal_slot),
+                lamports_per_byte_year: sysvar.and_then(|s| s.lamports_per_byte_year),
+                exemption_threshold: sysvar.and_then(|s| s.exemption_threshold),
+                burn_percent: sysvar.and_then(|s| s.burn_percent),
+                validator_point_value: sysvar.and_then(|s| s.validator_point_value),
+                next_slot: sysvar.and_then(|s| s.next_slot),
+                last_restart_slot: sysvar.and_then(|s| s.last_restart_slot),
             };
             account_records.push(account_record);
         }
 
         for token_data in &accounts_and_tokens.tokens {
-            #[cfg(feature = "STRING_TIMESTAMP")]
-            let retrieval_timestamp = token_data.retrieval_timestamp.as_ref().map(|bt| {
-                Utc.from_utc_datetime(&NaiveDateTime::from_timestamp_opt(bt.timestamp, 0).unwrap())
-                    .to_rfc3339()
-            });
-            #[cfg(feature = "INT_TIMESTAMP")]
             let retrieval_timestamp = token_data
                 .retrieval_timestamp
-                .to_owned()
-                .map(|bt| bt.timestamp * 1_000_000);
+                .as_ref()
+                .map(|bt| record_timestamp(bt.timestamp))
+                .transpose()
+                .map_err(|kind| at_slot(kind).for_account(&token_data.mint))?;
 
             let token_record = TokenRecord {
                 block_slot,
@@ -185,11 +336,38 @@ pub fn transform_to_account_and_token_records(
                     .collect(),
                 primary_sale_happened: Some(token_data.primary_sale_happened),
                 is_mutable: Some(token_data.is_mutable),
+                context_slot: token_data.context_slot.map(|slot| slot as i64),
+                state_drift_slots: token_data
+                    .context_slot
+                    .map(|slot| slot as i64 - etl_block.slot as i64),
+                metadata_source: token_data.metadata_source.to_owned(),
+                additional_metadata: token_data
+                    .additional_metadata
+                    .iter()
+                    .map(additional_metadata_record)
+                    .collect(),
             };
 
             token_records.push(token_record);
         }
     }
 
-    (account_records, token_records)
+    Ok((account_records, token_records))
+}
+
+fn transfer_fee_record(transfer_fee: &TransferFee) -> TransferFeeRecord {
+    TransferFeeRecord {
+        epoch: Some(transfer_fee.epoch),
+        maximum_fee: Some(transfer_fee.maximum_fee),
+        transfer_fee_basis_points: Some(transfer_fee.transfer_fee_basis_points),
+    }
+}
+
+fn additional_metadata_record(
+    additional_metadata: &AdditionalMetadata,
+) -> AdditionalMetadataRecord {
+    AdditionalMetadataRecord {
+        key: Some(additional_metadata.key.to_owned()),
+        value: Some(additional_metadata.value.to_owned()),
+    }
 }
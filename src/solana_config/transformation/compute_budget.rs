@@ -0,0 +1,83 @@
+use crate::solana_config::{
+    constants, instruction_decoders::compute_budget::ComputeBudgetInstruction,
+    proto_codegen::confirmed_block::InnerInstruction,
+};
+
+/// The compute budget a transaction requested through its Compute Budget instructions.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ComputeBudget {
+    /// The requested compute unit limit, if set.
+    pub compute_unit_limit: Option<u32>,
+    /// The requested price of a compute unit in micro-lamports, if set.
+    pub compute_unit_price: Option<u64>,
+    /// The requested heap frame size in bytes, if set.
+    pub heap_frame_bytes: Option<u32>,
+    /// The priority fee in lamports, paid on top of the base fee.
+    pub priority_fee: u64,
+}
+
+/// parses the Compute Budget instructions of a transaction.  only the top-level instructions are
+/// considered, since the runtime ignores Compute Budget instructions invoked through CPI.
+///
+/// the priority fee is the compute unit price times the compute unit limit (not the compute units
+/// consumed), rounded up to the next lamport.  if the transaction doesn't set a limit, each
+/// instruction other than the Compute Budget ones gets the default limit.
+pub fn parse_compute_budget(instructions: &[InnerInstruction]) -> ComputeBudget {
+    let mut compute_budget = ComputeBudget::default();
+    let mut deprecated_additional_fee = None;
+    let mut non_compute_budget_instructions: u32 = 0;
+
+    for instruction in instructions {
+        if instruction.program_id.as_deref() != Some(constants::COMPUTE_BUDGET_PROGRAM_ID_STR) {
+            non_compute_budget_instructions += 1;
+            continue;
+        }
+        let data = instruction
+            .data
+            .as_deref()
+            .and_then(|data| solana_sdk::bs58::decode(data).into_vec().ok());
+        match data.as_deref().and_then(ComputeBudgetInstruction::parse) {
+            Some(ComputeBudgetInstruction::RequestUnits {
+                units,
+                additional_fee,
+            }) => {
+                compute_budget.compute_unit_limit = Some(units);
+                deprecated_additional_fee = Some(additional_fee as u64);
+            }
+            Some(ComputeBudgetInstruction::RequestHeapFrame { bytes }) => {
+                compute_budget.heap_frame_bytes = Some(bytes)
+            }
+            Some(ComputeBudgetInstruction::SetComputeUnitLimit { units }) => {
+                compute_budget.compute_unit_limit = Some(units)
+            }
+            Some(ComputeBudgetInstruction::SetComputeUnitPrice { micro_lamports }) => {
+                compute_budget.compute_unit_price = Some(micro_lamports)
+            }
+            Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit { .. }) | None => (),
+        }
+    }
+
+    let effective_limit = compute_budget
+        .compute_unit_limit
+        .unwrap_or_else(|| {
+            non_compute_budget_instructions
+                .saturating_mul(constants::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        })
+        .min(constants::MAX_COMPUTE_UNIT_LIMIT);
+    compute_budget.priority_fee =
+        match (compute_budget.compute_unit_price, deprecated_additional_fee) {
+            (Some(price), _) => {
+                let micro_lamports = price as u128 * effective_limit as u128;
+                micro_lamports.div_ceil(constants::MICRO_LAMPORTS_PER_LAMPORT as u128) as u64
+            }
+            (None, Some(additional_fee)) => additional_fee,
+            (None, None) => 0,
+        };
+
+    compute_budget
+}
+
+/// the base fee of a transaction, which only depends on its number of signatures.
+pub fn base_fee(signature_count: usize) -> u64 {
+    signature_count as u64 * constants::LAMPORTS_PER_SIGNATURE
+}
@@ -0,0 +1,135 @@
+//! The error returned when a block can't be converted or transformed into records, with where in
+//! the block it happened.
+use std::fmt;
+
+use thiserror::Error;
+
+#[cfg(feature = "STRING_TIMESTAMP")]
+use chrono::{NaiveDateTime, TimeZone, Utc};
+
+/// What went wrong while converting or transforming a block.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum TransformErrorKind {
+    #[error("{0} is missing")]
+    MissingField(&'static str),
+    #[error("{0} is not a valid unix timestamp")]
+    InvalidTimestamp(i64),
+    #[error("unexpected reward type {0}")]
+    UnexpectedRewardType(i32),
+    #[error("{0} should be requested with the jsonParsed encoding")]
+    UnexpectedEncoding(&'static str),
+    #[error("the parsed instruction doesn't match the expected format: {0}")]
+    InvalidParsedInstruction(String),
+    #[error("the instruction parameters are not valid json: {0}")]
+    InvalidInstructionParams(String),
+    #[error("the instruction parameters are not a key-value pair")]
+    InstructionParamsNotAnObject,
+    #[error("the token metadata account can't be decoded: {0}")]
+    InvalidTokenMetadata(String),
+}
+
+/// An error converting or transforming a block, with the slot, transaction, instruction or
+/// account it happened at, when known.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub struct TransformError {
+    pub kind: TransformErrorKind,
+    pub slot: Option<u64>,
+    /// the index of the transaction within the block
+    pub transaction_index: Option<usize>,
+    /// the index of the top-level instruction within the transaction
+    pub instruction_index: Option<usize>,
+    /// the index of the inner instruction within the top-level instruction's inner instructions
+    pub inner_instruction_index: Option<usize>,
+    pub account: Option<String>,
+}
+
+impl From<TransformErrorKind> for TransformError {
+    fn from(kind: TransformErrorKind) -> Self {
+        TransformError {
+            kind,
+            slot: None,
+            transaction_index: None,
+            instruction_index: None,
+            inner_instruction_index: None,
+            account: None,
+        }
+    }
+}
+
+// The context is added from the innermost call outwards, so each method keeps the context that
+// was already set.
+impl TransformError {
+    pub fn at_slot(mut self, slot: u64) -> Self {
+        self.slot.get_or_insert(slot);
+        self
+    }
+
+    pub fn at_transaction(mut self, transaction_index: usize) -> Self {
+        self.transaction_index.get_or_insert(transaction_index);
+        self
+    }
+
+    pub fn at_instruction(mut self, instruction_index: usize) -> Self {
+        self.instruction_index.get_or_insert(instruction_index);
+        self
+    }
+
+    pub fn at_inner_instruction(mut self, parent_index: usize, inner_index: usize) -> Self {
+        if self.instruction_index.is_none() {
+            self.instruction_index = Some(parent_index);
+            self.inner_instruction_index = Some(inner_index);
+        }
+        self
+    }
+
+    pub fn for_account(mut self, account: &str) -> Self {
+        self.account.get_or_insert_with(|| account.to_string());
+        self
+    }
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut context = Vec::new();
+        if let Some(slot) = self.slot {
+            context.push(format!("slot {}", slot));
+        }
+        if let Some(transaction_index) = self.transaction_index {
+            context.push(format!("transaction {}", transaction_index));
+        }
+        match (self.instruction_index, self.inner_instruction_index) {
+            (Some(parent_index), Some(inner_index)) => context.push(format!(
+                "inner instruction {}.{}",
+                parent_index, inner_index
+            )),
+            (Some(instruction_index), None) => {
+                context.push(format!("instruction {}", instruction_index))
+            }
+            _ => {}
+        }
+        if let Some(account) = &self.account {
+            context.push(format!("account {}", account));
+        }
+        if context.is_empty() {
+            write!(f, "{}", self.kind)
+        } else {
+            write!(f, "{}: {}", context.join(", "), self.kind)
+        }
+    }
+}
+
+/// Converts a unix timestamp (in seconds) into the timestamp format of the records.
+#[cfg(feature = "STRING_TIMESTAMP")]
+pub fn record_timestamp(timestamp: i64) -> Result<String, TransformErrorKind> {
+    NaiveDateTime::from_timestamp_opt(timestamp, 0)
+        .map(|datetime| Utc.from_utc_datetime(&datetime).to_rfc3339())
+        .ok_or(TransformErrorKind::InvalidTimestamp(timestamp))
+}
+
+/// Converts a unix timestamp (in seconds) into the timestamp format of the records.
+#[cfg(feature = "INT_TIMESTAMP")]
+pub fn record_timestamp(timestamp: i64) -> Result<i64, TransformErrorKind> {
+    timestamp
+        .checked_mul(1_000_000)
+        .ok_or(TransformErrorKind::InvalidTimestamp(timestamp))
+}
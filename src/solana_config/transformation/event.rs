@@ -0,0 +1,70 @@
+use base64::Engine;
+use log::warn;
+
+use crate::solana_config::instruction_decoders::InstructionDecoderRegistry;
+
+#[cfg(feature = "STRING_TIMESTAMP")]
+use crate::solana_config::proto_codegen::records_string_timestamp::EventRecord;
+
+#[cfg(feature = "INT_TIMESTAMP")]
+use crate::solana_config::proto_codegen::records_int_timestamp::EventRecord;
+
+use super::invocation::{invoked_program, is_invocation_end};
+use super::transaction::CommonTableFields;
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// extracts the events emitted through `sol_log_data` (the `Program data:` log lines, which is what
+/// Anchor's `emit!` uses) from a transaction's log messages.  the emitting program is resolved by
+/// following the invoke stack in the logs.  a log line with several base64 chunks is a single event,
+/// with the chunks concatenated.
+pub fn transform_to_event_records(
+    common_table_fields: CommonTableFields,
+    log_messages: &[String],
+    instruction_decoders: &InstructionDecoderRegistry,
+) -> Vec<EventRecord> {
+    let mut event_records = Vec::new();
+    let mut invoke_stack: Vec<&str> = Vec::new();
+
+    for log in log_messages {
+        if let Some((program_id, _)) = invoked_program(log) {
+            invoke_stack.push(program_id);
+        } else if is_invocation_end(log) {
+            invoke_stack.pop();
+        } else if let Some(payload) = log.strip_prefix(PROGRAM_DATA_PREFIX) {
+            let data = match payload
+                .split(' ')
+                .map(|chunk| base64::engine::general_purpose::STANDARD.decode(chunk))
+                .collect::<Result<Vec<Vec<u8>>, _>>()
+            {
+                Ok(chunks) => chunks.concat(),
+                Err(e) => {
+                    warn!("event data is not valid base64: {:?}", e);
+                    continue;
+                }
+            };
+            let program_id = invoke_stack.last().map(|program_id| program_id.to_string());
+            let decoded_event = program_id
+                .as_deref()
+                .and_then(|program_id| instruction_decoders.decode_event(program_id, &data));
+
+            event_records.push(EventRecord {
+                block_slot: common_table_fields.block_slot,
+                block_hash: common_table_fields.block_hash.to_owned(),
+                block_timestamp: common_table_fields.block_timestamp.to_owned(),
+                tx_signature: common_table_fields.tx_signature.to_owned(),
+                program_id,
+                index: Some(event_records.len() as i64),
+                data: Some(data),
+                event_type: decoded_event
+                    .as_ref()
+                    .map(|decoded| decoded.event_type.to_owned()),
+                decoded_data: decoded_event
+                    .and_then(|decoded| decoded.fields)
+                    .map(|fields| fields.to_string()),
+            });
+        }
+    }
+
+    event_records
+}
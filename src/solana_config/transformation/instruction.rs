@@ -1,6 +1,9 @@
 use serde_json::Value;
 
-use crate::solana_config::proto_codegen::confirmed_block::InnerInstruction;
+use crate::solana_config::{
+    instruction_decoders::InstructionDecoderRegistry,
+    proto_codegen::confirmed_block::InnerInstruction,
+};
 
 #[cfg(feature = "STRING_TIMESTAMP")]
 use crate::solana_config::proto_codegen::records_string_timestamp::{
@@ -10,6 +13,8 @@ use crate::solana_config::proto_codegen::records_string_timestamp::{
 #[cfg(feature = "INT_TIMESTAMP")]
 use crate::solana_config::proto_codegen::records_int_timestamp::{InstructionRecord, ParamsRecord};
 
+use super::error::{TransformError, TransformErrorKind};
+use super::invocation::Invocation;
 use super::transaction::CommonTableFields;
 
 pub fn transform_to_instruction_record(
@@ -17,15 +22,35 @@ pub fn transform_to_instruction_record(
     instruction: &InnerInstruction,
     parent_index: Option<u32>,
     inner_index: usize,
-) -> InstructionRecord {
+    invocation: Option<&Invocation>,
+    instruction_decoders: &InstructionDecoderRegistry,
+) -> Result<InstructionRecord, TransformError> {
     let instruction_type: Option<String>;
     let instruction_parsed: Option<String>;
     let param_records: Vec<ParamsRecord>;
+    let mut program = instruction.program.to_owned();
     match &instruction.parsed_dict {
         None => {
-            instruction_type = None;
             instruction_parsed = instruction.parsed_string.to_owned();
-            param_records = Vec::new();
+            // the RPC node could not parse the instruction, fall back to a registered decoder
+            match instruction_decoders.decode(instruction) {
+                None => {
+                    instruction_type = None;
+                    param_records = Vec::new();
+                }
+                Some((program_name, decoded)) => {
+                    instruction_type = Some(decoded.instruction_type);
+                    param_records = decoded
+                        .params
+                        .into_iter()
+                        .map(|(key, value)| ParamsRecord {
+                            key: Some(key),
+                            value: Some(value.to_string()),
+                        })
+                        .collect();
+                    program = program.or_else(|| Some(program_name.to_string()));
+                }
+            }
         }
         Some(parsed) => {
             instruction_type = parsed.r#type.to_owned();
@@ -36,7 +61,8 @@ pub fn transform_to_instruction_record(
                     param_records = Vec::new();
                 }
                 Some(params) => {
-                    let params_deserialized: Value = serde_json::from_str(params).unwrap();
+                    let params_deserialized: Value = serde_json::from_str(params)
+                        .map_err(|e| TransformErrorKind::InvalidInstructionParams(e.to_string()))?;
                     param_records = match &params_deserialized {
                         Value::Object(o) => o
                             .iter()
@@ -45,13 +71,14 @@ pub fn transform_to_instruction_record(
                                 value: Some(value.to_string()),
                             })
                             .collect(),
-                        _ => panic!("instruction parameters are not a key-value pair"),
+                        _ => return Err(TransformErrorKind::InstructionParamsNotAnObject.into()),
                     };
                 }
             }
         }
     }
-    InstructionRecord {
+    let invocation_log = invocation.and_then(|invocation| invocation.log.as_ref());
+    Ok(InstructionRecord {
         block_slot: common_table_fields.block_slot,
         block_hash: common_table_fields.block_hash,
         block_timestamp: common_table_fields.block_timestamp,
@@ -61,9 +88,16 @@ pub fn transform_to_instruction_record(
         accounts: instruction.accounts.to_owned(),
         data: instruction.data.to_owned(),
         parsed: instruction_parsed,
-        program: instruction.program.to_owned(),
+        program,
         program_id: instruction.program_id.to_owned(),
         instruction_type: instruction_type.to_owned(),
         params: param_records,
-    }
+        stack_height: invocation.map(|invocation| invocation.stack_height as i64),
+        invocation_path: invocation.map(|invocation| invocation.invocation_path.to_owned()),
+        compute_units_consumed: invocation_log.and_then(|log| log.compute_units_consumed),
+        status: invocation_log
+            .and_then(|log| log.success)
+            .map(|success| String::from(if success { "Success" } else { "Failure" })),
+        err: invocation_log.and_then(|log| log.err.to_owned()),
+    })
 }
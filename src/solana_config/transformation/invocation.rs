@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::solana_config::proto_codegen::confirmed_block::{InnerInstruction, InnerInstructions};
+
+/// the stack height of a top-level instruction.
+const TOP_LEVEL_STACK_HEIGHT: u32 = 1;
+
+/// whether the word can be a base58-encoded pubkey, so that lines such as
+/// `Program log: success` aren't mistaken for the logs of a program.
+fn is_program_id(word: &str) -> bool {
+    (32..=44).contains(&word.len())
+        && word
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() && !matches!(b, b'0' | b'O' | b'I' | b'l'))
+}
+
+/// returns the program id and depth of an `Program <id> invoke [<depth>]` log line.
+pub(crate) fn invoked_program(log: &str) -> Option<(&str, Option<u32>)> {
+    let mut words = log.split(' ');
+    match (words.next(), words.next(), words.next()) {
+        (Some("Program"), Some(program_id), Some("invoke")) if is_program_id(program_id) => {
+            let depth = words
+                .next()
+                .and_then(|depth| depth.strip_prefix('['))
+                .and_then(|depth| depth.strip_suffix(']'))
+                .and_then(|depth| depth.parse().ok());
+            Some((program_id, depth))
+        }
+        _ => None,
+    }
+}
+
+/// whether the log line is the `Program <id> success` or `Program <id> failed: ...` that ends an invocation.
+pub(crate) fn is_invocation_end(log: &str) -> bool {
+    let mut words = log.split(' ');
+    matches!(
+        (words.next(), words.next(), words.next()),
+        (Some("Program"), Some(program_id), Some("success" | "failed:")) if is_program_id(program_id)
+    )
+}
+
+/// A single program invocation, as recorded in the transaction's log messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvocationLog {
+    pub program_id: String,
+    pub depth: Option<u32>,
+    /// from the `Program <id> consumed <n> of <m> compute units` line
+    pub compute_units_consumed: Option<u64>,
+    /// `None` if the invocation never ended in the logs, e.g. when the logs are truncated
+    pub success: Option<bool>,
+    /// the message of the `Program <id> failed: <err>` line
+    pub err: Option<String>,
+}
+
+/// parses the invocations of a transaction's log messages, in execution order.
+pub fn parse_invocation_logs(log_messages: &[String]) -> Vec<InvocationLog> {
+    let mut invocations: Vec<InvocationLog> = Vec::new();
+    // indices into invocations of the programs currently executing
+    let mut invoke_stack: Vec<usize> = Vec::new();
+
+    for log in log_messages {
+        if let Some((program_id, depth)) = invoked_program(log) {
+            invoke_stack.push(invocations.len());
+            invocations.push(InvocationLog {
+                program_id: program_id.to_string(),
+                depth,
+                compute_units_consumed: None,
+                success: None,
+                err: None,
+            });
+            continue;
+        }
+
+        let Some(current) = invoke_stack.last().map(|i| &mut invocations[*i]) else {
+            continue;
+        };
+        let Some(rest) = log
+            .strip_prefix("Program ")
+            .and_then(|rest| rest.strip_prefix(current.program_id.as_str()))
+        else {
+            continue;
+        };
+
+        if let Some(consumed) = rest.strip_prefix(" consumed ") {
+            current.compute_units_consumed = consumed
+                .split(' ')
+                .next()
+                .and_then(|units| units.parse().ok());
+        } else if rest == " success" {
+            current.success = Some(true);
+            invoke_stack.pop();
+        } else if let Some(err) = rest.strip_prefix(" failed: ") {
+            current.success = Some(false);
+            current.err = Some(err.to_string());
+            invoke_stack.pop();
+        }
+    }
+
+    invocations
+}
+
+/// Where an instruction sits in the cross-program invocation tree of its transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    pub stack_height: u32,
+    /// the path of the instruction from its top-level instruction, e.g. `2.0.1` is the second
+    /// instruction invoked by the first instruction invoked by top-level instruction 2.
+    pub invocation_path: String,
+    pub log: Option<InvocationLog>,
+}
+
+/// The cross-program invocation tree of a transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InvocationTree {
+    pub top_level: Vec<Invocation>,
+    /// the inner instructions, keyed by the index of their top-level instruction
+    pub inner: HashMap<u32, Vec<Invocation>>,
+}
+
+impl InvocationTree {
+    /// the invocation of the top-level instruction at index.
+    pub fn top_level(&self, index: usize) -> Option<&Invocation> {
+        self.top_level.get(index)
+    }
+
+    /// the invocation of the inner instruction at inner_index, invoked by the top-level
+    /// instruction at parent_index.
+    pub fn inner(&self, parent_index: u32, inner_index: usize) -> Option<&Invocation> {
+        self.inner
+            .get(&parent_index)
+            .and_then(|invocations| invocations.get(inner_index))
+    }
+}
+
+/// matches the next log invocation to an instruction, if the program and depth agree.  instructions
+/// that don't log their invocation (precompiles) or that come after truncated logs won't match.
+fn next_log<'a>(
+    logs: &mut std::iter::Peekable<impl Iterator<Item = &'a InvocationLog>>,
+    instruction: &InnerInstruction,
+    stack_height: Option<u32>,
+) -> Option<InvocationLog> {
+    logs.next_if(|log| {
+        instruction.program_id.as_deref() == Some(log.program_id.as_str())
+            && (stack_height.is_none() || log.depth.is_none() || stack_height == log.depth)
+    })
+    .cloned()
+}
+
+/// builds the invocation tree of a transaction from its instructions, inner instructions and logs.
+/// the stack height is taken from the inner instruction when the RPC node provides it
+/// (since solana v1.14.6), otherwise from the logs.
+pub fn build_invocation_tree(
+    instructions: &[InnerInstruction],
+    inner_instructions: &[InnerInstructions],
+    log_messages: &[String],
+) -> InvocationTree {
+    let invocation_logs = parse_invocation_logs(log_messages);
+    let mut logs = invocation_logs.iter().peekable();
+    let inner_by_parent: HashMap<u32, &InnerInstructions> = inner_instructions
+        .iter()
+        .map(|inner| (inner.index, inner))
+        .collect();
+
+    let mut tree = InvocationTree::default();
+    for (index, instruction) in instructions.iter().enumerate() {
+        tree.top_level.push(Invocation {
+            stack_height: TOP_LEVEL_STACK_HEIGHT,
+            invocation_path: index.to_string(),
+            log: next_log(&mut logs, instruction, Some(TOP_LEVEL_STACK_HEIGHT)),
+        });
+
+        let Some(inner) = inner_by_parent.get(&(index as u32)) else {
+            continue;
+        };
+        // path[i] is the position of the ancestor at stack height i + 1 among its siblings
+        let mut path = vec![index];
+        // the number of children seen so far at each stack height
+        let mut child_counts = vec![0];
+        let mut invocations = Vec::with_capacity(inner.instructions.len());
+        for inner_instruction in &inner.instructions {
+            let log = next_log(&mut logs, inner_instruction, inner_instruction.stack_height);
+            let stack_height = inner_instruction
+                .stack_height
+                .or_else(|| log.as_ref().and_then(|log| log.depth))
+                .unwrap_or(TOP_LEVEL_STACK_HEIGHT + 1)
+                .max(TOP_LEVEL_STACK_HEIGHT + 1);
+
+            // number of ancestors, capped at the depth we've seen so far
+            let ancestors = (stack_height as usize - 1).min(path.len());
+            path.truncate(ancestors);
+            child_counts.truncate(ancestors);
+            let position = child_counts[ancestors - 1];
+            child_counts[ancestors - 1] += 1;
+            path.push(position);
+            child_counts.push(0);
+
+            invocations.push(Invocation {
+                stack_height,
+                invocation_path: path
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<String>>()
+                    .join("."),
+                log,
+            });
+        }
+        tree.inner.insert(index as u32, invocations);
+    }
+
+    tree
+}
+
+/// returns the instructions of a transaction in execution order (each top-level instruction followed
+/// by the instructions it invoked), with their invocation path.
+pub fn executed_instructions<'a>(
+    instructions: &'a [InnerInstruction],
+    inner_instructions: &'a [InnerInstructions],
+    invocation_tree: &InvocationTree,
+) -> Vec<(Option<String>, &'a InnerInstruction)> {
+    let mut executed = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        executed.push((
+            invocation_tree
+                .top_level(index)
+                .map(|invocation| invocation.invocation_path.to_owned()),
+            instruction,
+        ));
+        for inner in inner_instructions
+            .iter()
+            .filter(|inner| inner.index as usize == index)
+        {
+            for (inner_index, inner_instruction) in inner.instructions.iter().enumerate() {
+                executed.push((
+                    invocation_tree
+                        .inner(inner.index, inner_index)
+                        .map(|invocation| invocation.invocation_path.to_owned()),
+                    inner_instruction,
+                ));
+            }
+        }
+    }
+    executed
+}
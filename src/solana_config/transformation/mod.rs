@@ -1,5 +1,11 @@
 pub mod account;
 pub mod block;
+pub mod compute_budget;
+pub mod error;
+pub mod event;
 pub mod instruction;
+pub mod invocation;
+pub mod native_transfer;
+pub mod stake_action;
 pub mod token_transfer;
 pub mod transaction;
@@ -0,0 +1,149 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use serde_json::Value;
+
+use crate::solana_config::proto_codegen::confirmed_block::{
+    CompiledAccount, InnerInstruction, TransactionStatusMeta,
+};
+
+#[cfg(feature = "STRING_TIMESTAMP")]
+use crate::solana_config::proto_codegen::records_string_timestamp::NativeTransferRecord;
+
+#[cfg(feature = "INT_TIMESTAMP")]
+use crate::solana_config::proto_codegen::records_int_timestamp::NativeTransferRecord;
+
+use super::invocation::{executed_instructions, InvocationTree};
+use super::transaction::CommonTableFields;
+
+const SYSTEM_PROGRAM: &str = "system";
+
+/// the transaction fee, paid by the fee payer.
+pub const FEE: &str = "fee";
+/// lamport movements seen in the pre and post balances that no instruction accounts for,
+/// e.g. programs debiting the accounts they own, or closing accounts.
+pub const UNATTRIBUTED: &str = "unattributed";
+
+/// returns the (source, destination) info keys of the system instructions that move lamports.
+fn system_transfer_keys(instruction_type: &str) -> Option<(&'static str, &'static str)> {
+    match instruction_type {
+        "transfer" | "transferWithSeed" => Some(("source", "destination")),
+        "createAccount" | "createAccountWithSeed" => Some(("source", "newAccount")),
+        "withdrawNonceAccount" => Some(("nonceAccount", "destination")),
+        _ => None,
+    }
+}
+
+/// returns the (kind, source, destination, lamports) of a system instruction that moves lamports.
+fn parse_system_transfer(instruction: &InnerInstruction) -> Option<(String, String, String, u64)> {
+    if instruction.program.as_deref() != Some(SYSTEM_PROGRAM) {
+        return None;
+    }
+    let parsed = instruction.parsed_dict.as_ref()?;
+    let instruction_type = parsed.r#type.as_deref()?;
+    let (source_key, destination_key) = system_transfer_keys(instruction_type)?;
+    let info: Value = serde_json::from_str(parsed.info.as_deref()?).ok()?;
+
+    Some((
+        instruction_type.to_string(),
+        info.get(source_key)?.as_str()?.to_string(),
+        info.get(destination_key)?.as_str()?.to_string(),
+        info.get("lamports")?.as_u64()?,
+    ))
+}
+
+/// extracts every native SOL movement of a transaction:
+///     - the transaction fee.
+///     - the system program transfers, account creations and nonce withdrawals, including those
+///       invoked by other programs.  these are rolled back when the transaction fails.
+///     - whatever remains of each account's balance change after the above, as `unattributed`.
+pub fn transform_to_native_transfer_records(
+    common_table_fields: CommonTableFields,
+    account_keys: &[CompiledAccount],
+    instructions: &[InnerInstruction],
+    meta: &TransactionStatusMeta,
+    invocation_tree: &InvocationTree,
+) -> Vec<NativeTransferRecord> {
+    let mut native_transfer_records = Vec::new();
+    // the lamports each account is expected to gain (or lose) from the movements recorded so far
+    let mut explained: HashMap<&str, i128> = HashMap::new();
+
+    let mut push_record = |source: Option<String>,
+                           destination: Option<String>,
+                           lamports: u64,
+                           kind: &str,
+                           instruction_path: Option<String>| {
+        native_transfer_records.push(NativeTransferRecord {
+            block_slot: common_table_fields.block_slot,
+            block_hash: common_table_fields.block_hash.to_owned(),
+            block_timestamp: common_table_fields.block_timestamp.to_owned(),
+            tx_signature: common_table_fields.tx_signature.to_owned(),
+            index: Some(native_transfer_records.len() as i64),
+            source,
+            destination,
+            lamports: Some(lamports),
+            kind: Some(kind.to_string()),
+            instruction_path,
+        });
+    };
+
+    if let Some(fee_payer) = account_keys.first() {
+        if meta.fee > 0 {
+            *explained.entry(fee_payer.pubkey.as_str()).or_default() -= meta.fee as i128;
+            push_record(Some(fee_payer.pubkey.to_owned()), None, meta.fee, FEE, None);
+        }
+    }
+
+    let executed = executed_instructions(instructions, &meta.inner_instructions, invocation_tree);
+
+    if meta.err.is_none() {
+        for (instruction_path, instruction) in executed {
+            let Some((kind, source, destination, lamports)) = parse_system_transfer(instruction)
+            else {
+                continue;
+            };
+            if let Some(account) = account_keys.iter().find(|a| a.pubkey == source) {
+                *explained.entry(account.pubkey.as_str()).or_default() -= lamports as i128;
+            }
+            if let Some(account) = account_keys.iter().find(|a| a.pubkey == destination) {
+                *explained.entry(account.pubkey.as_str()).or_default() += lamports as i128;
+            }
+            push_record(
+                Some(source),
+                Some(destination),
+                lamports,
+                &kind,
+                instruction_path,
+            );
+        }
+    }
+
+    for (i, account) in account_keys.iter().enumerate() {
+        let (Some(pre), Some(post)) = (meta.pre_balances.get(i), meta.post_balances.get(i)) else {
+            continue;
+        };
+        let residual = (*post as i128 - *pre as i128)
+            - explained
+                .get(account.pubkey.as_str())
+                .copied()
+                .unwrap_or_default();
+        match residual.cmp(&0) {
+            Ordering::Greater => push_record(
+                None,
+                Some(account.pubkey.to_owned()),
+                residual as u64,
+                UNATTRIBUTED,
+                None,
+            ),
+            Ordering::Less => push_record(
+                Some(account.pubkey.to_owned()),
+                None,
+                residual.unsigned_abs() as u64,
+                UNATTRIBUTED,
+                None,
+            ),
+            Ordering::Equal => {}
+        }
+    }
+
+    native_transfer_records
+}
@@ -0,0 +1,120 @@
+use serde_json::Value;
+
+use crate::solana_config::{
+    proto_codegen::confirmed_block::{InnerInstruction, TransactionStatusMeta},
+    stake::STAKE_PROGRAM,
+};
+
+#[cfg(feature = "STRING_TIMESTAMP")]
+use crate::solana_config::proto_codegen::records_string_timestamp::StakeActionRecord;
+
+#[cfg(feature = "INT_TIMESTAMP")]
+use crate::solana_config::proto_codegen::records_int_timestamp::StakeActionRecord;
+
+use super::invocation::{executed_instructions, InvocationTree};
+use super::transaction::CommonTableFields;
+
+/// the info keys of a stake instruction that hold the accounts of the stake action.
+struct StakeActionKeys {
+    /// the stake account the action applies to (and the lamports leave)
+    stake_account: &'static str,
+    /// where the lamports go, e.g. the new account of a split
+    destination: Option<&'static str>,
+    vote_account: Option<&'static str>,
+    authority: &'static str,
+}
+
+/// returns the info keys of the stake instructions that are recorded as stake actions.
+fn stake_action_keys(instruction_type: &str) -> Option<StakeActionKeys> {
+    let keys = match instruction_type {
+        "delegate" => StakeActionKeys {
+            stake_account: "stakeAccount",
+            destination: None,
+            vote_account: Some("voteAccount"),
+            authority: "stakeAuthority",
+        },
+        "deactivate" => StakeActionKeys {
+            stake_account: "stakeAccount",
+            destination: None,
+            vote_account: None,
+            authority: "stakeAuthority",
+        },
+        "split" => StakeActionKeys {
+            stake_account: "stakeAccount",
+            destination: Some("newSplitAccount"),
+            vote_account: None,
+            authority: "stakeAuthority",
+        },
+        "merge" => StakeActionKeys {
+            stake_account: "source",
+            destination: Some("destination"),
+            vote_account: None,
+            authority: "stakeAuthority",
+        },
+        "withdraw" => StakeActionKeys {
+            stake_account: "stakeAccount",
+            destination: Some("destination"),
+            vote_account: None,
+            authority: "withdrawAuthority",
+        },
+        _ => return None,
+    };
+    Some(keys)
+}
+
+fn info_string(info: &Value, key: &str) -> Option<String> {
+    info.get(key)
+        .and_then(Value::as_str)
+        .map(|value| value.to_string())
+}
+
+/// extracts the stake actions (delegate, deactivate, split, merge and withdraw) of a transaction,
+/// including those invoked by other programs, e.g. stake pools.  failed transactions have none.
+pub fn transform_to_stake_action_records(
+    common_table_fields: CommonTableFields,
+    instructions: &[InnerInstruction],
+    meta: &TransactionStatusMeta,
+    invocation_tree: &InvocationTree,
+) -> Vec<StakeActionRecord> {
+    let mut stake_action_records = Vec::new();
+    if meta.err.is_some() {
+        return stake_action_records;
+    }
+
+    for (instruction_path, instruction) in
+        executed_instructions(instructions, &meta.inner_instructions, invocation_tree)
+    {
+        if instruction.program.as_deref() != Some(STAKE_PROGRAM) {
+            continue;
+        }
+        let Some(parsed) = &instruction.parsed_dict else {
+            continue;
+        };
+        let Some(action) = parsed.r#type.as_deref() else {
+            continue;
+        };
+        let Some(keys) = stake_action_keys(action) else {
+            continue;
+        };
+        let info: Value = match parsed.info.as_deref().map(serde_json::from_str) {
+            Some(Ok(info)) => info,
+            _ => continue,
+        };
+
+        stake_action_records.push(StakeActionRecord {
+            block_slot: common_table_fields.block_slot,
+            block_hash: common_table_fields.block_hash.to_owned(),
+            block_timestamp: common_table_fields.block_timestamp.to_owned(),
+            tx_signature: common_table_fields.tx_signature.to_owned(),
+            instruction_path,
+            action: Some(action.to_string()),
+            stake_account: info_string(&info, keys.stake_account),
+            destination: keys.destination.and_then(|key| info_string(&info, key)),
+            vote_account: keys.vote_account.and_then(|key| info_string(&info, key)),
+            authority: info_string(&info, keys.authority),
+            lamports: info.get("lamports").and_then(Value::as_u64),
+        });
+    }
+
+    stake_action_records
+}
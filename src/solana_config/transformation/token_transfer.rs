@@ -1,5 +1,7 @@
+use std::collections::HashMap;
+
 use crate::solana_config::proto_codegen::confirmed_block::{
-    InnerInstruction, TokenTransferInstruction,
+    CompiledAccount, InnerInstruction, TokenBalance, TokenTransferInstruction,
 };
 
 #[cfg(feature = "STRING_TIMESTAMP")]
@@ -86,6 +88,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     TRANSFER_CHECKED => TokenTransferRecord {
                         block_slot,
@@ -103,6 +107,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     TRANSFER_CHECKED_WITH_FEE => TokenTransferRecord {
                         block_slot,
@@ -120,6 +126,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     BURN => TokenTransferRecord {
                         block_slot,
@@ -137,6 +145,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     BURN_CHECKED => TokenTransferRecord {
                         block_slot,
@@ -154,6 +164,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     MINT_TO => TokenTransferRecord {
                         block_slot,
@@ -171,6 +183,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     MINT_TO_CHECKED => TokenTransferRecord {
                         block_slot,
@@ -188,6 +202,8 @@ pub fn transform_to_token_transfer_record(
                         fee,
                         fee_decimals,
                         memo,
+                        source_owner: None,
+                        destination_owner: None,
                     },
                     _ => return None,
                 }
@@ -208,6 +224,8 @@ pub fn transform_to_token_transfer_record(
                     mint_authority: None,
                     transfer_type: Some(String::from(TRANSFER)),
                     memo,
+                    source_owner: None,
+                    destination_owner: None,
                 }
             } else {
                 return None;
@@ -217,3 +235,50 @@ pub fn transform_to_token_transfer_record(
         }
     }
 }
+
+/// maps the token accounts of a transaction to their balance, which holds the account's mint, decimals and owner.
+/// the pre-transaction balance is preferred, the post-transaction balance covers the accounts created by the transaction.
+pub fn token_accounts_from_balances(
+    account_keys: &[CompiledAccount],
+    pre_token_balances: &[TokenBalance],
+    post_token_balances: &[TokenBalance],
+) -> HashMap<String, TokenBalance> {
+    post_token_balances
+        .iter()
+        .chain(pre_token_balances)
+        .filter_map(|balance| {
+            account_keys
+                .get(balance.account_index as usize)
+                .map(|account| (account.pubkey.to_owned(), balance.to_owned()))
+        })
+        .collect()
+}
+
+/// fills in the mint, decimals and owners of a token transfer from the token accounts of its transaction,
+/// since instructions like the spl-token `transfer` don't carry them.
+pub fn enrich_token_transfer_record(
+    token_transfer_record: &mut TokenTransferRecord,
+    token_accounts: &HashMap<String, TokenBalance>,
+) {
+    let source = token_transfer_record
+        .source
+        .as_ref()
+        .and_then(|source| token_accounts.get(source));
+    let destination = token_transfer_record
+        .destination
+        .as_ref()
+        .and_then(|destination| token_accounts.get(destination));
+    let owner = |balance: &TokenBalance| Some(balance.owner.to_owned()).filter(|o| !o.is_empty());
+
+    if token_transfer_record.mint.is_none() {
+        token_transfer_record.mint = source.or(destination).map(|b| b.mint.to_owned());
+    }
+    if token_transfer_record.decimals.is_none() {
+        token_transfer_record.decimals = source
+            .or(destination)
+            .and_then(|b| b.ui_token_amount.as_ref())
+            .map(|ui_token_amount| ui_token_amount.decimals as u64);
+    }
+    token_transfer_record.source_owner = source.and_then(owner);
+    token_transfer_record.destination_owner = destination.and_then(owner);
+}
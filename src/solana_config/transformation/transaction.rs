@@ -1,21 +1,30 @@
-use crate::solana_config::proto_codegen::{confirmed_block::CompiledAccount, etl_block::EtlBlock};
+use crate::solana_config::{
+    instruction_decoders::InstructionDecoderRegistry,
+    proto_codegen::{
+        confirmed_block::{CompiledAccount, TransactionStatusMeta},
+        etl_block::EtlBlock,
+    },
+};
 
 #[cfg(feature = "STRING_TIMESTAMP")]
-use {
-    crate::solana_config::proto_codegen::records_string_timestamp::{
-        BalanceChangeRecord, InstructionRecord, TokenBalanceRecord, TokenTransferRecord,
-        TransactionAccountRecord, TransactionRecord,
-    },
-    chrono::{NaiveDateTime, TimeZone, Utc},
+use crate::solana_config::proto_codegen::records_string_timestamp::{
+    BalanceChangeRecord, EventRecord, InstructionRecord, NativeTransferRecord, StakeActionRecord,
+    TokenBalanceRecord, TokenTransferRecord, TransactionAccountRecord, TransactionRecord,
 };
 
 #[cfg(feature = "INT_TIMESTAMP")]
 use crate::solana_config::proto_codegen::records_int_timestamp::{
-    BalanceChangeRecord, InstructionRecord, TokenBalanceRecord, TokenTransferRecord,
-    TransactionAccountRecord, TransactionRecord,
+    BalanceChangeRecord, EventRecord, InstructionRecord, NativeTransferRecord, StakeActionRecord,
+    TokenBalanceRecord, TokenTransferRecord, TransactionAccountRecord, TransactionRecord,
 };
 
-use super::{instruction, token_transfer};
+use super::{
+    compute_budget::{self, ComputeBudget},
+    error::{record_timestamp, TransformError, TransformErrorKind},
+    event, instruction,
+    invocation::{self, InvocationTree},
+    native_transfer, stake_action, token_transfer,
+};
 
 #[derive(Clone)]
 pub struct CommonTableFields {
@@ -28,41 +37,112 @@ pub struct CommonTableFields {
     pub tx_signature: Option<String>,
 }
 
+/// the source of the accounts in the message's static account keys.
+const STATIC_ACCOUNT_SOURCE: &str = "Transaction";
+
+/// the source of the accounts loaded from address lookup tables.
+const LOOKUP_TABLE_ACCOUNT_SOURCE: &str = "LookupTable";
+
+/// returns all of the accounts of a transaction in the order the balances index them: the static
+/// account keys, followed by the writable and then the readonly addresses loaded from lookup tables.
+/// with the jsonParsed encoding, the loaded addresses are already among the account keys, so they
+/// are only appended when they are missing.
+fn transaction_accounts(
+    account_keys: &[CompiledAccount],
+    meta: Option<&TransactionStatusMeta>,
+) -> Vec<CompiledAccount> {
+    let mut accounts: Vec<CompiledAccount> = account_keys
+        .iter()
+        .map(|account| CompiledAccount {
+            source: match account.source.as_str() {
+                "" => String::from(STATIC_ACCOUNT_SOURCE),
+                source => source.to_owned(),
+            },
+            ..account.to_owned()
+        })
+        .collect();
+
+    let has_loaded_addresses = accounts
+        .iter()
+        .any(|account| account.source == LOOKUP_TABLE_ACCOUNT_SOURCE);
+    if let (Some(meta), false) = (meta, has_loaded_addresses) {
+        let loaded_writable = meta
+            .loaded_writable_addresses
+            .iter()
+            .map(|pubkey| (pubkey, true));
+        let loaded_readonly = meta
+            .loaded_readonly_addresses
+            .iter()
+            .map(|pubkey| (pubkey, false));
+        accounts.extend(
+            loaded_writable
+                .chain(loaded_readonly)
+                .map(|(pubkey, writable)| CompiledAccount {
+                    pubkey: pubkey.to_owned(),
+                    signer: false,
+                    source: String::from(LOOKUP_TABLE_ACCOUNT_SOURCE),
+                    writable,
+                }),
+        );
+    }
+
+    accounts
+}
+
+#[allow(clippy::type_complexity)]
 pub fn transform_to_transaction_records(
     etl_block: &EtlBlock,
-) -> (
-    Vec<TransactionRecord>,
-    Vec<InstructionRecord>,
-    Vec<TokenTransferRecord>,
-) {
+    instruction_decoders: &InstructionDecoderRegistry,
+) -> Result<
+    (
+        Vec<TransactionRecord>,
+        Vec<InstructionRecord>,
+        Vec<TokenTransferRecord>,
+        Vec<EventRecord>,
+        Vec<NativeTransferRecord>,
+        Vec<StakeActionRecord>,
+    ),
+    TransformError,
+> {
     let mut transaction_records = Vec::with_capacity(etl_block.transactions.len());
     let mut instruction_records = Vec::new();
     let mut token_transfer_records = Vec::new();
+    let mut event_records = Vec::new();
+    let mut native_transfer_records = Vec::new();
+    let mut stake_action_records = Vec::new();
 
-    let table_context = etl_block.table_context.to_owned().unwrap();
+    let at_slot = |kind| TransformError::from(kind).at_slot(etl_block.slot);
+    let table_context = etl_block
+        .table_context
+        .as_ref()
+        .ok_or(TransformErrorKind::MissingField("etl_block.table_context"))
+        .map_err(at_slot)?;
     let block_slot = Some(etl_block.slot as i64);
     let block_hash = Some(table_context.block_hash.to_owned());
-
-    #[cfg(feature = "STRING_TIMESTAMP")]
-    let block_timestamp = table_context.block_timestamp.as_ref().map(|bt| {
-        Utc.from_utc_datetime(&NaiveDateTime::from_timestamp_opt(bt.timestamp, 0).unwrap())
-            .to_rfc3339()
-    });
-    #[cfg(feature = "INT_TIMESTAMP")]
     let block_timestamp = table_context
         .block_timestamp
-        .to_owned()
-        .map(|bt| bt.timestamp * 1_000_000);
+        .as_ref()
+        .map(|bt| record_timestamp(bt.timestamp))
+        .transpose()
+        .map_err(at_slot)?;
 
     for (tx_index, tx_with_meta) in etl_block.transactions.iter().enumerate() {
+        let in_transaction = |error: TransformError| -> TransformError {
+            error.at_transaction(tx_index).at_slot(etl_block.slot)
+        };
+        let tx_token_transfers_start = token_transfer_records.len();
         let recent_block_hash: Option<String>;
         let tx_lead_signature: Option<String>;
         let transaction_account_records: Vec<TransactionAccountRecord>;
         let tx_accounts: Vec<CompiledAccount>;
         let common_table_fields: CommonTableFields;
+        let compute_budget: Option<ComputeBudget>;
+        let base_fee: Option<u64>;
+        let mut invocation_tree = InvocationTree::default();
         match &tx_with_meta.transaction {
             Some(tx) => {
                 tx_lead_signature = tx.signatures.get(0).cloned();
+                base_fee = Some(compute_budget::base_fee(tx.signatures.len()));
                 common_table_fields = CommonTableFields {
                     block_slot,
                     block_hash: block_hash.to_owned(),
@@ -74,16 +154,31 @@ pub fn transform_to_transaction_records(
                         recent_block_hash = None;
                         tx_accounts = Vec::new();
                         transaction_account_records = Vec::new();
+                        compute_budget = None;
                     }
                     Some(tx_message) => {
                         recent_block_hash = Some(tx_message.recent_blockhash.to_owned());
-                        tx_accounts = tx_message.account_keys.to_owned();
+                        if let Some(meta) = &tx_with_meta.meta {
+                            invocation_tree = invocation::build_invocation_tree(
+                                &tx_message.instructions,
+                                &meta.inner_instructions,
+                                &meta.log_messages,
+                            );
+                        }
+                        compute_budget = Some(compute_budget::parse_compute_budget(
+                            &tx_message.instructions,
+                        ));
+                        tx_accounts = transaction_accounts(
+                            &tx_message.account_keys,
+                            tx_with_meta.meta.as_ref(),
+                        );
                         transaction_account_records = tx_accounts
                             .iter()
                             .map(|k| TransactionAccountRecord {
                                 pubkey: Some(k.pubkey.to_owned()),
                                 signer: Some(k.signer),
                                 writable: Some(k.writable),
+                                source: Some(k.source.to_owned()),
                             })
                             .collect();
 
@@ -98,7 +193,12 @@ pub fn transform_to_transaction_records(
                                 instruction,
                                 None,
                                 instruction_index,
-                            );
+                                invocation_tree.top_level(instruction_index),
+                                instruction_decoders,
+                            )
+                            .map_err(|error| {
+                                in_transaction(error.at_instruction(instruction_index))
+                            })?;
 
                             instruction_records.push(instruction_record);
 
@@ -133,6 +233,8 @@ pub fn transform_to_transaction_records(
                 tx_accounts = Vec::new();
                 transaction_account_records = Vec::new();
                 tx_lead_signature = None;
+                compute_budget = None;
+                base_fee = None;
                 common_table_fields = CommonTableFields {
                     block_slot,
                     block_hash: block_hash.to_owned(),
@@ -144,13 +246,38 @@ pub fn transform_to_transaction_records(
 
         match &tx_with_meta.meta {
             Some(meta) => {
+                let token_accounts = token_transfer::token_accounts_from_balances(
+                    &tx_accounts,
+                    &meta.pre_token_balances,
+                    &meta.post_token_balances,
+                );
+                let instructions = tx_with_meta
+                    .transaction
+                    .as_ref()
+                    .and_then(|tx| tx.message.as_ref())
+                    .map_or(&[][..], |message| message.instructions.as_slice());
+                native_transfer_records.append(
+                    &mut native_transfer::transform_to_native_transfer_records(
+                        common_table_fields.to_owned(),
+                        &tx_accounts,
+                        instructions,
+                        meta,
+                        &invocation_tree,
+                    ),
+                );
+                stake_action_records.append(&mut stake_action::transform_to_stake_action_records(
+                    common_table_fields.to_owned(),
+                    instructions,
+                    meta,
+                    &invocation_tree,
+                ));
                 let balance_changes: Vec<BalanceChangeRecord> = tx_accounts
                     .into_iter()
                     .enumerate()
                     .map(|(i, account)| BalanceChangeRecord {
                         account: Some(account.pubkey),
-                        before: Some(meta.pre_balances[i]),
-                        after: Some(meta.post_balances[i]),
+                        before: meta.pre_balances.get(i).copied(),
+                        after: meta.post_balances.get(i).copied(),
                     })
                     .collect();
                 let pre_token_balances = meta
@@ -208,9 +335,33 @@ pub fn transform_to_transaction_records(
                     balance_changes,
                     pre_token_balances,
                     post_token_balances,
+                    compute_unit_limit: compute_budget
+                        .as_ref()
+                        .and_then(|cb| cb.compute_unit_limit.map(u64::from)),
+                    compute_unit_price: compute_budget
+                        .as_ref()
+                        .and_then(|cb| cb.compute_unit_price),
+                    heap_frame_bytes: compute_budget
+                        .as_ref()
+                        .and_then(|cb| cb.heap_frame_bytes.map(u64::from)),
+                    priority_fee: compute_budget.as_ref().map(|cb| cb.priority_fee),
+                    base_fee,
+                    version: tx_with_meta.version.to_owned(),
+                    error_type: err.as_ref().map(|e| e.error_type.to_owned()),
+                    failed_instruction_index: err.as_ref().and_then(|e| e.failed_instruction_index),
+                    instruction_error_type: err
+                        .as_ref()
+                        .and_then(|e| e.instruction_error_type.to_owned()),
+                    custom_error_code: err.as_ref().and_then(|e| e.custom_error_code),
                 };
                 transaction_records.push(transaction_record);
 
+                event_records.append(&mut event::transform_to_event_records(
+                    common_table_fields.to_owned(),
+                    &meta.log_messages,
+                    instruction_decoders,
+                ));
+
                 for inner_instructions in &meta.inner_instructions {
                     let parent_index = inner_instructions.index;
                     // this will be updated at the end of each iteration to be the current inner_instruction
@@ -225,7 +376,14 @@ pub fn transform_to_transaction_records(
                             inner_instruction,
                             Some(parent_index),
                             inner_index,
-                        );
+                            invocation_tree.inner(parent_index, inner_index),
+                            instruction_decoders,
+                        )
+                        .map_err(|error| {
+                            in_transaction(
+                                error.at_inner_instruction(parent_index as usize, inner_index),
+                            )
+                        })?;
                         instruction_records.push(instruction_record);
 
                         // token transfers
@@ -250,6 +408,14 @@ pub fn transform_to_transaction_records(
                         prev_inner_instruction = Some(inner_instruction.to_owned());
                     }
                 }
+
+                for token_transfer_record in &mut token_transfer_records[tx_token_transfers_start..]
+                {
+                    token_transfer::enrich_token_transfer_record(
+                        token_transfer_record,
+                        &token_accounts,
+                    );
+                }
             }
             None => {
                 let transaction_record = TransactionRecord {
@@ -268,15 +434,34 @@ pub fn transform_to_transaction_records(
                     balance_changes: Vec::new(),
                     pre_token_balances: Vec::new(),
                     post_token_balances: Vec::new(),
+                    compute_unit_limit: compute_budget
+                        .as_ref()
+                        .and_then(|cb| cb.compute_unit_limit.map(u64::from)),
+                    compute_unit_price: compute_budget
+                        .as_ref()
+                        .and_then(|cb| cb.compute_unit_price),
+                    heap_frame_bytes: compute_budget
+                        .as_ref()
+                        .and_then(|cb| cb.heap_frame_bytes.map(u64::from)),
+                    priority_fee: compute_budget.as_ref().map(|cb| cb.priority_fee),
+                    base_fee,
+                    version: tx_with_meta.version.to_owned(),
+                    error_type: None,
+                    failed_instruction_index: None,
+                    instruction_error_type: None,
+                    custom_error_code: None,
                 };
                 transaction_records.push(transaction_record);
             }
         }
     }
 
-    (
+    Ok((
         transaction_records,
         instruction_records,
         token_transfer_records,
-    )
+        event_records,
+        native_transfer_records,
+        stake_action_records,
+    ))
 }
@@ -34,9 +34,12 @@ pub struct AccountValueResponse {
 #[derive(serde::Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct ContextInfoResponse {
-    pub slot: i32,
+    /// The slot at which the request was evaluated.  NOTE: this is the current state of the
+    /// account(s), which may be well past the slot of the block being indexed.
+    pub slot: u64,
     /// The API Version used (can be `"legacy"`, a number, or undefined)
-    apiVersion: String,
+    #[serde(default)]
+    apiVersion: Option<serde_json::Value>,
 }
 
 /// Struct for token amounts that are returned. Contains unprocessed amount as string w/o proper decimal point.
@@ -152,6 +155,162 @@ pub struct AccountInfoInfoStructureResponse {
     pub leaderScheduleEpoch: Option<i64>,
     pub slot: Option<i64>, // NOTE: this is separate from rootSlot.
     pub unixTimestamp: Option<i64>,
+    /// The [Token-2022 extensions](https://spl.solana.com/token-2022/extensions) of a mint or token account.
+    pub extensions: Option<Vec<AccountInfoExtensionResponse>>,
+    /// The authorities and lockup of a stake account.
+    pub meta: Option<StakeMetaResponse>,
+    /// The delegation of a stake account, only present once the stake is delegated.
+    pub stake: Option<StakeStakeResponse>,
+    /// The authority of a durable nonce, address lookup table or upgradeable program data account.
+    pub authority: Option<String>,
+    /// The durable nonce of a nonce account.
+    pub blockhash: Option<String>,
+    /// The fee schedule of a nonce account or the fees sysvar.
+    pub feeCalculator: Option<FeeCalculatorResponse>,
+    /// The slot an address lookup table was deactivated at, u64::MAX if it is active (string representation of u64)
+    pub deactivationSlot: Option<String>,
+    /// The slot an address lookup table was last extended at (string representation of u64)
+    pub lastExtendedSlot: Option<String>,
+    pub lastExtendedSlotStartIndex: Option<u32>,
+    /// The addresses of an address lookup table.
+    pub addresses: Option<Vec<String>>,
+    // epoch schedule sysvar
+    pub slotsPerEpoch: Option<u64>,
+    pub leaderScheduleSlotOffset: Option<u64>,
+    pub warmup: Option<bool>,
+    pub firstNormalEpoch: Option<u64>,
+    pub firstNormalSlot: Option<u64>,
+    // rent sysvar
+    /// string representation of u64
+    pub lamportsPerByteYear: Option<String>,
+    pub exemptionThreshold: Option<f64>,
+    pub burnPercent: Option<u32>,
+    // rewards sysvar
+    pub validatorPointValue: Option<f64>,
+    // slot history sysvar
+    pub nextSlot: Option<u64>,
+    // last restart slot sysvar
+    pub lastRestartSlot: Option<u64>,
+}
+
+/// The authorities and lockup of a [stake account](https://docs.solana.com/staking/stake-accounts).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct StakeMetaResponse {
+    /// The lamports kept in the account to keep it rent exempt (string representation of u64)
+    pub rentExemptReserve: String,
+    pub authorized: StakeAuthorizedResponse,
+    pub lockup: StakeLockupResponse,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct StakeAuthorizedResponse {
+    /// The authority that may delegate, deactivate, split and merge the stake
+    pub staker: String,
+    /// The authority that may withdraw from the account
+    pub withdrawer: String,
+}
+
+/// Withdrawals are locked until both the timestamp and epoch are reached, unless signed by the custodian.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct StakeLockupResponse {
+    pub unixTimestamp: i64,
+    pub epoch: u64,
+    pub custodian: String,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct StakeStakeResponse {
+    pub delegation: StakeDelegationResponse,
+    /// The vote account credits observed when the rewards were last paid out
+    pub creditsObserved: u64,
+}
+
+/// The delegation of a stake account.  The amounts and epochs are string representations of u64,
+/// where an epoch of u64::MAX means "never".
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct StakeDelegationResponse {
+    /// The vote account the stake is delegated to
+    pub voter: String,
+    pub stake: String,
+    pub activationEpoch: String,
+    pub deactivationEpoch: String,
+    pub warmupCooldownRate: f64,
+}
+
+/// A Token-2022 extension as returned by the jsonParsed encoding.  The shape of the state depends
+/// on the extension, and is decoded into one of the `Extension*Response` structs below.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct AccountInfoExtensionResponse {
+    /// The name of the extension (e.g. `"transferFeeConfig"`)
+    pub extension: String,
+    /// The state of the extension.  Missing for extensions without any state (e.g. `"nonTransferable"`)
+    pub state: Option<serde_json::Value>,
+}
+
+/// A transfer fee, which applies from its epoch onwards.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionTransferFeeResponse {
+    pub epoch: u64,
+    pub maximumFee: u64,
+    pub transferFeeBasisPoints: u32,
+}
+
+/// State of the `transferFeeConfig` mint extension.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionTransferFeeConfigResponse {
+    pub transferFeeConfigAuthority: Option<String>,
+    pub withdrawWithheldAuthority: Option<String>,
+    pub withheldAmount: u64,
+    pub olderTransferFee: ExtensionTransferFeeResponse,
+    pub newerTransferFee: ExtensionTransferFeeResponse,
+}
+
+/// State of the `transferFeeAmount` token account extension.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionTransferFeeAmountResponse {
+    pub withheldAmount: u64,
+}
+
+/// State of the `metadataPointer` mint extension.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionMetadataPointerResponse {
+    pub authority: Option<String>,
+    pub metadataAddress: Option<String>,
+}
+
+/// State of the `tokenMetadata` mint extension (on-chain token metadata).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionTokenMetadataResponse {
+    pub updateAuthority: Option<String>,
+    pub mint: String,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// key-value pairs of additional metadata
+    pub additionalMetadata: Vec<(String, String)>,
+}
+
+/// State of the `interestBearingConfig` mint extension.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionInterestBearingConfigResponse {
+    pub rateAuthority: Option<String>,
+    pub initializationTimestamp: i64,
+    pub preUpdateAverageRate: i32,
+    pub lastUpdateTimestamp: i64,
+    pub currentRate: i32,
+}
+
+/// State of the `permanentDelegate` mint extension.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionPermanentDelegateResponse {
+    pub delegate: Option<String>,
+}
+
+/// State of the `transferHook` mint extension.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct ExtensionTransferHookResponse {
+    pub authority: Option<String>,
+    pub programId: Option<String>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
@@ -1,5 +1,10 @@
 use crate::solana_config::types::request_types::ResponseError;
-use solana_transaction_status::UiConfirmedBlock;
+use serde::Deserialize as _;
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, EncodedTransaction, EncodedTransactionWithStatusMeta,
+    Rewards, TransactionVersion, UiConfirmedBlock, UiInnerInstructions, UiLoadedAddresses,
+    UiTransactionReturnData, UiTransactionStatusMeta, UiTransactionTokenBalance,
+};
 use thiserror::Error;
 
 /// Response for [getBlock](https://docs.solana.com/api/http#getblock).  Will either contain an error
@@ -9,10 +14,175 @@ use thiserror::Error;
 pub struct BlockResponse {
     jsonrpc: String,
     pub error: Option<ResponseError>,
-    pub result: Option<UiConfirmedBlock>,
+    pub result: Option<BlockWithTransactionErrors>,
     id: i32,
 }
 
+/// A block along with the error of each of its transactions.  The errors are deserialized through
+/// [TransactionErrorResponse] rather than the solana sdk, so that an error variant added by a newer
+/// validator doesn't fail the whole block.
+#[derive(Debug, Clone)]
+pub struct BlockWithTransactionErrors {
+    /// The block, where every transaction's status is `Ok`.  Use `transaction_errors` instead.
+    pub block: UiConfirmedBlock,
+    /// The error of each transaction, in the order of the block's transactions.  `None` if the
+    /// transaction succeeded.
+    pub transaction_errors: Vec<Option<TransactionErrorResponse>>,
+}
+
+impl<'de> serde::Deserialize<'de> for BlockWithTransactionErrors {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let block = UiConfirmedBlockResponse::deserialize(deserializer)?;
+        let (transactions, transaction_errors) = match block.transactions {
+            Some(transactions) => {
+                let (transactions, transaction_errors): (Vec<_>, Vec<_>) = transactions
+                    .into_iter()
+                    .map(TransactionWithErrorResponse::split_error)
+                    .unzip();
+                (Some(transactions), transaction_errors)
+            }
+            None => (None, Vec::new()),
+        };
+        Ok(BlockWithTransactionErrors {
+            block: UiConfirmedBlock {
+                previous_blockhash: block.previous_blockhash,
+                blockhash: block.blockhash,
+                parent_slot: block.parent_slot,
+                transactions,
+                signatures: block.signatures,
+                rewards: block.rewards,
+                block_time: block.block_time,
+                block_height: block.block_height,
+            },
+            transaction_errors,
+        })
+    }
+}
+
+/// [UiConfirmedBlock], with transactions whose errors are deserialized as [TransactionErrorResponse].
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiConfirmedBlockResponse {
+    previous_blockhash: String,
+    blockhash: String,
+    parent_slot: u64,
+    #[serde(default)]
+    transactions: Option<Vec<TransactionWithErrorResponse>>,
+    #[serde(default)]
+    signatures: Option<Vec<String>>,
+    #[serde(default)]
+    rewards: Option<Rewards>,
+    block_time: Option<i64>,
+    block_height: Option<u64>,
+}
+
+/// [EncodedTransactionWithStatusMeta], with the error deserialized as [TransactionErrorResponse].
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionWithErrorResponse {
+    transaction: EncodedTransaction,
+    meta: Option<TransactionStatusMetaResponse>,
+    #[serde(default)]
+    version: Option<TransactionVersion>,
+}
+
+impl TransactionWithErrorResponse {
+    /// takes the error out of the transaction, leaving a successful status in its metadata.
+    fn split_error(
+        self,
+    ) -> (
+        EncodedTransactionWithStatusMeta,
+        Option<TransactionErrorResponse>,
+    ) {
+        let (meta, err) = match self.meta {
+            Some(meta) => {
+                let (meta, err) = meta.split_error();
+                (Some(meta), err)
+            }
+            None => (None, None),
+        };
+        let transaction = EncodedTransactionWithStatusMeta {
+            transaction: self.transaction,
+            meta,
+            version: self.version,
+        };
+        (transaction, err)
+    }
+}
+
+/// [UiTransactionStatusMeta], with the error deserialized as [TransactionErrorResponse].  The
+/// `status` repeats the error, so it is skipped.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionStatusMetaResponse {
+    err: Option<TransactionErrorResponse>,
+    fee: u64,
+    pre_balances: Vec<u64>,
+    post_balances: Vec<u64>,
+    #[serde(default = "OptionSerializer::none")]
+    inner_instructions: OptionSerializer<Vec<UiInnerInstructions>>,
+    #[serde(default = "OptionSerializer::none")]
+    log_messages: OptionSerializer<Vec<String>>,
+    #[serde(default = "OptionSerializer::none")]
+    pre_token_balances: OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    #[serde(default = "OptionSerializer::none")]
+    post_token_balances: OptionSerializer<Vec<UiTransactionTokenBalance>>,
+    #[serde(default = "OptionSerializer::none")]
+    rewards: OptionSerializer<Rewards>,
+    #[serde(default = "OptionSerializer::skip")]
+    loaded_addresses: OptionSerializer<UiLoadedAddresses>,
+    #[serde(default = "OptionSerializer::skip")]
+    return_data: OptionSerializer<UiTransactionReturnData>,
+    #[serde(default = "OptionSerializer::skip")]
+    compute_units_consumed: OptionSerializer<u64>,
+}
+
+impl TransactionStatusMetaResponse {
+    /// takes the error out of the metadata, leaving a successful status that the solana sdk types
+    /// can always hold.
+    fn split_error(self) -> (UiTransactionStatusMeta, Option<TransactionErrorResponse>) {
+        let meta = UiTransactionStatusMeta {
+            err: None,
+            status: Ok(()),
+            fee: self.fee,
+            pre_balances: self.pre_balances,
+            post_balances: self.post_balances,
+            inner_instructions: self.inner_instructions,
+            log_messages: self.log_messages,
+            pre_token_balances: self.pre_token_balances,
+            post_token_balances: self.post_token_balances,
+            rewards: self.rewards,
+            loaded_addresses: self.loaded_addresses,
+            return_data: self.return_data,
+            compute_units_consumed: self.compute_units_consumed,
+        };
+        (meta, self.err)
+    }
+}
+
+impl From<UiConfirmedBlock> for BlockWithTransactionErrors {
+    /// takes the errors of a block that was already deserialized by the solana sdk, e.g. when
+    /// it comes from bigtable.
+    fn from(block: UiConfirmedBlock) -> Self {
+        let transaction_errors = block
+            .transactions
+            .iter()
+            .flatten()
+            .map(|transaction| {
+                transaction
+                    .meta
+                    .as_ref()
+                    .and_then(|meta| meta.status.clone().err())
+                    .map(TransactionErrorResponse::from)
+            })
+            .collect();
+        BlockWithTransactionErrors {
+            block,
+            transaction_errors,
+        }
+    }
+}
+
 /// this is used for parsing the json response from calling the rpc node's [getBlocks()](https://docs.solana.com/api/http#getblock) function.
 #[derive(serde::Deserialize, Clone, Debug)]
 #[allow(dead_code)]
@@ -416,6 +586,128 @@ pub enum TransactionErrorSolana {
     InvalidLoadedAccountsDataSizeLimit,
 }
 
+/// A transaction error as returned by the rpc node.  Falls back to the generic [Unknown](TransactionErrorResponse::Unknown)
+/// bucket for variants added by newer validators, so that they don't fail deserialization.
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum TransactionErrorResponse {
+    Known(TransactionErrorSolana),
+    /// an instruction error whose inner error is not known
+    UnknownInstructionError {
+        #[serde(rename = "InstructionError")]
+        instruction_error: (u8, serde_json::Value),
+    },
+    Unknown(serde_json::Value),
+}
+
+/// The name used for errors that aren't known to the indexer.
+pub const UNKNOWN_ERROR_TYPE: &str = "Unknown";
+
+/// returns the name of an externally tagged enum variant, either `"Variant"` or `{"Variant": ...}`.
+fn variant_name(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(name) => Some(name.to_owned()),
+        serde_json::Value::Object(map) if map.len() == 1 => map.keys().next().cloned(),
+        _ => None,
+    }
+}
+
+impl From<solana_sdk::transaction::TransactionError> for TransactionErrorResponse {
+    fn from(err: solana_sdk::transaction::TransactionError) -> Self {
+        serde_json::to_value(&err)
+            .and_then(serde_json::from_value)
+            .unwrap_or_else(|_| TransactionErrorResponse::Unknown(err.to_string().into()))
+    }
+}
+
+impl TransactionErrorResponse {
+    /// The error as understood by the solana sdk, if the sdk knows the variant.
+    fn sdk_error(&self) -> Option<solana_sdk::transaction::TransactionError> {
+        serde_json::to_value(self)
+            .and_then(serde_json::from_value)
+            .ok()
+    }
+
+    /// The human readable error message, as the solana sdk would display it when it knows the
+    /// variant, otherwise the raw JSON of the error.
+    pub fn message(&self) -> String {
+        match (self.sdk_error(), self) {
+            (Some(err), _) => err.to_string(),
+            (None, TransactionErrorResponse::Known(err)) => err.to_string(),
+            (None, unknown) => {
+                serde_json::to_string(unknown).unwrap_or_else(|_| UNKNOWN_ERROR_TYPE.to_string())
+            }
+        }
+    }
+
+    /// The name of the transaction error, e.g. `InstructionError` or `AccountInUse`.  Variants
+    /// known to neither the indexer nor the solana sdk are named [UNKNOWN_ERROR_TYPE].
+    pub fn error_type(&self) -> String {
+        match self {
+            TransactionErrorResponse::Known(err) => serde_json::to_value(err)
+                .ok()
+                .as_ref()
+                .and_then(variant_name)
+                .unwrap_or_else(|| UNKNOWN_ERROR_TYPE.to_string()),
+            TransactionErrorResponse::UnknownInstructionError { .. } => {
+                String::from("InstructionError")
+            }
+            TransactionErrorResponse::Unknown(value) => self
+                .sdk_error()
+                .and_then(|_| variant_name(value))
+                .unwrap_or_else(|| UNKNOWN_ERROR_TYPE.to_string()),
+        }
+    }
+
+    /// The index of the instruction that failed, if the transaction failed on an instruction.
+    pub fn failed_instruction_index(&self) -> Option<u8> {
+        match self {
+            TransactionErrorResponse::Known(TransactionErrorSolana::InstructionError(index, _)) => {
+                Some(*index)
+            }
+            TransactionErrorResponse::UnknownInstructionError {
+                instruction_error: (index, _),
+            } => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// The name of the instruction error, e.g. `Custom` or `InvalidAccountData`.
+    pub fn instruction_error_type(&self) -> Option<String> {
+        match self {
+            TransactionErrorResponse::Known(TransactionErrorSolana::InstructionError(_, err)) => {
+                Some(
+                    serde_json::to_value(err)
+                        .ok()
+                        .as_ref()
+                        .and_then(variant_name)
+                        .unwrap_or_else(|| UNKNOWN_ERROR_TYPE.to_string()),
+                )
+            }
+            TransactionErrorResponse::UnknownInstructionError {
+                instruction_error: (_, err),
+            } => Some(
+                serde_json::from_value::<solana_sdk::instruction::InstructionError>(err.clone())
+                    .ok()
+                    .and_then(|_| variant_name(err))
+                    .unwrap_or_else(|| UNKNOWN_ERROR_TYPE.to_string()),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The program-specific error code of a `Custom` instruction error.
+    pub fn custom_error_code(&self) -> Option<u32> {
+        match self {
+            TransactionErrorResponse::Known(TransactionErrorSolana::InstructionError(
+                _,
+                InstructionError::Custom(code),
+            )) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
 /// Solana VersionResponse, since it can be a string, "legacy", or a number starting at 0.
 #[allow(non_camel_case_types)]
 #[derive(serde::Deserialize, Clone, Debug)]
@@ -120,7 +120,7 @@ impl BlocksRequest {
 /// Optional parameters for [getAccounts](https://docs.solana.com/api/http#getmultipleaccounts)
 ///
 /// Notes:
-/// - Did not include dataSlice and minContextSlot despite being optional arguments.
+/// - Did not include dataSlice despite being an optional argument.
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 #[allow(dead_code)]
 pub struct OptionalAccountParams {
@@ -133,8 +133,9 @@ pub struct OptionalAccountParams {
     /// [commitment](https://docs.solana.com/terminology#commitment) levels are available in the
     /// [Configuring State Commitment documentation](https://docs.solana.com/api/http#configuring-state-commitment).
     commitment: String,
-    // / The minimum slot that the request can be evaluated at
-    // minContextSlot: u32,
+    /// The minimum slot that the request can be evaluated at.  Omitted from the request when `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minContextSlot: Option<u64>,
 }
 
 /// The request structure for [getMultipleAccounts](https://docs.solana.com/api/http#getmultipleaccounts)
@@ -148,11 +149,13 @@ pub struct AccountsRequest {
 }
 
 impl AccountsRequest {
-    /// Creates an Accounts Requests given an array of pubkeys (up to 100)
-    pub fn new(account: Vec<String>) -> Self {
+    /// Creates an Accounts Requests given an array of pubkeys (up to 100), and optionally the
+    /// minimum slot that the request can be evaluated at.
+    pub fn new(account: Vec<String>, min_context_slot: Option<u64>) -> Self {
         let opt = OptionalAccountParams {
             encoding: String::from("jsonParsed"),
             commitment: String::from("finalized"),
+            minContextSlot: min_context_slot,
         };
         Self {
             jsonrpc: String::from("2.0"),
@@ -1,11 +1,43 @@
 //! Defines a RequestConfig Enum which represents the data necessary to be passed on for requests
+use reqwest::header::HeaderMap;
+
+use crate::{config::Config, request};
+
+/// The default number of attempts at a request before switching to the fallback endpoint.
+const DEFAULT_FALLBACK_THRESHOLD: u32 = 2;
+
+/// The RPC node that requests switch to when the endpoint keeps failing.
+#[derive(Debug, Clone)]
+pub struct RpcFallback {
+    /// the address of the fallback RPC node
+    pub endpoint: String,
+    /// the number of attempts at a request before switching to the fallback endpoint
+    pub threshold: u32,
+    /// the headers sent with the requests to the fallback endpoint
+    pub headers: HeaderMap,
+}
+
+impl RpcFallback {
+    /// Reads the `FALLBACK_ENDPOINT` and `RPC_FALLBACK_THRESHOLD` settings, returning `None`
+    /// without a fallback endpoint.
+    pub fn from_config(config: &Config) -> Option<RpcFallback> {
+        Some(RpcFallback {
+            endpoint: config.fallback_endpoint.clone()?,
+            threshold: config
+                .rpc_fallback_threshold
+                .unwrap_or(DEFAULT_FALLBACK_THRESHOLD),
+            headers: request::get_headers(config.gcp_api_key.as_deref()),
+        })
+    }
+}
 
 /// An enum that should only contain one item, contains what is required to be
 /// sent to the configuration
 pub enum RequestConfig {
-    /// Request Builder for the Solana Configuration
+    /// Request Builder for the Solana Configuration, along with the fallback RPC node, if there
+    /// is one
     #[cfg(feature = "SOLANA")]
-    ReqBldr(reqwest::RequestBuilder),
+    ReqBldr(reqwest::RequestBuilder, Option<RpcFallback>),
 }
 
 impl RequestConfig {
@@ -13,9 +45,9 @@ impl RequestConfig {
     pub fn try_clone(&self) -> Option<Self> {
         match self {
             #[cfg(feature = "SOLANA")]
-            RequestConfig::ReqBldr(req_builder) => {
-                req_builder.try_clone().map(RequestConfig::ReqBldr)
-            }
+            RequestConfig::ReqBldr(req_builder, fallback) => req_builder
+                .try_clone()
+                .map(|req_builder| RequestConfig::ReqBldr(req_builder, fallback.clone())),
         }
     }
 
@@ -23,7 +55,15 @@ impl RequestConfig {
     pub fn to_requestbuilder(&self) -> reqwest::RequestBuilder {
         match self {
             #[cfg(feature = "SOLANA")]
-            RequestConfig::ReqBldr(reqbldr) => reqbldr.try_clone().unwrap(),
+            RequestConfig::ReqBldr(reqbldr, _) => reqbldr.try_clone().unwrap(),
+        }
+    }
+
+    /// Returns the fallback RPC node, if there is one
+    pub fn fallback(&self) -> Option<&RpcFallback> {
+        match self {
+            #[cfg(feature = "SOLANA")]
+            RequestConfig::ReqBldr(_, fallback) => fallback.as_ref(),
         }
     }
 }
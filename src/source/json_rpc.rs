@@ -1,5 +1,5 @@
-use super::config::RequestConfig;
-use crate::{constants, metrics::Metrics, request};
+use super::config::{RequestConfig, RpcFallback};
+use crate::{constants, metrics::Metrics};
 use log::{error, info, warn};
 use std::time;
 use tokio::time::{sleep, timeout, Duration};
@@ -11,31 +11,29 @@ use crate::solana_config::{
 };
 
 /// Creates a new request builder using the fallback endpoint.
-pub async fn use_fallback_endpoint(request: String, endpoint: String) -> reqwest::RequestBuilder {
+pub async fn use_fallback_endpoint(
+    request: String,
+    fallback: &RpcFallback,
+) -> reqwest::RequestBuilder {
     let fallback_client = reqwest::Client::new();
-    let headers = request::get_headers();
-    let fallback_endpoint = endpoint.parse::<String>().unwrap();
     fallback_client
-        .post(fallback_endpoint)
+        .post(&fallback.endpoint)
         .body(request.clone())
-        .headers(headers)
+        .headers(fallback.headers.clone())
 }
 
 /// calls the rpc method in a loop with delay to account for potential disconnections.
 /// `RPC_METHOD_TIMEOUT` can be added to the .env file to adjust the timeout, otherwise it is 5 seconds.
-/// `RPC_FALLBACK_THRESHOLD` can be added to the .env file to adjust how many attempts until we try
-///     to utilize the fallback server.
+/// `RPC_FALLBACK_THRESHOLD` can be set to adjust how many attempts until we try to utilize the
+///     fallback server (`FALLBACK_ENDPOINT`), carried by the request config.
 #[allow(non_snake_case)]
 pub async fn call_rpc_method(
     request_config: RequestConfig,
     request: String,
     metrics: Option<Metrics>,
 ) -> reqwest::Response {
-    // Get the fallback threshold (how many attempts until we use fallback)
-    let fallback_threshold = dotenvy::var("RPC_FALLBACK_THRESHOLD")
-        .unwrap_or_else(|_| "2".to_string())
-        .parse()
-        .unwrap_or(2);
+    // Get the fallback (and how many attempts until we use it)
+    let fallback = request_config.fallback().cloned();
 
     let response_timeout = Duration::from_secs(constants::RESPONSE_TIMEOUT);
 
@@ -47,12 +45,10 @@ pub async fn call_rpc_method(
     // Attempt to connect to the server
     let mut i = 1;
     loop {
-        // after enough failures, switch to the fallback endpoint, if there is one in the environment variables.
-        if i == fallback_threshold {
-            if let Ok(fb) = dotenvy::var("FALLBACK_ENDPOINT") {
-                info!("Switching to the fallback endpoint");
-                cur_request_builder = use_fallback_endpoint(request.clone(), fb).await;
-            }
+        // after enough failures, switch to the fallback endpoint, if there is one in the config.
+        if let Some(fallback) = fallback.as_ref().filter(|fallback| i == fallback.threshold) {
+            info!("Switching to the fallback endpoint");
+            cur_request_builder = use_fallback_endpoint(request.clone(), fallback).await;
         }
 
         // Update request count metrics
@@ -1,7 +1,7 @@
 import subprocess
 
 # List of arguments
-args = ["blocks", "block_rewards", "transactions", "instructions", "tokens", "token_transfers", "accounts"]
+args = ["blocks", "block_rewards", "transactions", "instructions", "tokens", "token_transfers", "accounts", "events", "native_transfers", "stake_actions"]
 
 # Loop through each argument and run the command
 for arg in args:
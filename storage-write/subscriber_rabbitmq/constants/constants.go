@@ -1,13 +1,16 @@
 package constants
 
 const (
-	Consumer             = "bq_inserter"
-	BlockTableID         = "Blocks"
-	BlockRewardsTableID  = "Block Rewards"
-	TransactionTableID   = "Transactions"
-	InstructionTableID   = "Instructions"
-	TokenTransferTableID = "Token Transfers"
-	TokenTableID         = "Tokens"
-	AccountTableID       = "Accounts"
+	Consumer              = "bq_inserter"
+	BlockTableID          = "Blocks"
+	BlockRewardsTableID   = "Block Rewards"
+	TransactionTableID    = "Transactions"
+	InstructionTableID    = "Instructions"
+	TokenTransferTableID  = "Token Transfers"
+	TokenTableID          = "Tokens"
+	AccountTableID        = "Accounts"
+	EventTableID          = "Events"
+	NativeTransferTableID = "Native Transfers"
+	StakeActionTableID    = "Stake Actions"
 
 )
@@ -83,6 +83,9 @@ func main() {
 	var tokenRecordsChan chan [1]*pbcodegen.TokenRecord
 	var tokenTransferRecordsChan chan [1]*pbcodegen.TokenTransferRecord
 	var accountRecordsChan chan [1]*pbcodegen.AccountRecord
+	var eventRecordsChan chan [1]*pbcodegen.EventRecord
+	var nativeTransferRecordsChan chan [1]*pbcodegen.NativeTransferRecord
+	var stakeActionRecordsChan chan [1]*pbcodegen.StakeActionRecord
 
 	// some setup for the write client
 	writeClient, err := managedwriter.NewClient(ctx, ProjectID)
@@ -149,9 +152,33 @@ func main() {
 		}
 		accountRecordsChan = make(chan [1]*pbcodegen.AccountRecord, constants.BatchSize)
 		go insertion.StartPipeline(ctx, writeClient, constants.AccountTableID, descriptorProto, accountRecordsChan)
+	case "events":
+		recordInterface := &pbcodegen.EventRecord{}
+		descriptorProto, err := adapt.NormalizeDescriptor(recordInterface.ProtoReflect().Descriptor())
+		if err != nil {
+			fmt.Println("NormalizeDescriptor:", err)
+		}
+		eventRecordsChan = make(chan [1]*pbcodegen.EventRecord, constants.BatchSize)
+		go insertion.StartPipeline(ctx, writeClient, constants.EventTableID, descriptorProto, eventRecordsChan)
+	case "native_transfers":
+		recordInterface := &pbcodegen.NativeTransferRecord{}
+		descriptorProto, err := adapt.NormalizeDescriptor(recordInterface.ProtoReflect().Descriptor())
+		if err != nil {
+			fmt.Println("NormalizeDescriptor:", err)
+		}
+		nativeTransferRecordsChan = make(chan [1]*pbcodegen.NativeTransferRecord, constants.BatchSize)
+		go insertion.StartPipeline(ctx, writeClient, constants.NativeTransferTableID, descriptorProto, nativeTransferRecordsChan)
+	case "stake_actions":
+		recordInterface := &pbcodegen.StakeActionRecord{}
+		descriptorProto, err := adapt.NormalizeDescriptor(recordInterface.ProtoReflect().Descriptor())
+		if err != nil {
+			fmt.Println("NormalizeDescriptor:", err)
+		}
+		stakeActionRecordsChan = make(chan [1]*pbcodegen.StakeActionRecord, constants.BatchSize)
+		go insertion.StartPipeline(ctx, writeClient, constants.StakeActionTableID, descriptorProto, stakeActionRecordsChan)
 	default:
 		fmt.Println("Unknown record type:", os.Args[1])
-		panic("Please provide one of the following: blocks, block_rewards, transactions, instructions, token_transfers, tokens, accounts")
+		panic("Please provide one of the following: blocks, block_rewards, transactions, instructions, token_transfers, tokens, accounts, events, native_transfers, stake_actions")
 	}
 	// deserialize the raw data from rabbitmq and spawn transformer threads.
 	// the transformed records are then sent by the worker threads to an internal queue for insertion
@@ -236,9 +263,39 @@ func main() {
 
 				record := [1]*pbcodegen.AccountRecord{&recordData}
 				accountRecordsChan <- record
+			case "events":
+				fmt.Println("Starting the event record inserter...")
+				var recordData pbcodegen.EventRecord
+				if err := proto.Unmarshal(d.Body, &recordData); err != nil {
+					fmt.Println("proto.Unmarshal err:", err)
+					panic("")
+				}
+
+				record := [1]*pbcodegen.EventRecord{&recordData}
+				eventRecordsChan <- record
+			case "native_transfers":
+				fmt.Println("Starting the native transfer record inserter...")
+				var recordData pbcodegen.NativeTransferRecord
+				if err := proto.Unmarshal(d.Body, &recordData); err != nil {
+					fmt.Println("proto.Unmarshal err:", err)
+					panic("")
+				}
+
+				record := [1]*pbcodegen.NativeTransferRecord{&recordData}
+				nativeTransferRecordsChan <- record
+			case "stake_actions":
+				fmt.Println("Starting the stake action record inserter...")
+				var recordData pbcodegen.StakeActionRecord
+				if err := proto.Unmarshal(d.Body, &recordData); err != nil {
+					fmt.Println("proto.Unmarshal err:", err)
+					panic("")
+				}
+
+				record := [1]*pbcodegen.StakeActionRecord{&recordData}
+				stakeActionRecordsChan <- record
 			default:
 				fmt.Println("Unknown record type:", os.Args[1])
-				panic("Please provide one of the following: blocks, block_rewards, transactions, instructions, token_transfers, tokens, accounts")
+				panic("Please provide one of the following: blocks, block_rewards, transactions, instructions, token_transfers, tokens, accounts, events, native_transfers, stake_actions")
 			}
 
 			fmt.Println("Finished processing the message, ending the thread.")